@@ -0,0 +1,151 @@
+use chrono::NaiveDateTime;
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+
+use crate::{
+    config::AnomalyConfig,
+    models::{
+        anomaly_policy,
+        sensor::{self, SensorType},
+        sensor_history::{self, AnomalyKind},
+    },
+    notifier,
+};
+
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    pub detail: String,
+    // Pembacaan sebelumnya sudah ditandai dengan jenis yang sama (notifikasi tidak diulang)
+    pub ongoing: bool,
+}
+
+// Perubahan per menit yang masih masuk akal untuk tiap tipe sensor (None = tidak dibatasi)
+fn max_rate_per_minute(sensor_type: &SensorType) -> Option<f64> {
+    match sensor_type {
+        SensorType::Temperature => Some(2.0),
+        SensorType::Humidity => Some(10.0),
+        SensorType::SoilMoisture => Some(5.0),
+        SensorType::PH => Some(0.5),
+        // Intensitas cahaya bisa berubah seketika karena awan
+        SensorType::LightIntensity => None,
+        // Sensor virtual tidak menerima pembacaan langsung
+        SensorType::Vpd | SensorType::DewPoint | SensorType::GrowingDegreeDays => None,
+    }
+}
+
+// Batas flatline untuk sensor ini (0 = tidak dicek). anomaly_policy paling spesifik menang:
+// land + tipe > land > tipe > default config (hanya untuk tipe di `flatline_types`).
+async fn flatline_readings(db: &DatabaseConnection, config: &AnomalyConfig, s: &sensor::Model) -> Result<usize, DbErr> {
+    let policies = anomaly_policy::Entity::find()
+        .filter(
+            Condition::any()
+                .add(anomaly_policy::Column::LandId.eq(s.land_id))
+                .add(anomaly_policy::Column::LandId.is_null()),
+        )
+        .all(db)
+        .await?;
+    let policy = policies
+        .iter()
+        .filter(|p| p.sensor_type.as_ref().is_none_or(|t| *t == s.sensor_type))
+        .max_by_key(|p| (p.land_id.is_some(), p.sensor_type.is_some()));
+    Ok(match policy {
+        Some(p) => p.flatline_readings.max(0) as usize,
+        None if config.flatline_types.contains(&s.sensor_type) => config.flatline_readings,
+        None => 0,
+    })
+}
+
+fn check(
+    config: &AnomalyConfig,
+    flat: usize,
+    s: &sensor::Model,
+    recent: &[sensor_history::Model],
+    value: f64,
+    at: NaiveDateTime,
+) -> Option<(AnomalyKind, String)> {
+    let prev = recent.first()?;
+
+    // Selisih waktu di bawah satu menit dihitung satu menit agar noise pembacaan rapat tidak ikut ditandai
+    if let Some(limit) = max_rate_per_minute(&s.sensor_type) {
+        let minutes = (at - prev.recorded_at).num_seconds().max(60) as f64 / 60.0;
+        let rate = (value - prev.value).abs() / minutes;
+        if rate > limit {
+            return Some((
+                AnomalyKind::RateOfChange,
+                format!("Changed {:.2} per minute (limit {:.2})", rate, limit),
+            ));
+        }
+    }
+
+    // Pembanding ikut memuat pembacaan yang sudah ditandai, sehingga pergeseran nilai yang
+    // memang nyata (mis. probe dipindah) berhenti dianggap anomali setelah beberapa pembacaan
+    let window = &recent[..recent.len().min(config.window as usize)];
+    if window.len() >= config.min_samples {
+        let n = window.len() as f64;
+        let mean = window.iter().map(|h| h.value).sum::<f64>() / n;
+        let std = (window.iter().map(|h| (h.value - mean).powi(2)).sum::<f64>() / n).sqrt();
+        if std > f64::EPSILON {
+            let z = (value - mean).abs() / std;
+            if z > config.z_threshold {
+                return Some((
+                    AnomalyKind::ZScore,
+                    format!("z-score {:.1} against mean {:.2} (threshold {:.1})", z, mean, config.z_threshold),
+                ));
+            }
+        }
+    }
+
+    if flat > 1
+        && recent.len() >= flat - 1
+        && recent[..flat - 1].iter().all(|h| (h.value - value).abs() < f64::EPSILON)
+    {
+        return Some((
+            AnomalyKind::Flatline,
+            format!("Same value {} for {} consecutive readings", value, flat),
+        ));
+    }
+
+    None
+}
+
+// Nilai pembacaan baru terhadap statistik pembacaan terakhir sensor tersebut
+pub async fn score(
+    db: &DatabaseConnection,
+    config: &AnomalyConfig,
+    s: &sensor::Model,
+    value: f64,
+    at: NaiveDateTime,
+) -> Result<Option<Anomaly>, DbErr> {
+    let flat = flatline_readings(db, config, s).await?;
+    let limit = config.window.max(flat.saturating_sub(1) as u64);
+    let recent = sensor_history::Entity::find()
+        .filter(sensor_history::Column::SensorId.eq(s.id))
+        .filter(sensor_history::Column::RecordedAt.lte(at))
+        .order_by_desc(sensor_history::Column::RecordedAt)
+        .order_by_desc(sensor_history::Column::Id)
+        .limit(limit)
+        .all(db)
+        .await?;
+
+    Ok(check(config, flat, s, &recent, value, at).map(|(kind, detail)| Anomaly {
+        ongoing: recent.first().is_some_and(|h| h.anomaly == Some(kind)),
+        kind,
+        detail,
+    }))
+}
+
+// Kabari pemilik land sekali di awal setiap rangkaian anomali
+pub async fn notify(db: &DatabaseConnection, s: &sensor::Model, value: f64, anomaly: &Anomaly) -> Result<(), DbErr> {
+    if anomaly.ongoing {
+        return Ok(());
+    }
+    notifier::notify_land_owner(
+        db,
+        s.land_id,
+        format!(
+            "Sensor \"{}\" reported a suspicious reading ({}): {}. Automations ignored this reading.",
+            s.name, value, anomaly.detail
+        ),
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use chrono::{Duration, Local};
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    extractors::AuthUser,
+    models::audit_log::{self, AuditAction},
+};
+
+// Snapshot entitas dalam bentuk JSON (field yang di-skip serde, mis. hash, ikut tidak tersimpan)
+pub fn snapshot<T: Serialize>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+// Field yang berubah antara dua snapshot: { "field": { "from": .., "to": .. } }
+fn diff(before: &Value, after: &Value) -> Value {
+    let (Value::Object(b), Value::Object(a)) = (before, after) else {
+        return json!({ "value": { "from": before, "to": after } });
+    };
+
+    let mut changes = Map::new();
+    for key in b.keys().chain(a.keys()) {
+        let from = b.get(key).unwrap_or(&Value::Null);
+        let to = a.get(key).unwrap_or(&Value::Null);
+        if from != to && !changes.contains_key(key) {
+            changes.insert(key.clone(), json!({ "from": from, "to": to }));
+        }
+    }
+    Value::Object(changes)
+}
+
+// Simpan satu entri audit. Perubahan data sudah tersimpan saat fungsi ini dipanggil,
+// jadi kegagalan menulis audit hanya di-log dan tidak menggagalkan request.
+#[allow(clippy::too_many_arguments)]
+pub async fn record(
+    db: &DatabaseConnection,
+    actor_id: Option<i32>,
+    ip: Option<&str>,
+    action: AuditAction,
+    entity_type: &str,
+    entity_id: i32,
+    before: Option<Value>,
+    after: Option<Value>,
+) {
+    let changes = match (&before, &after) {
+        (Some(b), Some(a)) => Some(diff(b, a)),
+        _ => None,
+    };
+    // Update yang tidak mengubah apa pun tidak perlu dicatat
+    if let Some(Value::Object(c)) = &changes
+        && c.is_empty()
+    {
+        return;
+    }
+
+    let entry = audit_log::ActiveModel {
+        actor_id: Set(actor_id),
+        action: Set(action),
+        entity_type: Set(entity_type.to_string()),
+        entity_id: Set(entity_id),
+        before: Set(before),
+        after: Set(after),
+        changes: Set(changes),
+        ip: Set(ip.map(str::to_string)),
+        created_at: Set(Local::now().naive_local()),
+        ..Default::default()
+    };
+    if let Err(e) = entry.insert(db).await {
+        eprintln!("Failed to write audit log for {} {}: {:?}", entity_type, entity_id, e);
+    }
+}
+
+pub async fn created<T: Serialize>(db: &DatabaseConnection, auth: &AuthUser, entity_type: &str, entity_id: i32, after: &T) {
+    record(db, Some(auth.id), Some(&auth.ip), AuditAction::Create, entity_type, entity_id, None, Some(snapshot(after))).await
+}
+
+pub async fn updated<T: Serialize>(
+    db: &DatabaseConnection,
+    auth: &AuthUser,
+    entity_type: &str,
+    entity_id: i32,
+    before: &T,
+    after: &T,
+) {
+    record(
+        db,
+        Some(auth.id),
+        Some(&auth.ip),
+        AuditAction::Update,
+        entity_type,
+        entity_id,
+        Some(snapshot(before)),
+        Some(snapshot(after)),
+    )
+    .await
+}
+
+pub async fn deleted<T: Serialize>(db: &DatabaseConnection, auth: &AuthUser, entity_type: &str, entity_id: i32, before: &T) {
+    record(db, Some(auth.id), Some(&auth.ip), AuditAction::Delete, entity_type, entity_id, Some(snapshot(before)), None).await
+}
+
+// Hapus entri audit yang lebih tua dari masa retensi
+pub async fn purge_older_than(db: &DatabaseConnection, days: i64) -> Result<u64, DbErr> {
+    let cutoff = Local::now().naive_local() - Duration::days(days);
+    let res = audit_log::Entity::delete_many()
+        .filter(audit_log::Column::CreatedAt.lt(cutoff))
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected)
+}
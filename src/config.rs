@@ -0,0 +1,147 @@
+use std::str::FromStr;
+
+use sea_orm::ActiveEnum;
+use shuttle_runtime::SecretStore;
+
+use crate::models::sensor::SensorType;
+
+// Ambil nilai dari Secrets.toml, pakai default jika kosong / tidak valid
+fn secret_or<T: FromStr>(secrets: &SecretStore, key: &str, default: T) -> T {
+    secrets
+        .get(key)
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+#[derive(Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+}
+
+const MIN_TOKEN_SECRET_LENGTH: usize = 32;
+
+#[derive(Clone)]
+pub struct SecurityConfig {
+    pub password: PasswordPolicy,
+    // Lockout akun setelah sejumlah login gagal berturut-turut
+    pub max_failed_logins: i32,
+    pub lockout_minutes: i64,
+    // Throttling /login per IP dan per username
+    pub login_max_attempts: usize,
+    pub login_window_seconds: u64,
+    // Bearer token: access token pendek bertanda tangan HMAC + refresh token di database
+    pub token_secret: Vec<u8>,
+    pub access_token_ttl_seconds: i64,
+    pub refresh_token_ttl_days: i64,
+    pub password_reset_ttl_minutes: i64,
+}
+
+impl SecurityConfig {
+    pub fn from_secrets(secrets: &SecretStore) -> Self {
+        // Kunci HMAC access token wajib diisi sendiri per deployment; tanpa itu siapa pun
+        // bisa membuat token untuk user mana saja, jadi service tidak boleh jalan
+        let token_secret = secrets
+            .get("TOKEN_SECRET")
+            .filter(|s| s.trim().len() >= MIN_TOKEN_SECRET_LENGTH)
+            .unwrap_or_else(|| panic!("TOKEN_SECRET must be set to at least {} characters", MIN_TOKEN_SECRET_LENGTH));
+
+        Self {
+            password: PasswordPolicy {
+                min_length: secret_or(secrets, "PASSWORD_MIN_LENGTH", 8),
+                require_uppercase: secret_or(secrets, "PASSWORD_REQUIRE_UPPERCASE", true),
+                require_lowercase: secret_or(secrets, "PASSWORD_REQUIRE_LOWERCASE", true),
+                require_digit: secret_or(secrets, "PASSWORD_REQUIRE_DIGIT", true),
+                require_symbol: secret_or(secrets, "PASSWORD_REQUIRE_SYMBOL", false),
+            },
+            max_failed_logins: secret_or(secrets, "LOGIN_MAX_FAILED", 5),
+            lockout_minutes: secret_or(secrets, "LOGIN_LOCKOUT_MINUTES", 15),
+            login_max_attempts: secret_or(secrets, "LOGIN_RATE_LIMIT", 10),
+            login_window_seconds: secret_or(secrets, "LOGIN_RATE_WINDOW_SECONDS", 60),
+            token_secret: token_secret.into_bytes(),
+            access_token_ttl_seconds: secret_or(secrets, "ACCESS_TOKEN_TTL_SECONDS", 900),
+            refresh_token_ttl_days: secret_or(secrets, "REFRESH_TOKEN_TTL_DAYS", 30),
+            password_reset_ttl_minutes: secret_or(secrets, "PASSWORD_RESET_TTL_MINUTES", 30),
+        }
+    }
+}
+
+// Berapa lama data lama disimpan sebelum dibersihkan job latar belakang (0 = simpan selamanya)
+#[derive(Clone)]
+pub struct RetentionConfig {
+    pub audit_log_days: i64,
+    // Land, sensor, tanaman, pompa, katup & automation yang di-soft delete
+    pub soft_delete_days: i64,
+    // Default riwayat sensor; bisa di-override per land / tipe sensor lewat retention_policy
+    pub sensor_raw_days: i32,
+    pub sensor_hourly_days: i32,
+    // Observasi & forecast cuaca (dihitung dari jam berlakunya)
+    pub weather_days: i64,
+    // Interval job pembersihan
+    pub purge_interval_minutes: u64,
+}
+
+impl RetentionConfig {
+    pub fn from_secrets(secrets: &SecretStore) -> Self {
+        Self {
+            audit_log_days: secret_or(secrets, "AUDIT_RETENTION_DAYS", 365),
+            soft_delete_days: secret_or(secrets, "SOFT_DELETE_RETENTION_DAYS", 30),
+            sensor_raw_days: secret_or(secrets, "SENSOR_RAW_RETENTION_DAYS", 30),
+            sensor_hourly_days: secret_or(secrets, "SENSOR_HOURLY_RETENTION_DAYS", 365),
+            weather_days: secret_or(secrets, "WEATHER_RETENTION_DAYS", 90),
+            purge_interval_minutes: secret_or(secrets, "RETENTION_PURGE_INTERVAL_MINUTES", 60),
+        }
+    }
+}
+
+// Deteksi anomali pembacaan sensor saat ingest
+#[derive(Clone)]
+pub struct AnomalyConfig {
+    // Jumlah pembacaan terakhir yang dipakai sebagai pembanding
+    pub window: u64,
+    // Z-score baru dihitung jika pembandingnya sudah sebanyak ini
+    pub min_samples: usize,
+    pub z_threshold: f64,
+    // Pembacaan identik berturut-turut yang dianggap probe macet (0 = nonaktif).
+    // Bisa di-override per land / tipe sensor lewat anomaly_policy.
+    pub flatline_readings: usize,
+    // Tipe sensor yang dicek flatline jika tidak ada anomaly_policy yang cocok. Cahaya tidak
+    // termasuk secara default karena nilai 0 sepanjang malam memang wajar.
+    pub flatline_types: Vec<SensorType>,
+}
+
+impl AnomalyConfig {
+    pub fn from_secrets(secrets: &SecretStore) -> Self {
+        Self {
+            window: secret_or(secrets, "ANOMALY_WINDOW", 30),
+            min_samples: secret_or(secrets, "ANOMALY_MIN_SAMPLES", 10),
+            z_threshold: secret_or(secrets, "ANOMALY_Z_THRESHOLD", 4.0),
+            flatline_readings: secret_or(secrets, "ANOMALY_FLATLINE_READINGS", 20),
+            // Nama tipe dipisah koma, mis. "Temperature,SoilMoisture"; nama yang tidak dikenal diabaikan
+            flatline_types: match secrets.get("ANOMALY_FLATLINE_TYPES") {
+                Some(list) => list
+                    .split(',')
+                    .filter_map(|name| SensorType::try_from_value(&name.trim().to_string()).ok())
+                    .collect(),
+                None => vec![SensorType::Temperature, SensorType::Humidity, SensorType::SoilMoisture, SensorType::PH],
+            },
+        }
+    }
+}
+
+// Pengambilan data cuaca dari provider (lihat `weather::from_secrets`)
+#[derive(Clone)]
+pub struct WeatherConfig {
+    pub poll_minutes: u64,
+}
+
+impl WeatherConfig {
+    pub fn from_secrets(secrets: &SecretStore) -> Self {
+        Self {
+            poll_minutes: secret_or(secrets, "WEATHER_POLL_MINUTES", 30),
+        }
+    }
+}
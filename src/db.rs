@@ -1,12 +1,18 @@
-use sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbErr, Schema, Statement};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, Database, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    RelationDef, RelationTrait, Schema, Set, Statement,
+    sea_query::{Expr, ForeignKeyAction, ForeignKeyCreateStatement, SimpleExpr, Table},
+};
 
-use crate::models::{land, sensor, user, user_role, sensor_history, plant, valve, notification, pump, automation, automation_history, seed, recommendation, pest_control};
+use crate::models::{audit_log, land, zone, sensor, derived_sensor, user, user_role, permission, role_permission, user_session, organization, organization_member, refresh_token, password_reset_token, sensor_history, sensor_history_hourly, sensor_history_daily, retention_policy, anomaly_policy, import_job, plant, harvest, valve, notification, pump, automation, automation_history, seed, recommendation, pest_control, task, task_comment, weather};
 
 pub async fn init_db(database_url: &str) -> Result<DatabaseConnection, DbErr> {
     Database::connect(database_url).await
 }
 
-pub async fn setup_tables(db: &DatabaseConnection) -> Result<(), DbErr> {
+// `reset` = true menghapus semua tabel dulu (RESET_DB, hanya untuk development).
+// Tanpa reset, data lama (termasuk import job yang terputus & riwayat sensor) tetap ada.
+pub async fn setup_tables(db: &DatabaseConnection, reset: bool) -> Result<(), DbErr> {
     let builder = db.get_database_backend();
     let schema = Schema::new(builder);
 
@@ -14,30 +20,8 @@ pub async fn setup_tables(db: &DatabaseConnection) -> Result<(), DbErr> {
     // 1. DROP TABLES (Raw SQL agar lebih pasti)
     // Menggunakan CASCADE agar relasi otomatis terputus
     // ====================================================
-    
-    // List tabel yang akan dihapus
-    let tables = [
-        "notification", // <--- Tambahkan ini (sebelum user dihapus)
-        "automation_history",
-        "automation",
-        "sensor_history",
-        "sensor",
-        "plant",
-        "valve",
-        "pump",
-        "land",
-        "seed",
-        "recommendation",
-        "pest_control", 
-        "user",
-        "user_role"
-    ];
-
-    for table in tables {
-        // Query: DROP TABLE IF EXISTS "nama_tabel" CASCADE;
-        let sql = format!("DROP TABLE IF EXISTS \"{}\" CASCADE;", table);
-        let stmt = Statement::from_string(builder, sql);
-        db.execute(stmt).await?;
+    if reset {
+        drop_tables(db).await?;
     }
 
     // ====================================================
@@ -46,20 +30,61 @@ pub async fn setup_tables(db: &DatabaseConnection) -> Result<(), DbErr> {
 
     // Role & User
     db.execute(builder.build(schema.create_table_from_entity(user_role::Entity).if_not_exists())).await?;
+    db.execute(builder.build(schema.create_table_from_entity(permission::Entity).if_not_exists())).await?;
+    db.execute(builder.build(schema.create_table_from_entity(role_permission::Entity).if_not_exists())).await?;
     db.execute(builder.build(schema.create_table_from_entity(user::Entity).if_not_exists())).await?;
+    db.execute(builder.build(schema.create_table_from_entity(user_session::Entity).if_not_exists())).await?;
+    db.execute(builder.build(schema.create_table_from_entity(refresh_token::Entity).if_not_exists())).await?;
+    db.execute(builder.build(schema.create_table_from_entity(password_reset_token::Entity).if_not_exists())).await?;
 
     // Seed (HARUS DIBUAT SEBELUM PLANT)
     db.execute(builder.build(schema.create_table_from_entity(seed::Entity).if_not_exists())).await?;
 
+    // Organization (HARUS DIBUAT SEBELUM LAND)
+    db.execute(builder.build(schema.create_table_from_entity(organization::Entity).if_not_exists())).await?;
+    db.execute(builder.build(schema.create_table_from_entity(organization_member::Entity).if_not_exists())).await?;
+
     // Land
     db.execute(builder.build(schema.create_table_from_entity(land::Entity).if_not_exists())).await?;
+    db.execute(builder.build(schema.create_table_from_entity(zone::Entity).if_not_exists())).await?;
+    db.execute(builder.build(schema.create_table_from_entity(weather::Entity).if_not_exists())).await?;
+
+    // Satu baris cuaca per land, jenis & jam; data baru untuk jam yang sama menimpa yang lama
+    db.execute(Statement::from_string(
+        builder,
+        "CREATE UNIQUE INDEX IF NOT EXISTS \"weather_slot\" ON \"weather\" (land_id, kind, valid_at);".to_string(),
+    ))
+    .await?;
 
     // Components
     db.execute(builder.build(schema.create_table_from_entity(sensor::Entity).if_not_exists())).await?;
     db.execute(builder.build(schema.create_table_from_entity(sensor_history::Entity).if_not_exists())).await?;
+    db.execute(builder.build(schema.create_table_from_entity(sensor_history_hourly::Entity).if_not_exists())).await?;
+    db.execute(builder.build(schema.create_table_from_entity(sensor_history_daily::Entity).if_not_exists())).await?;
+    db.execute(builder.build(schema.create_table_from_entity(retention_policy::Entity).if_not_exists())).await?;
+    db.execute(builder.build(schema.create_table_from_entity(anomaly_policy::Entity).if_not_exists())).await?;
+    db.execute(builder.build(schema.create_table_from_entity(import_job::Entity).if_not_exists())).await?;
+
+    // Rollup memakai upsert (ON CONFLICT) per sensor & bucket
+    for table in ["sensor_history_hourly", "sensor_history_daily"] {
+        let sql = format!(
+            "CREATE UNIQUE INDEX IF NOT EXISTS \"{0}_bucket\" ON \"{0}\" (sensor_id, bucket_start);",
+            table
+        );
+        db.execute(Statement::from_string(builder, sql)).await?;
+    }
+    db.execute(Statement::from_string(
+        builder,
+        "CREATE INDEX IF NOT EXISTS \"sensor_history_sensor_time\" ON \"sensor_history\" (sensor_id, recorded_at);".to_string(),
+    ))
+    .await?;
 
     // Plant (Sekarang aman dibuat karena Seed dan Land sudah ada)
     db.execute(builder.build(schema.create_table_from_entity(plant::Entity).if_not_exists())).await?;
+    db.execute(builder.build(schema.create_table_from_entity(harvest::Entity).if_not_exists())).await?;
+
+    // Sensor virtual merujuk sensor sumber & tanaman
+    db.execute(builder.build(schema.create_table_from_entity(derived_sensor::Entity).if_not_exists())).await?;
 
     db.execute(builder.build(schema.create_table_from_entity(valve::Entity).if_not_exists())).await?;
     db.execute(builder.build(schema.create_table_from_entity(pump::Entity).if_not_exists())).await?;
@@ -72,7 +97,257 @@ pub async fn setup_tables(db: &DatabaseConnection) -> Result<(), DbErr> {
 
     db.execute(builder.build(schema.create_table_from_entity(pest_control::Entity).if_not_exists())).await?;
 
+    // Task merujuk land, user, objek lapangan & rekomendasi
+    db.execute(builder.build(schema.create_table_from_entity(task::Entity).if_not_exists())).await?;
+    db.execute(builder.build(schema.create_table_from_entity(task_comment::Entity).if_not_exists())).await?;
+
     db.execute(builder.build(schema.create_table_from_entity(notification::Entity).if_not_exists())).await?;
 
+    db.execute(builder.build(schema.create_table_from_entity(audit_log::Entity).if_not_exists())).await?;
+
+    // ====================================================
+    // 3. UPGRADE DATABASE LAMA
+    // ====================================================
+    upgrade_schema(db).await?;
+
     Ok(())
-}
\ No newline at end of file
+}
+
+// `create_table_from_entity` tidak mengubah tabel yang sudah ada, jadi kolom & foreign key yang
+// ditambahkan setelah versi awal dipasang di sini. Semua langkah aman dijalankan ulang setiap start;
+// untuk database baru tidak ada yang berubah.
+async fn upgrade_schema(db: &DatabaseConnection) -> Result<(), DbErr> {
+    // User
+    add_column::<user::Entity>(db, user::Column::FailedLoginAttempts, Some(0.into())).await?;
+    add_column::<user::Entity>(db, user::Column::LockedUntil, None).await?;
+    add_column::<user::Entity>(db, user::Column::FullName, None).await?;
+    add_column::<user::Entity>(db, user::Column::Phone, None).await?;
+    add_column::<user::Entity>(db, user::Column::Email, None).await?;
+    add_column::<user::Entity>(db, user::Column::Language, Some("id".into())).await?;
+    add_column::<user::Entity>(db, user::Column::Timezone, Some("Asia/Jakarta".into())).await?;
+    add_column::<user::Entity>(db, user::Column::Status, Some("Active".into())).await?;
+    add_column::<user::Entity>(db, user::Column::DeletedAt, None).await?;
+
+    // Seed
+    add_column::<seed::Entity>(db, seed::Column::BaseTemperature, None).await?;
+
+    // Land
+    add_column::<land::Entity>(db, land::Column::Latitude, None).await?;
+    add_column::<land::Entity>(db, land::Column::Longitude, None).await?;
+    add_column::<land::Entity>(db, land::Column::Boundary, None).await?;
+    add_column::<land::Entity>(db, land::Column::DeletedAt, None).await?;
+    add_land_organization(db).await?;
+    ensure_foreign_key(db, "land", "organization_id", land::Relation::Organization.def()).await?;
+    ensure_foreign_key(db, "land", "user_id", land::Relation::User.def()).await?;
+
+    // Perangkat & tanaman
+    add_column::<sensor::Entity>(db, sensor::Column::ZoneId, None).await?;
+    add_column::<sensor::Entity>(db, sensor::Column::Latitude, None).await?;
+    add_column::<sensor::Entity>(db, sensor::Column::Longitude, None).await?;
+    add_column::<sensor::Entity>(db, sensor::Column::DeletedAt, None).await?;
+    ensure_foreign_key(db, "sensor", "zone_id", sensor::Relation::Zone.def()).await?;
+
+    add_column::<sensor_history::Entity>(db, sensor_history::Column::RolledUp, Some(false.into())).await?;
+    add_column::<sensor_history::Entity>(db, sensor_history::Column::Anomaly, None).await?;
+    add_column::<sensor_history::Entity>(db, sensor_history::Column::AnomalyDetail, None).await?;
+
+    add_column::<plant::Entity>(db, plant::Column::ZoneId, None).await?;
+    add_column::<plant::Entity>(db, plant::Column::ClosedAt, None).await?;
+    add_column::<plant::Entity>(db, plant::Column::DeletedAt, None).await?;
+    ensure_foreign_key(db, "plant", "zone_id", plant::Relation::Zone.def()).await?;
+
+    add_column::<valve::Entity>(db, valve::Column::ZoneId, None).await?;
+    add_column::<valve::Entity>(db, valve::Column::Latitude, None).await?;
+    add_column::<valve::Entity>(db, valve::Column::Longitude, None).await?;
+    add_column::<valve::Entity>(db, valve::Column::IsOpen, Some(false.into())).await?;
+    add_column::<valve::Entity>(db, valve::Column::MaxRuntimeSeconds, None).await?;
+    add_column::<valve::Entity>(db, valve::Column::MaxDailyVolume, None).await?;
+    add_column::<valve::Entity>(db, valve::Column::CooldownSeconds, None).await?;
+    add_column::<valve::Entity>(db, valve::Column::DeletedAt, None).await?;
+    ensure_foreign_key(db, "valve", "zone_id", valve::Relation::Zone.def()).await?;
+
+    add_column::<pump::Entity>(db, pump::Column::Latitude, None).await?;
+    add_column::<pump::Entity>(db, pump::Column::Longitude, None).await?;
+    add_column::<pump::Entity>(db, pump::Column::FlowRate, None).await?;
+    add_column::<pump::Entity>(db, pump::Column::MaxRuntimeSeconds, None).await?;
+    add_column::<pump::Entity>(db, pump::Column::MaxDailyVolume, None).await?;
+    add_column::<pump::Entity>(db, pump::Column::CooldownSeconds, None).await?;
+    add_column::<pump::Entity>(db, pump::Column::DeletedAt, None).await?;
+
+    // Automation
+    add_column::<automation::Entity>(db, automation::Column::ZoneId, None).await?;
+    add_column::<automation::Entity>(db, automation::Column::Enabled, Some(true.into())).await?;
+    add_column::<automation::Entity>(db, automation::Column::PausedUntil, None).await?;
+    add_column::<automation::Entity>(db, automation::Column::WeatherMode, Some("Ignore".into())).await?;
+    add_column::<automation::Entity>(db, automation::Column::RainThresholdMm, None).await?;
+    add_column::<automation::Entity>(db, automation::Column::RainLookbackHours, Some(crate::weather::DEFAULT_LOOKBACK_HOURS.into())).await?;
+    add_column::<automation::Entity>(db, automation::Column::RainForecastHours, Some(crate::weather::DEFAULT_FORECAST_HOURS.into())).await?;
+    add_column::<automation::Entity>(db, automation::Column::DeletedAt, None).await?;
+    ensure_foreign_key(db, "automation", "zone_id", automation::Relation::Zone.def()).await?;
+
+    // Riwayat lama tercatat sebelum ada jenis event, semuanya eksekusi biasa
+    add_column::<automation_history::Entity>(db, automation_history::Column::Event, Some("Triggered".into())).await?;
+    add_column::<automation_history::Entity>(db, automation_history::Column::UserId, None).await?;
+    add_column::<automation_history::Entity>(db, automation_history::Column::Note, None).await?;
+    add_column::<automation_history::Entity>(db, automation_history::Column::Volume, None).await?;
+    ensure_foreign_key(db, "automation_history", "user_id", automation_history::Relation::User.def()).await?;
+
+    Ok(())
+}
+
+// ALTER TABLE .. ADD COLUMN IF NOT EXISTS sesuai definisi kolom di model.
+// Kolom NOT NULL butuh `default` agar baris lama langsung terisi.
+async fn add_column<E: EntityTrait>(db: &DatabaseConnection, column: E::Column, default: Option<SimpleExpr>) -> Result<(), DbErr> {
+    let builder = db.get_database_backend();
+    let mut def = Schema::new(builder).get_column_def::<E>(column);
+    if let Some(value) = default {
+        def.default(value);
+    }
+    let stmt = Table::alter().table(E::default().table_ref()).add_column_if_not_exists(&mut def).to_owned();
+    db.execute(builder.build(&stmt)).await?;
+    Ok(())
+}
+
+// Pasang foreign key untuk kolom `table.column` jika belum ada. Constraint lama dengan aturan
+// ON DELETE berbeda (mis. land.user_id yang dulu Cascade) dihapus lalu dibuat ulang.
+async fn ensure_foreign_key(db: &DatabaseConnection, table: &str, column: &str, rel: RelationDef) -> Result<(), DbErr> {
+    let builder = db.get_database_backend();
+    // Kode `pg_constraint.confdeltype`
+    let wanted = match rel.on_delete {
+        Some(ForeignKeyAction::Cascade) => "c",
+        Some(ForeignKeyAction::SetNull) => "n",
+        Some(ForeignKeyAction::Restrict) => "r",
+        Some(ForeignKeyAction::SetDefault) => "d",
+        Some(ForeignKeyAction::NoAction) | None => "a",
+    };
+
+    let existing = db
+        .query_all(Statement::from_sql_and_values(
+            builder,
+            r#"SELECT c.conname, c.confdeltype::text AS on_delete
+               FROM pg_constraint c
+               JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = ANY (c.conkey)
+               WHERE c.contype = 'f' AND c.conrelid = $1::regclass AND a.attname = $2"#,
+            [format!("\"{}\"", table).into(), column.into()],
+        ))
+        .await?;
+    let mut found = false;
+    for row in existing {
+        let name: String = row.try_get("", "conname")?;
+        let on_delete: String = row.try_get("", "on_delete")?;
+        if on_delete == wanted {
+            found = true;
+        } else {
+            let sql = format!("ALTER TABLE \"{}\" DROP CONSTRAINT \"{}\";", table, name);
+            db.execute(Statement::from_string(builder, sql)).await?;
+        }
+    }
+    if !found {
+        let fk: ForeignKeyCreateStatement = rel.into();
+        db.execute(builder.build(&fk)).await?;
+    }
+    Ok(())
+}
+
+// Land dulu dimiliki user langsung. Setiap pemilik land lama dibuatkan organisasi sendiri
+// (dengan dia sebagai Owner) lalu land-nya dipindahkan ke organisasi tersebut.
+async fn add_land_organization(db: &DatabaseConnection) -> Result<(), DbErr> {
+    let builder = db.get_database_backend();
+    db.execute(Statement::from_string(
+        builder,
+        "ALTER TABLE \"land\" ADD COLUMN IF NOT EXISTS \"organization_id\" integer;".to_string(),
+    ))
+    .await?;
+
+    let owners = db
+        .query_all(Statement::from_string(
+            builder,
+            r#"SELECT DISTINCT u.id, u.username
+               FROM "land" l JOIN "user" u ON u.id = l.user_id
+               WHERE l.organization_id IS NULL"#.to_string(),
+        ))
+        .await?;
+    for row in owners {
+        let user_id: i32 = row.try_get("", "id")?;
+        let username: String = row.try_get("", "username")?;
+
+        let org = organization::ActiveModel {
+            name: Set(format!("{} #{}", username, user_id)),
+            created_at: Set(chrono::Local::now().naive_local()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+        organization_member::ActiveModel {
+            organization_id: Set(org.id),
+            user_id: Set(user_id),
+            role: Set(organization_member::OrgRole::Owner),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+        land::Entity::update_many()
+            .col_expr(land::Column::OrganizationId, Expr::value(org.id))
+            .filter(land::Column::UserId.eq(user_id))
+            .filter(land::Column::OrganizationId.is_null())
+            .exec(db)
+            .await?;
+    }
+
+    db.execute(Statement::from_string(
+        builder,
+        "ALTER TABLE \"land\" ALTER COLUMN \"organization_id\" SET NOT NULL;".to_string(),
+    ))
+    .await?;
+    Ok(())
+}
+
+async fn drop_tables(db: &DatabaseConnection) -> Result<(), DbErr> {
+    let builder = db.get_database_backend();
+
+    // List tabel yang akan dihapus
+    let tables = [
+        "audit_log",
+        "notification", // <--- Tambahkan ini (sebelum user dihapus)
+        "task_comment",
+        "task",
+        "automation_history",
+        "automation",
+        "sensor_history_daily",
+        "sensor_history_hourly",
+        "sensor_history",
+        "retention_policy",
+        "anomaly_policy",
+        "derived_sensor",
+        "import_job",
+        "sensor",
+        "weather",
+        "harvest",
+        "plant",
+        "valve",
+        "pump",
+        "zone",
+        "land",
+        "organization_member",
+        "organization",
+        "seed",
+        "recommendation",
+        "pest_control", 
+        "user_session",
+        "refresh_token",
+        "password_reset_token",
+        "user",
+        "role_permission",
+        "permission",
+        "user_role"
+    ];
+
+    for table in tables {
+        // Query: DROP TABLE IF EXISTS "nama_tabel" CASCADE;
+        let sql = format!("DROP TABLE IF EXISTS \"{}\" CASCADE;", table);
+        let stmt = Statement::from_string(builder, sql);
+        db.execute(stmt).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+
+use chrono::{Duration, Local, NaiveDateTime, NaiveTime};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait,
+    FromQueryResult, QueryFilter, QueryOrder, Set, Statement,
+};
+
+use crate::{
+    models::{
+        derived_sensor::{self, DerivedMetric},
+        plant, seed,
+        sensor::{self, SensorType},
+        sensor_history, sensor_history_daily,
+    },
+    soft_delete::SoftDelete,
+};
+
+// Dipakai jika seed tidak punya base_temperature
+pub const DEFAULT_BASE_TEMPERATURE: f64 = 10.0;
+
+// Pembacaan suhu & kelembapan yang dipasangkan harus sama-sama sebaru ini
+const PAIR_WINDOW_MINUTES: i64 = 30;
+
+pub fn sensor_type(metric: DerivedMetric) -> SensorType {
+    match metric {
+        DerivedMetric::Vpd => SensorType::Vpd,
+        DerivedMetric::DewPoint => SensorType::DewPoint,
+        DerivedMetric::GrowingDegreeDays => SensorType::GrowingDegreeDays,
+    }
+}
+
+// ====================================================
+// RUMUS
+// ====================================================
+
+// Tekanan uap jenuh (kPa), persamaan Tetens
+fn saturation_vapour_pressure(temperature: f64) -> f64 {
+    0.6108 * (17.27 * temperature / (temperature + 237.3)).exp()
+}
+
+// VPD (kPa) dari suhu (°C) & kelembapan relatif (%)
+pub fn vpd(temperature: f64, humidity: f64) -> f64 {
+    saturation_vapour_pressure(temperature) * (1.0 - humidity.clamp(0.0, 100.0) / 100.0)
+}
+
+// Titik embun (°C), pendekatan Magnus
+pub fn dew_point(temperature: f64, humidity: f64) -> f64 {
+    let (a, b) = (17.27, 237.7);
+    let gamma = (humidity.clamp(1.0, 100.0) / 100.0).ln() + a * temperature / (b + temperature);
+    b * gamma / (a - gamma)
+}
+
+// GDD kumulatif dari suhu min & maks harian
+pub fn growing_degree_days(days: impl Iterator<Item = (f64, f64)>, base: f64) -> f64 {
+    days.map(|(min, max)| ((min + max) / 2.0 - base).max(0.0)).sum()
+}
+
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+// ====================================================
+// PERHITUNGAN
+// ====================================================
+
+// Pembacaan terbaru yang bukan anomali, paling lambat `at`
+async fn latest_reading<C: ConnectionTrait>(
+    db: &C,
+    sensor_id: i32,
+    at: NaiveDateTime,
+) -> Result<Option<sensor_history::Model>, DbErr> {
+    sensor_history::Entity::find()
+        .filter(sensor_history::Column::SensorId.eq(sensor_id))
+        .filter(sensor_history::Column::RecordedAt.lte(at))
+        .filter(sensor_history::Column::Anomaly.is_null())
+        .order_by_desc(sensor_history::Column::RecordedAt)
+        .one(db)
+        .await
+}
+
+#[derive(FromQueryResult)]
+struct DayRange {
+    day: NaiveDateTime,
+    min_value: f64,
+    max_value: f64,
+}
+
+const RAW_DAY_RANGES: &str = r#"
+SELECT date_trunc('day', recorded_at) AS day, MIN(value) AS min_value, MAX(value) AS max_value
+FROM "sensor_history"
+WHERE sensor_id = $1 AND recorded_at >= $2 AND recorded_at <= $3 AND anomaly IS NULL
+GROUP BY day
+"#;
+
+// Suhu min & maks per hari dalam [since, at]: agregat harian untuk hari yang sudah diringkas,
+// sisanya langsung dari pembacaan mentah
+async fn daily_ranges<C: ConnectionTrait>(
+    db: &C,
+    sensor_id: i32,
+    since: NaiveDateTime,
+    at: NaiveDateTime,
+) -> Result<BTreeMap<NaiveDateTime, (f64, f64)>, DbErr> {
+    let first_day = since.date().and_time(NaiveTime::MIN);
+    let mut days: BTreeMap<NaiveDateTime, (f64, f64)> = sensor_history_daily::Entity::find()
+        .filter(sensor_history_daily::Column::SensorId.eq(sensor_id))
+        .filter(sensor_history_daily::Column::BucketStart.gte(first_day))
+        .filter(sensor_history_daily::Column::BucketStart.lte(at))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|d| (d.bucket_start, (d.min_value, d.max_value)))
+        .collect();
+
+    let raw_from = days
+        .keys()
+        .next_back()
+        .map_or(since, |last| *last + Duration::days(1));
+    let raw = DayRange::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        RAW_DAY_RANGES,
+        [sensor_id.into(), raw_from.into(), at.into()],
+    ))
+    .all(db)
+    .await?;
+    for r in raw {
+        days.entry(r.day).or_insert((r.min_value, r.max_value));
+    }
+    Ok(days)
+}
+
+// Nilai sensor virtual pada waktu `at`; None jika data sumbernya belum cukup
+pub async fn compute<C: ConnectionTrait>(
+    db: &C,
+    def: &derived_sensor::Model,
+    at: NaiveDateTime,
+) -> Result<Option<f64>, DbErr> {
+    let fresh = |r: &sensor_history::Model| at - r.recorded_at <= Duration::minutes(PAIR_WINDOW_MINUTES);
+
+    let value = match def.metric {
+        DerivedMetric::Vpd | DerivedMetric::DewPoint => {
+            let Some(humidity_id) = def.humidity_sensor_id else {
+                return Ok(None);
+            };
+            let temperature = latest_reading(db, def.temperature_sensor_id, at).await?.filter(fresh);
+            let humidity = latest_reading(db, humidity_id, at).await?.filter(fresh);
+            let (Some(t), Some(h)) = (temperature, humidity) else {
+                return Ok(None);
+            };
+            if def.metric == DerivedMetric::Vpd {
+                vpd(t.value, h.value)
+            } else {
+                dew_point(t.value, h.value)
+            }
+        }
+        DerivedMetric::GrowingDegreeDays => {
+            let Some(plant_id) = def.plant_id else {
+                return Ok(None);
+            };
+            let Some(p) = plant::Entity::find_active_by_id(plant_id).one(db).await? else {
+                return Ok(None);
+            };
+            if p.planted_at > at {
+                return Ok(Some(0.0));
+            }
+            let base = seed::Entity::find_by_id(p.seed_id)
+                .one(db)
+                .await?
+                .and_then(|s| s.base_temperature)
+                .unwrap_or(DEFAULT_BASE_TEMPERATURE);
+            let days = daily_ranges(db, def.temperature_sensor_id, p.planted_at, at).await?;
+            growing_degree_days(days.into_values(), base)
+        }
+    };
+    Ok(Some(round(value)))
+}
+
+async fn record<C: ConnectionTrait>(
+    db: &C,
+    sensor_id: i32,
+    value: f64,
+    at: NaiveDateTime,
+) -> Result<sensor_history::Model, DbErr> {
+    sensor_history::ActiveModel {
+        sensor_id: Set(sensor_id),
+        value: Set(value),
+        recorded_at: Set(at),
+        rolled_up: Set(false),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+}
+
+pub async fn is_derived(db: &DatabaseConnection, sensor_id: i32) -> Result<bool, DbErr> {
+    Ok(derived_sensor::Entity::find()
+        .filter(derived_sensor::Column::SensorId.eq(sensor_id))
+        .one(db)
+        .await?
+        .is_some())
+}
+
+// Definisi sensor virtual aktif yang memakai sensor ini sebagai sumber
+async fn dependents(db: &DatabaseConnection, sensor_id: i32) -> Result<Vec<derived_sensor::Model>, DbErr> {
+    let defs = derived_sensor::Entity::find()
+        .filter(
+            Condition::any()
+                .add(derived_sensor::Column::TemperatureSensorId.eq(sensor_id))
+                .add(derived_sensor::Column::HumiditySensorId.eq(sensor_id)),
+        )
+        .all(db)
+        .await?;
+
+    let mut active = Vec::new();
+    for def in defs {
+        if sensor::Entity::find_active_by_id(def.sensor_id).one(db).await?.is_some() {
+            active.push(def);
+        }
+    }
+    Ok(active)
+}
+
+// Dipanggil setelah pembacaan sumber (bukan anomali) tersimpan: hitung & simpan nilai sensor virtual
+// yang bergantung padanya. Pembacaan baru dikembalikan agar bisa dievaluasi ke automation.
+pub async fn on_reading(
+    db: &DatabaseConnection,
+    reading: &sensor_history::Model,
+) -> Result<Vec<sensor_history::Model>, DbErr> {
+    let mut readings = Vec::new();
+    for def in dependents(db, reading.sensor_id).await? {
+        if let Some(value) = compute(db, &def, reading.recorded_at).await? {
+            readings.push(record(db, def.sensor_id, value, reading.recorded_at).await?);
+        }
+    }
+    Ok(readings)
+}
+
+// Nilai awal saat sensor virtual baru dibuat
+pub async fn initialize(db: &DatabaseConnection, def: &derived_sensor::Model) -> Result<Option<sensor_history::Model>, DbErr> {
+    let now = Local::now().naive_local();
+    match compute(db, def, now).await? {
+        Some(value) => Ok(Some(record(db, def.sensor_id, value, now).await?)),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,166 @@
+use chrono::{Local, NaiveDateTime};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, Set,
+    TransactionTrait,
+};
+
+use crate::{
+    models::{
+        automation,
+        automation_history::{self, AutomationEvent},
+    },
+    notifier, safety,
+    soft_delete::SoftDelete,
+    weather::{self, Decision},
+};
+
+// Hasil eksekusi sebuah automation
+pub enum RunOutcome {
+    Triggered(automation_history::Model),
+    // Tidak dijalankan dan tidak dicatat: automation nonaktif / di-pause, atau blokir / hujan
+    // yang masih berlangsung
+    Skipped(String),
+    Blocked(automation_history::Model),
+    // Tidak menyiram karena hujan; alasannya tercatat di history (sekali per masa hujan)
+    RainSkipped(automation_history::Model),
+}
+
+// Cek apakah automation boleh dijalankan saat ini (enabled & tidak sedang di-pause)
+pub fn check_active(auto: &automation::Model, now: NaiveDateTime) -> Result<(), String> {
+    if !auto.enabled {
+        return Err("Automation is disabled".to_string());
+    }
+    if let Some(until) = auto.paused_until
+        && until > now
+    {
+        return Err(format!("Automation is paused until {}", until));
+    }
+    Ok(())
+}
+
+// Catat kejadian ke automation_history
+pub async fn record_event<C: ConnectionTrait>(
+    db: &C,
+    automation_id: i32,
+    event: AutomationEvent,
+    user_id: Option<i32>,
+    note: Option<String>,
+    volume: Option<f64>,
+) -> Result<automation_history::Model, DbErr> {
+    automation_history::ActiveModel {
+        automation_id: Set(automation_id),
+        event: Set(event),
+        user_id: Set(user_id),
+        note: Set(note),
+        volume: Set(volume),
+        triggered_at: Set(Local::now().naive_local()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+}
+
+// Kejadian terakhir sebuah automation, dipakai agar blokir & skip hujan yang berulang tidak dicatat terus-menerus
+async fn last_event<C: ConnectionTrait>(db: &C, automation_id: i32) -> Result<Option<automation_history::Model>, DbErr> {
+    automation_history::Entity::find()
+        .filter(automation_history::Column::AutomationId.eq(automation_id))
+        .order_by_desc(automation_history::Column::TriggeredAt)
+        .order_by_desc(automation_history::Column::Id)
+        .one(db)
+        .await
+}
+
+// Jalur eksekusi tunggal: dipakai oleh engine (user_id = None) maupun trigger manual.
+// Aturan hujan hanya berlaku untuk engine; trigger manual selalu menyiram penuh.
+pub async fn run(
+    db: &DatabaseConnection,
+    auto: &automation::Model,
+    user_id: Option<i32>,
+) -> Result<RunOutcome, DbErr> {
+    let now = Local::now().naive_local();
+    if let Err(reason) = check_active(auto, now) {
+        return Ok(RunOutcome::Skipped(reason));
+    }
+
+    let (volume, note) = match user_id {
+        Some(_) => (auto.dispense_amount, None),
+        None => match weather::assess(db, auto, now).await? {
+            Decision::Run { volume, note } => (volume, note),
+            Decision::Skip { reason } => {
+                // Satu event per masa hujan: selama event terakhir masih Skipped, pembacaan berikutnya tidak dicatat
+                if last_event(db, auto.id).await?.is_some_and(|h| h.event == AutomationEvent::Skipped) {
+                    return Ok(RunOutcome::Skipped(reason));
+                }
+                let history = record_event(db, auto.id, AutomationEvent::Skipped, None, Some(reason), None).await?;
+                return Ok(RunOutcome::RainSkipped(history));
+            }
+        },
+    };
+    // Batas pompa & katup dicek terhadap volume yang benar-benar akan dialirkan
+    let auto = &automation::Model { dispense_amount: volume, ..auto.clone() };
+
+    // Interlock & pencatatan Triggered dalam satu transaksi (lihat `safety::check`)
+    let txn = db.begin().await?;
+
+    // Interlock: blokir, catat alasannya, lalu kabari pemilik land.
+    // Blokir yang masih berlangsung (event terakhir juga Blocked, mis. sensor macet di 0 selama
+    // cooldown) tidak dicatat ulang oleh engine, dan pemilik land hanya dikabari di awalnya.
+    if let Some(reason) = safety::check(&txn, auto).await? {
+        let ongoing = last_event(&txn, auto.id).await?.is_some_and(|h| h.event == AutomationEvent::Blocked);
+        if ongoing && user_id.is_none() {
+            txn.commit().await?;
+            return Ok(RunOutcome::Skipped(reason));
+        }
+        let history = record_event(&txn, auto.id, AutomationEvent::Blocked, user_id, Some(reason.clone()), None).await?;
+        txn.commit().await?;
+        if !ongoing {
+            notifier::notify_land_owner(
+                db,
+                auto.land_id,
+                format!("Automation \"{}\" was blocked: {}", auto.name, reason),
+            )
+            .await?;
+        }
+        return Ok(RunOutcome::Blocked(history));
+    }
+
+    // Perintah ke pompa & katup dikirim di sini; untuk saat ini cukup dicatat
+    let history = record_event(
+        &txn,
+        auto.id,
+        AutomationEvent::Triggered,
+        user_id,
+        note,
+        Some(volume),
+    )
+    .await?;
+    txn.commit().await?;
+    Ok(RunOutcome::Triggered(history))
+}
+
+// Dipanggil setiap ada pembacaan sensor baru: jalankan automation yang threshold-nya terlewati.
+// Mengembalikan event Triggered, Blocked maupun Skipped (hujan) yang tercatat.
+pub async fn evaluate_reading(
+    db: &DatabaseConnection,
+    sensor_id: i32,
+    value: f64,
+) -> Result<Vec<automation_history::Model>, DbErr> {
+    let automations = automation::Entity::find_active()
+        .filter(automation::Column::SensorId.eq(sensor_id))
+        .filter(automation::Column::Enabled.eq(true))
+        .all(db)
+        .await?;
+
+    let mut events = Vec::new();
+    for auto in automations {
+        if value >= auto.sensor_value {
+            continue;
+        }
+        match run(db, &auto, None).await? {
+            RunOutcome::Triggered(h) | RunOutcome::Blocked(h) | RunOutcome::RainSkipped(h) => events.push(h),
+            RunOutcome::Skipped(_) => {}
+        }
+    }
+
+    Ok(events)
+}
@@ -0,0 +1,114 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use actix_session::SessionExt;
+use actix_web::{dev::Payload, http::header, web, FromRequest, HttpRequest};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    AppState,
+    error::ApiError,
+    handlers::auth::{account_suspended, client_ip, session_user_id},
+    models::{
+        organization_member::{self, OrgRole},
+        user::{self, UserStatus},
+        user_role,
+    },
+    permissions::{self, role_permission_names},
+    soft_delete::SoftDelete,
+    tokens::verify_access_token,
+};
+
+// User yang sedang login, diambil dari header "Authorization: Bearer" atau dari session.
+// Tambahkan sebagai parameter handler untuk mewajibkan login.
+pub struct AuthUser {
+    pub id: i32,
+    pub role: String,
+    // Permission dari role user, lihat `permissions::ALL`
+    pub permissions: Vec<String>,
+    // Keanggotaan organisasi: (organization_id, role di organisasi tersebut)
+    pub memberships: Vec<(i32, OrgRole)>,
+    // IP asal request, dicatat di audit log
+    pub ip: String,
+}
+
+impl AuthUser {
+    pub fn has(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|p| p == permission)
+    }
+
+    pub fn require(&self, permission: &str) -> Result<(), ApiError> {
+        if self.has(permission) {
+            Ok(())
+        } else {
+            Err(ApiError::forbidden(format!("Requires {} permission", permission)).with_code("missing_permission"))
+        }
+    }
+
+    // Super-admin platform melihat & mengelola semua organisasi
+    pub fn is_super_admin(&self) -> bool {
+        self.has(permissions::PLATFORM_ADMIN)
+    }
+
+    pub fn org_role(&self, organization_id: i32) -> Option<OrgRole> {
+        self.memberships
+            .iter()
+            .find(|(org, _)| *org == organization_id)
+            .map(|(_, role)| *role)
+    }
+}
+
+impl FromRequest for AuthUser {
+    type Error = ApiError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let session = req.get_session();
+        let ip = client_ip(req);
+        let data = req.app_data::<web::Data<AppState>>().cloned();
+        let bearer = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(|t| t.trim().to_string());
+
+        Box::pin(async move {
+            let data = data.ok_or_else(ApiError::internal)?;
+
+            // Bearer token yang dikirim tapi tidak valid langsung ditolak, tidak jatuh ke session
+            let user_id = match bearer {
+                Some(token) => verify_access_token(&data.security.token_secret, &token)
+                    .ok_or_else(|| ApiError::unauthorized("Invalid or expired access token").with_code("invalid_token"))?,
+                None => session_user_id(&session).ok_or_else(|| ApiError::unauthorized("Not authenticated"))?,
+            };
+
+            let (u, role) = user::Entity::find_active_by_id(user_id)
+                .find_also_related(user_role::Entity)
+                .one(&data.db)
+                .await?
+                .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?;
+
+            if u.status == UserStatus::Suspended {
+                return Err(account_suspended());
+            }
+
+            let permissions = role_permission_names(&data.db, u.user_role_id).await?;
+            let memberships = organization_member::Entity::find()
+                .filter(organization_member::Column::UserId.eq(u.id))
+                .all(&data.db)
+                .await?
+                .into_iter()
+                .map(|m| (m.organization_id, m.role))
+                .collect();
+
+            Ok(AuthUser {
+                id: u.id,
+                role: role.map(|r| r.name).unwrap_or_else(|| "user".to_string()),
+                permissions,
+                memberships,
+                ip,
+            })
+        })
+    }
+}
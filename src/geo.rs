@@ -0,0 +1,115 @@
+use sea_orm::prelude::Json;
+
+// Poligon disimpan sebagai satu ring titik [lng, lat] (urutan GeoJSON), tanpa titik penutup
+pub type Ring = Vec<[f64; 2]>;
+
+// `land.size` & `zone.area` dalam hektar; luas poligon boleh meleset sebanyak ini dari luas yang dicatat
+pub const AREA_TOLERANCE: f64 = 0.1;
+
+const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+const SQUARE_METERS_PER_HECTARE: f64 = 10_000.0;
+
+// Pesan kesalahan jika ring tidak bisa dipakai sebagai batas area
+pub fn ring_error(ring: &[[f64; 2]]) -> Option<String> {
+    if ring.len() < 3 {
+        return Some("Must have at least 3 points".to_string());
+    }
+    for (i, [lng, lat]) in ring.iter().enumerate() {
+        if !(-180.0..=180.0).contains(lng) || !(-90.0..=90.0).contains(lat) {
+            return Some(format!("Point {} is not a valid [lng, lat] coordinate", i));
+        }
+    }
+    if area_hectares(ring) <= 0.0 {
+        return Some("Must enclose an area".to_string());
+    }
+    None
+}
+
+// Luas ring dibandingkan dengan luas yang dicatat
+pub fn area_error(ring: &[[f64; 2]], recorded: f64) -> Option<String> {
+    let area = area_hectares(ring);
+    if (area - recorded).abs() > recorded * AREA_TOLERANCE {
+        return Some(format!("Polygon covers {:.4} ha but the recorded size is {} ha", area, recorded));
+    }
+    None
+}
+
+// Latitude & longitude harus dikirim berpasangan dan dalam rentang
+pub fn point_error(latitude: Option<f64>, longitude: Option<f64>) -> Option<String> {
+    match (latitude, longitude) {
+        (None, None) => None,
+        (Some(lat), Some(lng)) if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lng) => None,
+        (Some(_), Some(_)) => Some("Latitude must be within ±90 and longitude within ±180".to_string()),
+        _ => Some("Latitude and longitude must be given together".to_string()),
+    }
+}
+
+// Luas ring di permukaan bola (hektar)
+pub fn area_hectares(ring: &[[f64; 2]]) -> f64 {
+    let mut sum = 0.0;
+    for i in 0..ring.len() {
+        let [lng1, lat1] = ring[i];
+        let [lng2, lat2] = ring[(i + 1) % ring.len()];
+        sum += (lng2 - lng1).to_radians() * (2.0 + lat1.to_radians().sin() + lat2.to_radians().sin());
+    }
+    (sum * EARTH_RADIUS_METERS * EARTH_RADIUS_METERS / 2.0).abs() / SQUARE_METERS_PER_HECTARE
+}
+
+// Titik tengah sederhana (rata-rata titik), cukup untuk penanda peta di petak kecil
+pub fn centroid(ring: &[[f64; 2]]) -> (f64, f64) {
+    let n = ring.len().max(1) as f64;
+    let lng = ring.iter().map(|p| p[0]).sum::<f64>() / n;
+    let lat = ring.iter().map(|p| p[1]).sum::<f64>() / n;
+    (lat, lng)
+}
+
+// Titik lokasi: yang dikirim, atau titik tengah boundary
+pub fn point_or_centroid(latitude: Option<f64>, longitude: Option<f64>, boundary: Option<&Ring>) -> (Option<f64>, Option<f64>) {
+    match (latitude, longitude, boundary) {
+        (None, None, Some(ring)) => {
+            let (lat, lng) = centroid(ring);
+            (Some(lat), Some(lng))
+        }
+        _ => (latitude, longitude),
+    }
+}
+
+// Titik terakhir yang sama dengan titik pertama dibuang agar tersimpan seragam
+pub fn normalize(mut ring: Ring) -> Ring {
+    if ring.len() > 1 && ring.first() == ring.last() {
+        ring.pop();
+    }
+    ring
+}
+
+pub fn to_json(ring: &Ring) -> Json {
+    serde_json::json!(ring)
+}
+
+pub fn from_json(value: &Json) -> Option<Ring> {
+    serde_json::from_value(value.clone()).ok()
+}
+
+// ====================================================
+// GEOJSON
+// ====================================================
+
+pub fn point_geometry(latitude: Option<f64>, longitude: Option<f64>) -> Json {
+    match (latitude, longitude) {
+        (Some(lat), Some(lng)) => serde_json::json!({ "type": "Point", "coordinates": [lng, lat] }),
+        _ => Json::Null,
+    }
+}
+
+// GeoJSON mewajibkan ring tertutup (titik pertama diulang di akhir)
+pub fn polygon_geometry(ring: &Ring) -> Json {
+    let mut closed = ring.clone();
+    if let Some(first) = ring.first() {
+        closed.push(*first);
+    }
+    serde_json::json!({ "type": "Polygon", "coordinates": [closed] })
+}
+
+pub fn feature(geometry: Json, properties: Json) -> Json {
+    serde_json::json!({ "type": "Feature", "geometry": geometry, "properties": properties })
+}
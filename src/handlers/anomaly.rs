@@ -0,0 +1,124 @@
+use actix_web::{delete, get, put, web};
+use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, Set};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    audit,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::{anomaly_policy, sensor::SensorType},
+    permissions,
+    response,
+    tenancy::{self, Access},
+    validation::Validator,
+};
+
+#[derive(Deserialize)]
+pub struct SetPolicyRequest {
+    pub land_id: Option<i32>,
+    pub sensor_type: Option<SensorType>,
+    pub flatline_readings: i32,
+}
+
+// Policy untuk satu land butuh hak kelola land; policy global hanya super-admin
+async fn require_policy_admin(data: &AppState, auth: &AuthUser, land_id: Option<i32>) -> Result<(), ApiError> {
+    match land_id {
+        Some(land_id) => {
+            auth.require(permissions::LAND_WRITE)?;
+            tenancy::land_access(&data.db, auth, land_id, Access::Manage).await?;
+            Ok(())
+        }
+        None => auth.require(permissions::PLATFORM_ADMIN),
+    }
+}
+
+// Policy global + policy land yang boleh dilihat caller, beserta default dari config
+#[get("/anomaly-policies")]
+pub async fn get_anomaly_policies(auth: AuthUser, data: web::Data<AppState>) -> ApiResult {
+    let mut select = anomaly_policy::Entity::find().order_by_asc(anomaly_policy::Column::Id);
+    if let Some(land_ids) = tenancy::visible_land_ids(&data.db, &auth).await? {
+        select = select.filter(
+            Condition::any()
+                .add(anomaly_policy::Column::LandId.is_null())
+                .add(anomaly_policy::Column::LandId.is_in(land_ids)),
+        );
+    }
+
+    Ok(response::ok(serde_json::json!({
+        "defaults": {
+            "flatline_readings": data.anomaly.flatline_readings,
+            "flatline_types": data.anomaly.flatline_types
+        },
+        "policies": select.all(&data.db).await?
+    })))
+}
+
+// Buat atau ganti policy untuk kombinasi land + tipe sensor
+#[put("/anomaly-policies")]
+pub async fn set_anomaly_policy(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    form: web::Json<SetPolicyRequest>,
+) -> ApiResult {
+    require_policy_admin(&data, &auth, form.land_id).await?;
+
+    let mut v = Validator::new(&data.db);
+    if form.flatline_readings < 0 || form.flatline_readings == 1 {
+        v.add("flatline_readings", "Must be 0 (disabled) or at least 2");
+    }
+    v.finish()?;
+
+    let existing = anomaly_policy::Entity::find()
+        .filter(match form.land_id {
+            Some(land_id) => anomaly_policy::Column::LandId.eq(land_id),
+            None => anomaly_policy::Column::LandId.is_null(),
+        })
+        .filter(match &form.sensor_type {
+            Some(t) => anomaly_policy::Column::SensorType.eq(t.clone()),
+            None => anomaly_policy::Column::SensorType.is_null(),
+        })
+        .one(&data.db)
+        .await?;
+
+    let policy = match existing {
+        Some(before) => {
+            let mut active: anomaly_policy::ActiveModel = before.clone().into();
+            active.flatline_readings = Set(form.flatline_readings);
+            let policy = active.update(&data.db).await?;
+            audit::updated(&data.db, &auth, "anomaly_policy", policy.id, &before, &policy).await;
+            policy
+        }
+        None => {
+            let policy = anomaly_policy::ActiveModel {
+                land_id: Set(form.land_id),
+                sensor_type: Set(form.sensor_type.clone()),
+                flatline_readings: Set(form.flatline_readings),
+                ..Default::default()
+            }
+            .insert(&data.db)
+            .await?;
+            audit::created(&data.db, &auth, "anomaly_policy", policy.id, &policy).await;
+            policy
+        }
+    };
+
+    Ok(response::ok_with_message("Anomaly policy saved", policy))
+}
+
+#[delete("/anomaly-policies/{id}")]
+pub async fn delete_anomaly_policy(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    let policy = anomaly_policy::Entity::find_by_id(path.into_inner())
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Anomaly policy"))?;
+    require_policy_admin(&data, &auth, policy.land_id).await?;
+
+    anomaly_policy::Entity::delete_by_id(policy.id).exec(&data.db).await?;
+    audit::deleted(&data.db, &auth, "anomaly_policy", policy.id, &policy).await;
+    Ok(response::message("Anomaly policy deleted"))
+}
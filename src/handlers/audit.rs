@@ -0,0 +1,76 @@
+use actix_web::{get, web};
+use chrono::NaiveDateTime;
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    error::ApiResult,
+    extractors::AuthUser,
+    models::audit_log::{self, AuditAction},
+    permissions, response, tenancy,
+};
+
+#[derive(Deserialize)]
+pub struct AuditQuery {
+    // Riwayat satu entitas: ?entity_type=pump&entity_id=3
+    pub entity_type: Option<String>,
+    pub entity_id: Option<i32>,
+    // Aktivitas satu user
+    pub actor_id: Option<i32>,
+    pub action: Option<AuditAction>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+// Selain super-admin, hanya melihat perubahan yang dilakukan anggota organisasi yang dikelolanya
+#[get("/audit-logs")]
+pub async fn get_audit_logs(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    query: web::Query<AuditQuery>,
+) -> ApiResult {
+    auth.require(permissions::AUDIT_READ)?;
+
+    let mut select = audit_log::Entity::find()
+        .order_by_desc(audit_log::Column::CreatedAt)
+        .order_by_desc(audit_log::Column::Id);
+
+    if let Some(actor_ids) = tenancy::managed_user_ids(&data.db, &auth).await? {
+        select = select.filter(audit_log::Column::ActorId.is_in(actor_ids));
+    }
+    if let Some(entity_type) = &query.entity_type {
+        select = select.filter(audit_log::Column::EntityType.eq(entity_type.trim()));
+    }
+    if let Some(entity_id) = query.entity_id {
+        select = select.filter(audit_log::Column::EntityId.eq(entity_id));
+    }
+    if let Some(actor_id) = query.actor_id {
+        select = select.filter(audit_log::Column::ActorId.eq(actor_id));
+    }
+    if let Some(action) = query.action {
+        select = select.filter(audit_log::Column::Action.eq(action));
+    }
+    if let Some(from) = query.from {
+        select = select.filter(audit_log::Column::CreatedAt.gte(from));
+    }
+    if let Some(to) = query.to {
+        select = select.filter(audit_log::Column::CreatedAt.lte(to));
+    }
+
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(50).clamp(1, 200);
+    let paginator = select.paginate(&data.db, per_page);
+    let total = paginator.num_items().await?;
+    let entries = paginator.fetch_page(page - 1).await?;
+
+    Ok(response::ok(serde_json::json!({
+        "items": entries,
+        "page": page,
+        "per_page": per_page,
+        "total": total,
+        "total_pages": total.div_ceil(per_page)
+    })))
+}
@@ -1,90 +1,211 @@
 use actix_session::Session;
-use actix_web::{HttpResponse, Responder, get, post, web};
-use bcrypt::verify;
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use actix_web::{http::header, post, web, HttpRequest};
+use std::sync::LazyLock;
+
+use bcrypt::{hash, verify, DEFAULT_COST};
+use chrono::{Duration, Local, NaiveDateTime};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
 use serde::Deserialize;
 
 use crate::{
     AppState,
-    models::{user, user_role},
+    error::{ApiError, ApiResult},
+    models::{user::{self, UserStatus}, user_role},
+    response,
+    soft_delete::SoftDelete,
+    tokens,
 };
 
+// Ambil user_id dari session (None jika belum login)
+pub fn session_user_id(session: &Session) -> Option<i32> {
+    session.get::<i32>("user_id").ok().flatten()
+}
+
+// Hash pembanding untuk username yang tidak ada, agar waktu respons sama dengan password salah
+static DUMMY_HASH: LazyLock<String> =
+    LazyLock::new(|| hash("dummy-password", DEFAULT_COST).expect("bcrypt hash dummy password"));
+
 #[derive(Deserialize)]
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
 }
 
+// Cek kredensial bersama untuk login session (/login) dan bearer token (/token).
+// Menerapkan throttling, lockout, dan reset hitungan gagal.
+async fn authenticate(
+    req: &HttpRequest,
+    data: &AppState,
+    form: &LoginRequest,
+) -> Result<(user::Model, String), ApiError> {
+    // Throttling per IP dan per username sebelum bcrypt dijalankan
+    let ip = client_ip(req);
+    let keys = [format!("ip:{}", ip), format!("user:{}", form.username.to_lowercase())];
+    if let Err(retry_after) = data.login_throttle.hit(&keys) {
+        return Err(ApiError::too_many_requests(format!(
+            "Too many login attempts, try again in {} seconds",
+            retry_after
+        )));
+    }
+
+    let invalid = || ApiError::unauthorized("Invalid username or password").with_code("invalid_credentials");
+
+    let Some(user) = user::Entity::find_active()
+        .filter(user::Column::Username.eq(&form.username))
+        .one(&data.db)
+        .await?
+    else {
+        let _ = verify(&form.password, &DUMMY_HASH);
+        return Err(invalid());
+    };
+
+    let now = Local::now().naive_local();
+    if let Some(until) = user.locked_until
+        && until > now
+    {
+        return Err(account_locked(until));
+    }
+
+    if !verify(&form.password, &user.password).unwrap_or(false) {
+        let attempts = user.failed_login_attempts + 1;
+        let mut active: user::ActiveModel = user.into();
+
+        // Kunci akun jika gagal terlalu sering, lalu reset hitungan
+        if attempts >= data.security.max_failed_logins {
+            let until = now + Duration::minutes(data.security.lockout_minutes);
+            active.failed_login_attempts = Set(0);
+            active.locked_until = Set(Some(until));
+            active.update(&data.db).await?;
+            return Err(account_locked(until));
+        }
+
+        active.failed_login_attempts = Set(attempts);
+        active.update(&data.db).await?;
+        return Err(invalid());
+    }
+
+    // Status baru dicek setelah password benar agar tidak membocorkan info akun
+    if user.status == UserStatus::Suspended {
+        return Err(account_suspended());
+    }
+
+    let role = user_role::Entity::find_by_id(user.user_role_id)
+        .one(&data.db)
+        .await?
+        .map(|r| r.name)
+        .unwrap_or_else(|| "user".to_string());
+
+    let user = if user.failed_login_attempts > 0 || user.locked_until.is_some() {
+        let mut active: user::ActiveModel = user.into();
+        active.failed_login_attempts = Set(0);
+        active.locked_until = Set(None);
+        active.update(&data.db).await?
+    } else {
+        user
+    };
+
+    Ok((user, role))
+}
+
+pub fn client_ip(req: &HttpRequest) -> String {
+    req.connection_info().realip_remote_addr().unwrap_or("unknown").to_string()
+}
+
 #[post("/login")]
 pub async fn login(
+    req: HttpRequest,
     data: web::Data<AppState>,
     session: Session,
     form: web::Json<LoginRequest>,
-) -> impl Responder {
-    let user = user::Entity::find()
-        .filter(user::Column::Username.eq(&form.username))
-        .one(&data.db)
-        .await
-        .unwrap();
-
-    if let Some(user) = user {
-        if verify(&form.password, &user.password).unwrap_or(false) {
-            let role = user_role::Entity::find_by_id(user.user_role_id)
-                .one(&data.db)
-                .await
-                .unwrap()
-                .map(|r| r.name)
-                .unwrap_or_else(|| "user".to_string());
-
-            session.insert("user_id", user.id).unwrap();
-            return HttpResponse::Ok().json(serde_json::json!({
-                "success": true,
-                "message": "Login successful",
-                "data": {
-                    "id": user.id,
-                    "username": user.username,
-                    "role": role
-                }
-            }));
-        }
-    }
+) -> ApiResult {
+    let (user, role) = authenticate(&req, &data, &form).await?;
 
-    HttpResponse::Unauthorized().json(serde_json::json!({
-        "success": false,
-        "error": "Invalid username or password"
-    }))
+    // IP & user agent ikut disimpan agar tampil di daftar session aktif
+    let user_agent = req
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    session.renew();
+    session
+        .insert("user_id", user.id)
+        .and_then(|_| session.insert("ip", client_ip(&req)))
+        .and_then(|_| session.insert("user_agent", user_agent))
+        .map_err(|e| {
+            eprintln!("Session error: {:?}", e);
+            ApiError::internal()
+        })?;
+
+    Ok(response::ok_with_message(
+        "Login successful",
+        serde_json::json!({
+            "id": user.id,
+            "username": user.username,
+            "role": role
+        }),
+    ))
 }
 
-#[get("/me")]
-pub async fn me(session: Session, data: web::Data<AppState>) -> impl Responder {
-    if let Some(user_id) = session.get::<i32>("user_id").unwrap() {
-        if let Some((u, role)) = user::Entity::find_by_id(user_id)
-            .find_also_related(user_role::Entity)
-            .one(&data.db)
-            .await
-            .unwrap()
-        {
-            return HttpResponse::Ok().json(serde_json::json!({
-                "success": true,
-                "data": {
-                    "id": u.id,
-                    "username": u.username,
-                    "role": role.map(|r| r.name).unwrap_or_else(|| "user".to_string()),
-                }
-            }));
-        }
+#[derive(Deserialize)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+// Login untuk aplikasi mobile / script: kembalikan access token + refresh token
+#[post("/token")]
+pub async fn issue_token(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    form: web::Json<LoginRequest>,
+) -> ApiResult {
+    let (user, role) = authenticate(&req, &data, &form).await?;
+    let tokens = tokens::issue_token_pair(&data.db, &data.security, user.id).await?;
+
+    Ok(response::ok_with_message(
+        "Login successful",
+        serde_json::json!({
+            "id": user.id,
+            "username": user.username,
+            "role": role,
+            "token": tokens
+        }),
+    ))
+}
+
+#[post("/token/refresh")]
+pub async fn refresh_token(
+    data: web::Data<AppState>,
+    form: web::Json<RefreshTokenRequest>,
+) -> ApiResult {
+    let tokens = tokens::rotate_refresh_token(&data.db, &data.security, &form.refresh_token)
+        .await?
+        .ok_or_else(|| ApiError::unauthorized("Invalid or expired refresh token").with_code("invalid_token"))?;
+
+    Ok(response::ok(tokens))
+}
+
+// Access token yang sudah terbit tetap berlaku sampai kedaluwarsa (umurnya pendek)
+#[post("/token/revoke")]
+pub async fn revoke_token(
+    data: web::Data<AppState>,
+    form: web::Json<RefreshTokenRequest>,
+) -> ApiResult {
+    if !tokens::revoke_refresh_token(&data.db, &form.refresh_token).await? {
+        return Err(ApiError::not_found("Refresh token"));
     }
+    Ok(response::message("Token revoked"))
+}
+
+fn account_locked(until: NaiveDateTime) -> ApiError {
+    ApiError::locked(format!("Account is locked until {}", until)).with_code("account_locked")
+}
 
-    HttpResponse::Unauthorized().json(serde_json::json!({
-        "success": false,
-        "error": "Not authenticated"
-    }))
+pub fn account_suspended() -> ApiError {
+    ApiError::forbidden("Account is suspended").with_code("account_suspended")
 }
 
 #[post("/logout")]
-pub async fn logout(session: Session) -> impl Responder {
+pub async fn logout(session: Session) -> ApiResult {
     session.purge();
-    HttpResponse::Ok().json(serde_json::json!({
-        "message": "Logged out"
-    }))
+    Ok(response::message("Logged out"))
 }
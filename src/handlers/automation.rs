@@ -1,7 +1,27 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
+use actix_web::{get, post, put, delete, web};
+use chrono::{Local, NaiveDateTime};
 use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
 use serde::Deserialize;
-use crate::{AppState, models::automation::{self, AutomationType}}; // Import Enum
+
+use crate::{
+    AppState,
+    audit,
+    engine::{self, RunOutcome},
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::{
+        automation::{self, AutomationType, WeatherMode},
+        automation_history::AutomationEvent,
+        sensor,
+    },
+    permissions,
+    response,
+    soft_delete::{self, SoftDelete},
+    tenancy::{self, Access},
+    validation::Validator,
+    weather,
+    zones,
+};
 
 #[derive(Deserialize)]
 pub struct CreateAutoRequest {
@@ -13,8 +33,21 @@ pub struct CreateAutoRequest {
     pub valve_id: i32,
     pub land_id: i32,
     pub dispense_amount: f64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub weather_mode: WeatherMode,
+    pub rain_threshold_mm: Option<f64>,
+    #[serde(default = "default_lookback_hours")]
+    pub rain_lookback_hours: i32,
+    #[serde(default = "default_forecast_hours")]
+    pub rain_forecast_hours: i32,
 }
 
+fn default_enabled() -> bool { true }
+fn default_lookback_hours() -> i32 { weather::DEFAULT_LOOKBACK_HOURS }
+fn default_forecast_hours() -> i32 { weather::DEFAULT_FORECAST_HOURS }
+
 #[derive(Deserialize)]
 pub struct UpdateAutoRequest {
     pub name: String,
@@ -24,10 +57,65 @@ pub struct UpdateAutoRequest {
     pub pump_id: i32,
     pub valve_id: i32,
     pub dispense_amount: f64,
+    #[serde(default)]
+    pub weather_mode: WeatherMode,
+    pub rain_threshold_mm: Option<f64>,
+    #[serde(default = "default_lookback_hours")]
+    pub rain_lookback_hours: i32,
+    #[serde(default = "default_forecast_hours")]
+    pub rain_forecast_hours: i32,
+}
+
+#[derive(Deserialize)]
+pub struct AutomationListQuery {
+    pub zone_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct PauseAutoRequest {
+    pub until: NaiveDateTime,
+    pub note: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct StateChangeRequest {
+    pub note: Option<String>,
+}
+
+// Sensor, pompa & katup harus ada dan berada di land yang sama dengan automation.
+// Mengembalikan zona automation, yaitu zona katupnya. Panggil setelah akses ke land dicek,
+// agar id milik tenant lain tidak bisa ditebak dari pesan validasi.
+async fn validate_refs(data: &AppState, land_id: i32, sensor_id: i32, pump_id: i32, valve_id: i32) -> Result<Option<i32>, ApiError> {
+    let mut v = Validator::new(&data.db);
+    v.sensor_on_land("sensor_id", sensor_id, land_id).await?;
+    v.pump_on_land("pump_id", pump_id, land_id).await?;
+    v.valve_on_land("valve_id", valve_id, land_id).await?;
+
+    let zone_id = zones::valve_zone(&data.db, valve_id).await?;
+    let sensor_zone = sensor::Entity::find_by_id(sensor_id).one(&data.db).await?.and_then(|s| s.zone_id);
+    if !zones::sensor_fits(sensor_zone, zone_id) {
+        v.add("sensor_id", "Sensor is in a different zone than the valve");
+    }
+    v.finish()?;
+    Ok(zone_id)
+}
+
+// Ambil automation sekaligus cek akses caller ke land-nya
+async fn find_automation(data: &AppState, auth: &AuthUser, id: i32, access: Access) -> Result<automation::Model, ApiError> {
+    let auto = automation::Entity::find_active_by_id(id).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Automation"))?;
+    tenancy::land_access(&data.db, auth, auto.land_id, access).await?;
+    Ok(auto)
 }
 
 #[post("/automations")]
-pub async fn create_automation(data: web::Data<AppState>, form: web::Json<CreateAutoRequest>) -> impl Responder {
+pub async fn create_automation(auth: AuthUser, data: web::Data<AppState>, form: web::Json<CreateAutoRequest>) -> ApiResult {
+    auth.require(permissions::AUTOMATION_WRITE)?;
+    let mut v = Validator::new(&data.db);
+    v.weather_rule("", &form.automation_type, form.weather_mode, form.rain_threshold_mm, form.rain_lookback_hours, form.rain_forecast_hours);
+    v.finish()?;
+    tenancy::land_access(&data.db, &auth, form.land_id, Access::Write).await?;
+    let zone_id = validate_refs(&data, form.land_id, form.sensor_id, form.pump_id, form.valve_id).await?;
+
     let new_auto = automation::ActiveModel {
         name: Set(form.name.clone()),
         automation_type: Set(form.automation_type.clone()), // Set Type
@@ -36,41 +124,48 @@ pub async fn create_automation(data: web::Data<AppState>, form: web::Json<Create
         pump_id: Set(form.pump_id),
         valve_id: Set(form.valve_id),
         land_id: Set(form.land_id),
+        zone_id: Set(zone_id),
         dispense_amount: Set(form.dispense_amount),
+        enabled: Set(form.enabled),
+        paused_until: Set(None),
+        weather_mode: Set(form.weather_mode),
+        rain_threshold_mm: Set(form.rain_threshold_mm),
+        rain_lookback_hours: Set(form.rain_lookback_hours),
+        rain_forecast_hours: Set(form.rain_forecast_hours),
         ..Default::default()
     };
-    match new_auto.insert(&data.db).await {
-        Ok(a) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": a })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
+    let auto = new_auto.insert(&data.db).await?;
+    audit::created(&data.db, &auth, "automation", auto.id, &auto).await;
+    Ok(response::ok(auto))
 }
 
 // ... Get functions sama ...
 #[get("/lands/{land_id}/automations")]
-pub async fn get_automations_by_land(data: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
-    match automation::Entity::find().filter(automation::Column::LandId.eq(path.into_inner())).all(&data.db).await {
-        Ok(res) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": res })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
+pub async fn get_automations_by_land(auth: AuthUser, data: web::Data<AppState>, path: web::Path<i32>, query: web::Query<AutomationListQuery>) -> ApiResult {
+    let land = tenancy::land_access(&data.db, &auth, path.into_inner(), Access::Read).await?;
+    let mut select = automation::Entity::find_active().filter(automation::Column::LandId.eq(land.id));
+    if let Some(zone_id) = query.zone_id {
+        select = select.filter(automation::Column::ZoneId.eq(zone_id));
     }
+    let res = select.all(&data.db).await?;
+    Ok(response::ok(res))
 }
 
 #[get("/automations/{id}")]
-pub async fn get_automation_by_id(data: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
-    match automation::Entity::find_by_id(path.into_inner()).one(&data.db).await {
-        Ok(Some(a)) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": a })),
-        Ok(None) => HttpResponse::NotFound().finish(),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
+pub async fn get_automation_by_id(auth: AuthUser, data: web::Data<AppState>, path: web::Path<i32>) -> ApiResult {
+    Ok(response::ok(find_automation(&data, &auth, path.into_inner(), Access::Read).await?))
 }
 
 #[put("/automations/{id}")]
-pub async fn update_automation(data: web::Data<AppState>, path: web::Path<i32>, form: web::Json<UpdateAutoRequest>) -> impl Responder {
-    let id = path.into_inner();
-    let existing = match automation::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(a)) => a,
-        Ok(None) => return HttpResponse::NotFound().finish(),
-        Err(_) => return HttpResponse::InternalServerError().finish(),
-    };
+pub async fn update_automation(auth: AuthUser, data: web::Data<AppState>, path: web::Path<i32>, form: web::Json<UpdateAutoRequest>) -> ApiResult {
+    auth.require(permissions::AUTOMATION_WRITE)?;
+    let existing = find_automation(&data, &auth, path.into_inner(), Access::Write).await?;
+    let mut v = Validator::new(&data.db);
+    v.weather_rule("", &form.automation_type, form.weather_mode, form.rain_threshold_mm, form.rain_lookback_hours, form.rain_forecast_hours);
+    v.finish()?;
+    let zone_id = validate_refs(&data, existing.land_id, form.sensor_id, form.pump_id, form.valve_id).await?;
+
+    let before = existing.clone();
     let mut active: automation::ActiveModel = existing.into();
     active.name = Set(form.name.clone());
     active.automation_type = Set(form.automation_type.clone()); // Update Type
@@ -78,18 +173,120 @@ pub async fn update_automation(data: web::Data<AppState>, path: web::Path<i32>,
     active.sensor_value = Set(form.sensor_value);
     active.pump_id = Set(form.pump_id);
     active.valve_id = Set(form.valve_id);
+    active.zone_id = Set(zone_id);
     active.dispense_amount = Set(form.dispense_amount);
+    active.weather_mode = Set(form.weather_mode);
+    active.rain_threshold_mm = Set(form.rain_threshold_mm);
+    active.rain_lookback_hours = Set(form.rain_lookback_hours);
+    active.rain_forecast_hours = Set(form.rain_forecast_hours);
 
-    match active.update(&data.db).await {
-        Ok(a) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": a })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
+    let auto = active.update(&data.db).await?;
+    audit::updated(&data.db, &auth, "automation", auto.id, &before, &auto).await;
+    Ok(response::ok(auto))
 }
 
 #[delete("/automations/{id}")]
-pub async fn delete_automation(data: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
-    match automation::Entity::delete_by_id(path.into_inner()).exec(&data.db).await {
-        Ok(_) => HttpResponse::Ok().json(serde_json::json!({ "success": true })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
+pub async fn delete_automation(auth: AuthUser, data: web::Data<AppState>, path: web::Path<i32>) -> ApiResult {
+    auth.require(permissions::AUTOMATION_WRITE)?;
+    let existing = find_automation(&data, &auth, path.into_inner(), Access::Write).await?;
+    soft_delete::delete::<automation::Entity, _>(&data.db, existing.id, soft_delete::now()).await?;
+    audit::deleted(&data.db, &auth, "automation", existing.id, &existing).await;
+    Ok(response::message("Automation deleted successfully"))
+}
+
+// Sensor, pompa & katup yang dipakai harus sudah aktif kembali
+#[post("/automations/{id}/restore")]
+pub async fn restore_automation(auth: AuthUser, data: web::Data<AppState>, path: web::Path<i32>) -> ApiResult {
+    auth.require(permissions::AUTOMATION_WRITE)?;
+    let id = path.into_inner();
+    let existing = automation::Entity::find_by_id(id).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Automation"))?;
+    tenancy::restorable_land(&data.db, &auth, existing.land_id).await?;
+    let Some(deleted_at) = existing.deleted_at else {
+        return Err(ApiError::conflict("Automation is not deleted").with_code("not_deleted"));
+    };
+
+    let missing = soft_delete::missing_automation_refs(&data.db, &existing).await?;
+    if !missing.is_empty() {
+        return Err(ApiError::conflict(format!("Restore the automation's {} first", missing.join(", "))).with_code("parent_deleted"));
+    }
+
+    soft_delete::restore::<automation::Entity, _>(&data.db, id, deleted_at).await?;
+    let auto = automation::Entity::find_active_by_id(id).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Automation"))?;
+    audit::updated(&data.db, &auth, "automation", id, &existing, &auto).await;
+    Ok(response::ok_with_message("Automation restored successfully", auto))
+}
+
+// Ubah status automation lalu catat siapa yang melakukannya
+async fn change_state(
+    data: &AppState,
+    auth: &AuthUser,
+    id: i32,
+    event: AutomationEvent,
+    note: Option<String>,
+    apply: impl FnOnce(&mut automation::ActiveModel),
+) -> ApiResult {
+    let existing = find_automation(data, auth, id, Access::Write).await?;
+    let before = existing.clone();
+    let mut active: automation::ActiveModel = existing.into();
+    apply(&mut active);
+
+    let updated = active.update(&data.db).await?;
+    audit::updated(&data.db, auth, "automation", id, &before, &updated).await;
+    let history = engine::record_event(&data.db, id, event, Some(auth.id), note, None).await?;
+    Ok(response::ok(serde_json::json!({ "automation": updated, "history": history })))
+}
+
+#[post("/automations/{id}/enable")]
+pub async fn enable_automation(auth: AuthUser, data: web::Data<AppState>, path: web::Path<i32>, form: Option<web::Json<StateChangeRequest>>) -> ApiResult {
+    auth.require(permissions::AUTOMATION_WRITE)?;
+    let note = form.and_then(|f| f.into_inner().note);
+    change_state(&data, &auth, path.into_inner(), AutomationEvent::Enabled, note, |a| {
+        a.enabled = Set(true);
+    }).await
+}
+
+#[post("/automations/{id}/disable")]
+pub async fn disable_automation(auth: AuthUser, data: web::Data<AppState>, path: web::Path<i32>, form: Option<web::Json<StateChangeRequest>>) -> ApiResult {
+    auth.require(permissions::AUTOMATION_WRITE)?;
+    let note = form.and_then(|f| f.into_inner().note);
+    change_state(&data, &auth, path.into_inner(), AutomationEvent::Disabled, note, |a| {
+        a.enabled = Set(false);
+    }).await
+}
+
+#[post("/automations/{id}/pause")]
+pub async fn pause_automation(auth: AuthUser, data: web::Data<AppState>, path: web::Path<i32>, form: web::Json<PauseAutoRequest>) -> ApiResult {
+    auth.require(permissions::AUTOMATION_WRITE)?;
+    if form.until <= Local::now().naive_local() {
+        return Err(ApiError::bad_request("Pause end time must be in the future"));
+    }
+    let form = form.into_inner();
+    let until = form.until;
+    let note = Some(form.note.unwrap_or_else(|| format!("Paused until {}", until)));
+    change_state(&data, &auth, path.into_inner(), AutomationEvent::Paused, note, |a| {
+        a.paused_until = Set(Some(until));
+    }).await
+}
+
+#[post("/automations/{id}/resume")]
+pub async fn resume_automation(auth: AuthUser, data: web::Data<AppState>, path: web::Path<i32>, form: Option<web::Json<StateChangeRequest>>) -> ApiResult {
+    auth.require(permissions::AUTOMATION_WRITE)?;
+    let note = form.and_then(|f| f.into_inner().note);
+    change_state(&data, &auth, path.into_inner(), AutomationEvent::Resumed, note, |a| {
+        a.paused_until = Set(None);
+    }).await
+}
+
+// Trigger manual: lewat jalur eksekusi yang sama dengan engine
+#[post("/automations/{id}/trigger")]
+pub async fn trigger_automation(auth: AuthUser, data: web::Data<AppState>, path: web::Path<i32>) -> ApiResult {
+    auth.require(permissions::AUTOMATION_TRIGGER)?;
+    let auto = find_automation(&data, &auth, path.into_inner(), Access::Write).await?;
+
+    match engine::run(&data.db, &auto, Some(auth.id)).await? {
+        RunOutcome::Triggered(h) => Ok(response::ok(h)),
+        RunOutcome::Skipped(reason) => Err(ApiError::conflict(reason).with_code("automation_inactive")),
+        RunOutcome::Blocked(h) => Err(ApiError::conflict(h.note.unwrap_or_default()).with_code("automation_blocked")),
+        RunOutcome::RainSkipped(h) => Err(ApiError::conflict(h.note.unwrap_or_default()).with_code("automation_skipped")),
     }
-}
\ No newline at end of file
+}
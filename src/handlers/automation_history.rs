@@ -1,90 +1,60 @@
-use actix_web::{get, post, web, HttpResponse, Responder};
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
-use serde::Deserialize;
-use chrono::Local;
-
-use crate::{AppState, models::automation_history};
-
-// Struct request jika Anda ingin trigger manual via API
-#[derive(Deserialize)]
-pub struct CreateAutoHistoryRequest {
-    pub automation_id: i32,
-}
-
-// Handler untuk mencatat history (Triggered)
-#[post("/automation-history")]
-pub async fn create_automation_history(
-    data: web::Data<AppState>,
-    form: web::Json<CreateAutoHistoryRequest>,
-) -> impl Responder {
-    let new_history = automation_history::ActiveModel {
-        automation_id: Set(form.automation_id),
-        triggered_at: Set(Local::now().naive_local()), // Otomatis set waktu sekarang
-        ..Default::default()
-    };
-
-    match new_history.insert(&data.db).await {
-        Ok(h) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": h
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+use actix_web::{get, web};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+
+use crate::{
+    AppState,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::{automation, automation_history},
+    response,
+    soft_delete::SoftDelete,
+    tenancy::{self, Access},
+};
+
+async fn check_access(data: &AppState, auth: &AuthUser, automation_id: i32) -> Result<(), ApiError> {
+    let auto = automation::Entity::find_active_by_id(automation_id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Automation"))?;
+    tenancy::land_access(&data.db, auth, auto.land_id, Access::Read).await?;
+    Ok(())
 }
 
 // Handler untuk mengambil list history berdasarkan ID automation
 #[get("/automations/{automation_id}/history")]
 pub async fn get_history_by_automation(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
     let automation_id = path.into_inner();
+    check_access(&data, &auth, automation_id).await?;
 
-    match automation_history::Entity::find()
+    let histories = automation_history::Entity::find()
         .filter(automation_history::Column::AutomationId.eq(automation_id))
         .order_by_desc(automation_history::Column::TriggeredAt) // Urutkan dari yang terbaru
         .all(&data.db)
-        .await
-    {
-        Ok(histories) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": histories
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+        .await?;
+
+    Ok(response::ok(histories))
 }
 
 // Handler untuk mengambil kejadian TERAKHIR saja
 #[get("/automations/{automation_id}/latest")]
 pub async fn get_latest_history_by_automation(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
     let automation_id = path.into_inner();
+    check_access(&data, &auth, automation_id).await?;
 
-    match automation_history::Entity::find()
+    let history = automation_history::Entity::find()
         .filter(automation_history::Column::AutomationId.eq(automation_id))
         .order_by_desc(automation_history::Column::TriggeredAt)
         .one(&data.db)
-        .await
-    {
-        Ok(Some(history)) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": history
-        })),
-        Ok(None) => HttpResponse::Ok().json(serde_json::json!({ // Return OK tapi null data
-            "success": true,
-            "data": null
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
-}
\ No newline at end of file
+        .await?;
+
+    // Return OK tapi null data jika belum ada kejadian
+    Ok(response::ok(history))
+}
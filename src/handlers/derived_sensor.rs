@@ -0,0 +1,138 @@
+use actix_web::{get, post, web};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    audit,
+    derived,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::{
+        derived_sensor::{self, DerivedMetric},
+        plant,
+        sensor::{self, SensorType},
+    },
+    permissions,
+    response,
+    soft_delete::SoftDelete,
+    tenancy::{self, Access},
+    validation::Validator,
+};
+
+#[derive(Deserialize)]
+pub struct CreateDerivedSensorRequest {
+    pub name: String,
+    pub metric: DerivedMetric,
+    pub temperature_sensor_id: i32,
+    pub humidity_sensor_id: Option<i32>,
+    pub plant_id: Option<i32>,
+}
+
+// Sensor sumber harus aktif, di land yang sama, dan bertipe sesuai
+async fn check_source(
+    v: &mut Validator<'_>,
+    data: &AppState,
+    field: &str,
+    sensor_id: i32,
+    land_id: i32,
+    expected: SensorType,
+) -> Result<(), ApiError> {
+    match sensor::Entity::find_active_by_id(sensor_id).one(&data.db).await? {
+        Some(s) if s.land_id == land_id && s.sensor_type != expected => {
+            v.add(field, format!("Sensor {} is not a {:?} sensor", sensor_id, expected))
+        }
+        Some(s) if s.land_id == land_id => {}
+        _ => v.add(field, format!("Sensor {} does not exist on this land", sensor_id)),
+    }
+    Ok(())
+}
+
+// Buat sensor virtual (VPD, titik embun, atau GDD) yang nilainya dihitung dari sensor lain
+#[post("/lands/{land_id}/derived-sensors")]
+pub async fn create_derived_sensor(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    form: web::Json<CreateDerivedSensorRequest>,
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let land_id = path.into_inner();
+    tenancy::land_access(&data.db, &auth, land_id, Access::Write).await?;
+
+    let mut v = Validator::new(&data.db);
+    v.max_length("name", &form.name, 255);
+    check_source(&mut v, &data, "temperature_sensor_id", form.temperature_sensor_id, land_id, SensorType::Temperature).await?;
+    match form.metric {
+        DerivedMetric::Vpd | DerivedMetric::DewPoint => match form.humidity_sensor_id {
+            Some(id) => check_source(&mut v, &data, "humidity_sensor_id", id, land_id, SensorType::Humidity).await?,
+            None => v.add("humidity_sensor_id", "Required for this metric"),
+        },
+        DerivedMetric::GrowingDegreeDays => match form.plant_id {
+            Some(id) => match plant::Entity::find_active_by_id(id).one(&data.db).await? {
+                Some(p) if p.land_id == land_id => {}
+                _ => v.add("plant_id", format!("Plant {} does not exist on this land", id)),
+            },
+            None => v.add("plant_id", "Required for this metric"),
+        },
+    }
+    v.finish()?;
+
+    let txn = data.db.begin().await?;
+    let s = sensor::ActiveModel {
+        name: Set(form.name.clone()),
+        sensor_type: Set(derived::sensor_type(form.metric)),
+        land_id: Set(land_id),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    let def = derived_sensor::ActiveModel {
+        sensor_id: Set(s.id),
+        metric: Set(form.metric),
+        temperature_sensor_id: Set(form.temperature_sensor_id),
+        // Field yang tidak dipakai metric ini diabaikan
+        humidity_sensor_id: Set(form.humidity_sensor_id.filter(|_| form.metric != DerivedMetric::GrowingDegreeDays)),
+        plant_id: Set(form.plant_id.filter(|_| form.metric == DerivedMetric::GrowingDegreeDays)),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    txn.commit().await?;
+
+    audit::created(&data.db, &auth, "sensor", s.id, &s).await;
+    audit::created(&data.db, &auth, "derived_sensor", def.id, &def).await;
+
+    let latest = derived::initialize(&data.db, &def).await?;
+    Ok(response::ok_with_message(
+        "Derived sensor created successfully",
+        serde_json::json!({
+            "sensor": s,
+            "definition": def,
+            "latest": latest
+        }),
+    ))
+}
+
+#[get("/lands/{land_id}/derived-sensors")]
+pub async fn get_derived_sensors_by_land(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    let land_id = path.into_inner();
+    tenancy::land_access(&data.db, &auth, land_id, Access::Read).await?;
+
+    let rows = derived_sensor::Entity::find()
+        .find_also_related(sensor::Entity)
+        .filter(sensor::Column::LandId.eq(land_id))
+        .filter(sensor::Column::DeletedAt.is_null())
+        .all(&data.db)
+        .await?;
+
+    let result: Vec<_> = rows
+        .into_iter()
+        .filter_map(|(def, s)| Some(serde_json::json!({ "sensor": s?, "definition": def })))
+        .collect();
+    Ok(response::ok(result))
+}
@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+
+use actix_web::{get, http::header, web, HttpResponse};
+use chrono::{Duration, Local, NaiveDateTime};
+use futures_util::stream;
+use sea_orm::{ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    handlers::sensor_history::sensor_access,
+    models::{sensor, sensor_history::{self, AnomalyKind}, sensor_history_daily, sensor_history_hourly},
+    rollup::{self, Resolution},
+    soft_delete::SoftDelete,
+    tenancy::{self, Access},
+};
+
+// Jumlah baris per query; memori tetap kecil berapa pun panjang rentangnya
+const PAGE_SIZE: u64 = 1000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Ndjson,
+}
+
+impl ExportFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+// Tanpa from/to: 24 jam terakhir. Tanpa resolution: pembacaan mentah.
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub format: ExportFormat,
+    pub resolution: Option<Resolution>,
+}
+
+// Satu baris ekspor; anomaly hanya untuk pembacaan mentah, min/max/count hanya untuk resolusi hourly/daily
+struct Row {
+    id: i32,
+    sensor_id: i32,
+    recorded_at: NaiveDateTime,
+    value: f64,
+    anomaly: Option<AnomalyKind>,
+    min: Option<f64>,
+    max: Option<f64>,
+    count: Option<i32>,
+}
+
+struct ExportState {
+    db: DatabaseConnection,
+    sensors: HashMap<i32, sensor::Model>,
+    resolution: Resolution,
+    format: ExportFormat,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    // Keyset (waktu, id) dari baris terakhir yang sudah dikirim
+    cursor: Option<(NaiveDateTime, i32)>,
+    started: bool,
+    done: bool,
+}
+
+// Kondisi keyset: setelah (t, id) berdasarkan urutan waktu lalu id
+fn after<C: ColumnTrait>(time_col: C, id_col: C, cursor: Option<(NaiveDateTime, i32)>) -> Condition {
+    match cursor {
+        Some((t, id)) => Condition::any()
+            .add(time_col.gt(t))
+            .add(Condition::all().add(time_col.eq(t)).add(id_col.gt(id))),
+        None => Condition::all(),
+    }
+}
+
+async fn fetch_page(state: &ExportState) -> Result<Vec<Row>, DbErr> {
+    let ids: Vec<i32> = state.sensors.keys().copied().collect();
+    let from = rollup::bucket_floor(state.resolution, state.from);
+
+    let rows = match state.resolution {
+        Resolution::Raw => sensor_history::Entity::find()
+            .filter(sensor_history::Column::SensorId.is_in(ids))
+            .filter(sensor_history::Column::RecordedAt.gte(from))
+            .filter(sensor_history::Column::RecordedAt.lt(state.to))
+            .filter(after(sensor_history::Column::RecordedAt, sensor_history::Column::Id, state.cursor))
+            .order_by_asc(sensor_history::Column::RecordedAt)
+            .order_by_asc(sensor_history::Column::Id)
+            .limit(PAGE_SIZE)
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(|h| Row {
+                id: h.id,
+                sensor_id: h.sensor_id,
+                recorded_at: h.recorded_at,
+                value: h.value,
+                anomaly: h.anomaly,
+                min: None,
+                max: None,
+                count: None,
+            })
+            .collect(),
+        Resolution::Hourly => sensor_history_hourly::Entity::find()
+            .filter(sensor_history_hourly::Column::SensorId.is_in(ids))
+            .filter(sensor_history_hourly::Column::BucketStart.gte(from))
+            .filter(sensor_history_hourly::Column::BucketStart.lt(state.to))
+            .filter(after(
+                sensor_history_hourly::Column::BucketStart,
+                sensor_history_hourly::Column::Id,
+                state.cursor,
+            ))
+            .order_by_asc(sensor_history_hourly::Column::BucketStart)
+            .order_by_asc(sensor_history_hourly::Column::Id)
+            .limit(PAGE_SIZE)
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(|b| Row {
+                id: b.id,
+                sensor_id: b.sensor_id,
+                recorded_at: b.bucket_start,
+                value: b.avg_value,
+                anomaly: None,
+                min: Some(b.min_value),
+                max: Some(b.max_value),
+                count: Some(b.count),
+            })
+            .collect(),
+        Resolution::Daily => sensor_history_daily::Entity::find()
+            .filter(sensor_history_daily::Column::SensorId.is_in(ids))
+            .filter(sensor_history_daily::Column::BucketStart.gte(from))
+            .filter(sensor_history_daily::Column::BucketStart.lt(state.to))
+            .filter(after(
+                sensor_history_daily::Column::BucketStart,
+                sensor_history_daily::Column::Id,
+                state.cursor,
+            ))
+            .order_by_asc(sensor_history_daily::Column::BucketStart)
+            .order_by_asc(sensor_history_daily::Column::Id)
+            .limit(PAGE_SIZE)
+            .all(&state.db)
+            .await?
+            .into_iter()
+            .map(|b| Row {
+                id: b.id,
+                sensor_id: b.sensor_id,
+                recorded_at: b.bucket_start,
+                value: b.avg_value,
+                anomaly: None,
+                min: Some(b.min_value),
+                max: Some(b.max_value),
+                count: Some(b.count),
+            })
+            .collect(),
+    };
+    Ok(rows)
+}
+
+fn opt<T: ToString>(v: Option<T>) -> String {
+    v.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn encode_csv(state: &ExportState, rows: &[Row]) -> Result<Vec<u8>, csv::Error> {
+    let aggregated = state.resolution != Resolution::Raw;
+    let mut w = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+
+    if !state.started {
+        let mut header = vec!["sensor_id", "sensor_name", "sensor_type", "recorded_at", "value"];
+        if aggregated {
+            header.extend(["min", "max", "count"]);
+        } else {
+            header.push("anomaly");
+        }
+        w.write_record(&header)?;
+    }
+
+    for row in rows {
+        let Some(s) = state.sensors.get(&row.sensor_id) else { continue };
+        let mut record = vec![
+            row.sensor_id.to_string(),
+            s.name.clone(),
+            format!("{:?}", s.sensor_type),
+            row.recorded_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            row.value.to_string(),
+        ];
+        if aggregated {
+            record.extend([opt(row.min), opt(row.max), opt(row.count)]);
+        } else {
+            record.push(opt(row.anomaly.map(|a| format!("{:?}", a))));
+        }
+        w.write_record(&record)?;
+    }
+
+    w.into_inner().map_err(|e| e.into_error().into())
+}
+
+fn encode_ndjson(state: &ExportState, rows: &[Row]) -> Result<Vec<u8>, serde_json::Error> {
+    let mut buf = Vec::new();
+    for row in rows {
+        let Some(s) = state.sensors.get(&row.sensor_id) else { continue };
+        let mut line = serde_json::json!({
+            "sensor_id": row.sensor_id,
+            "sensor_name": s.name,
+            "sensor_type": s.sensor_type,
+            "recorded_at": row.recorded_at,
+            "value": row.value,
+        });
+        if state.resolution != Resolution::Raw {
+            line["min"] = serde_json::json!(row.min);
+            line["max"] = serde_json::json!(row.max);
+            line["count"] = serde_json::json!(row.count);
+        } else {
+            line["anomaly"] = serde_json::json!(row.anomaly);
+        }
+        serde_json::to_writer(&mut buf, &line)?;
+        buf.push(b'\n');
+    }
+    Ok(buf)
+}
+
+// Ambil satu halaman lalu ubah menjadi potongan body; None saat semua baris sudah terkirim
+async fn next_chunk(mut state: ExportState) -> Option<(Result<web::Bytes, actix_web::Error>, ExportState)> {
+    if state.done {
+        return None;
+    }
+
+    let rows = match fetch_page(&state).await {
+        Ok(rows) => rows,
+        Err(e) => {
+            // Header response sudah terkirim, jadi stream hanya bisa diputus
+            eprintln!("Export sensor history failed: {e}");
+            state.done = true;
+            return Some((Err(actix_web::error::ErrorInternalServerError("export failed")), state));
+        }
+    };
+
+    let encoded = match state.format {
+        ExportFormat::Csv => encode_csv(&state, &rows).map_err(|e| e.to_string()),
+        ExportFormat::Ndjson => encode_ndjson(&state, &rows).map_err(|e| e.to_string()),
+    };
+
+    state.started = true;
+    state.done = (rows.len() as u64) < PAGE_SIZE;
+    if let Some(last) = rows.last() {
+        state.cursor = Some((last.recorded_at, last.id));
+    }
+
+    match encoded {
+        Ok(bytes) => Some((Ok(web::Bytes::from(bytes)), state)),
+        Err(e) => {
+            eprintln!("Export sensor history failed: {e}");
+            state.done = true;
+            Some((Err(actix_web::error::ErrorInternalServerError("export failed")), state))
+        }
+    }
+}
+
+fn stream_export(
+    data: &AppState,
+    sensors: Vec<sensor::Model>,
+    query: &ExportQuery,
+    filename: String,
+) -> ApiResult {
+    let to = query.to.unwrap_or_else(|| Local::now().naive_local());
+    let from = query.from.unwrap_or(to - Duration::days(1));
+    if from >= to {
+        return Err(ApiError::bad_request("from must be before to").with_code("invalid_range"));
+    }
+
+    let state = ExportState {
+        db: data.db.clone(),
+        sensors: sensors.into_iter().map(|s| (s.id, s)).collect(),
+        resolution: query.resolution.unwrap_or(Resolution::Raw),
+        format: query.format,
+        from,
+        to,
+        cursor: None,
+        started: false,
+        done: false,
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(query.format.content_type())
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}.{}\"", filename, query.format.extension()),
+        ))
+        .streaming(stream::unfold(state, next_chunk)))
+}
+
+#[get("/sensors/{sensor_id}/history/export")]
+pub async fn export_sensor_history(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    query: web::Query<ExportQuery>,
+) -> ApiResult {
+    let sensor_id = path.into_inner();
+    let s = sensor_access(&data, &auth, sensor_id, Access::Read).await?;
+
+    stream_export(&data, vec![s], &query, format!("sensor-{}-history", sensor_id))
+}
+
+#[get("/lands/{land_id}/history/export")]
+pub async fn export_land_history(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    query: web::Query<ExportQuery>,
+) -> ApiResult {
+    let land_id = path.into_inner();
+    tenancy::land_access(&data.db, &auth, land_id, Access::Read).await?;
+
+    let sensors = sensor::Entity::find_active()
+        .filter(sensor::Column::LandId.eq(land_id))
+        .all(&data.db)
+        .await?;
+
+    stream_export(&data, sensors, &query, format!("land-{}-history", land_id))
+}
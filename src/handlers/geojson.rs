@@ -0,0 +1,90 @@
+use actix_web::{get, web, HttpResponse};
+use sea_orm::{ColumnTrait, QueryFilter};
+use serde_json::{json, Value};
+
+use crate::{
+    AppState,
+    error::ApiResult,
+    extractors::AuthUser,
+    geo,
+    models::{pump, sensor, valve, zone},
+    soft_delete::SoftDelete,
+    tenancy::{self, Access},
+};
+
+// Luas hasil hitung poligon (hektar), untuk dibandingkan dengan luas yang dicatat
+fn computed_area(polygon: Option<&Value>) -> Option<f64> {
+    polygon.and_then(geo::from_json).map(|ring| geo::area_hectares(&ring))
+}
+
+// Land, zona & perangkat sebagai GeoJSON FeatureCollection untuk peta.
+// Perangkat tanpa koordinat tidak ikut; properti `kind` membedakan jenis feature.
+#[get("/lands/{id}/geojson")]
+pub async fn get_land_geojson(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    let l = tenancy::land_access(&data.db, &auth, path.into_inner(), Access::Read).await?;
+
+    let zones = zone::Entity::find_active().filter(zone::Column::LandId.eq(l.id)).all(&data.db).await?;
+    let sensors = sensor::Entity::find_active().filter(sensor::Column::LandId.eq(l.id)).all(&data.db).await?;
+    let pumps = pump::Entity::find_active().filter(pump::Column::LandId.eq(l.id)).all(&data.db).await?;
+    let valves = valve::Entity::find_active().filter(valve::Column::LandId.eq(l.id)).all(&data.db).await?;
+
+    let mut features = Vec::new();
+    let land_geometry = match l.boundary.as_ref().and_then(geo::from_json) {
+        Some(ring) => geo::polygon_geometry(&ring),
+        None => geo::point_geometry(l.latitude, l.longitude),
+    };
+    features.push(geo::feature(
+        land_geometry,
+        json!({
+            "kind": "land",
+            "id": l.id,
+            "name": l.location_name,
+            "size": l.size,
+            "computed_area": computed_area(l.boundary.as_ref()),
+            "latitude": l.latitude,
+            "longitude": l.longitude
+        }),
+    ));
+
+    for z in &zones {
+        let Some(ring) = z.polygon.as_ref().and_then(geo::from_json) else { continue };
+        features.push(geo::feature(
+            geo::polygon_geometry(&ring),
+            json!({
+                "kind": "zone",
+                "id": z.id,
+                "name": z.name,
+                "area": z.area,
+                "computed_area": geo::area_hectares(&ring)
+            }),
+        ));
+    }
+
+    for s in sensors.iter().filter(|s| s.latitude.is_some()) {
+        features.push(geo::feature(
+            geo::point_geometry(s.latitude, s.longitude),
+            json!({ "kind": "sensor", "id": s.id, "name": s.name, "sensor_type": s.sensor_type, "zone_id": s.zone_id }),
+        ));
+    }
+    for p in pumps.iter().filter(|p| p.latitude.is_some()) {
+        features.push(geo::feature(
+            geo::point_geometry(p.latitude, p.longitude),
+            json!({ "kind": "pump", "id": p.id, "name": p.name }),
+        ));
+    }
+    for v in valves.iter().filter(|v| v.latitude.is_some()) {
+        features.push(geo::feature(
+            geo::point_geometry(v.latitude, v.longitude),
+            json!({ "kind": "valve", "id": v.id, "name": v.name, "is_open": v.is_open, "zone_id": v.zone_id }),
+        ));
+    }
+
+    // Format GeoJSON mentah (bukan amplop response biasa) agar bisa langsung dipakai library peta
+    Ok(HttpResponse::Ok()
+        .content_type("application/geo+json")
+        .json(json!({ "type": "FeatureCollection", "features": features })))
+}
@@ -0,0 +1,229 @@
+use actix_web::{delete, get, post, web};
+use chrono::{Local, NaiveDateTime};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    audit,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::{
+        harvest::{self, QualityGrade, YieldUnit},
+        plant, seed,
+    },
+    permissions,
+    response,
+    soft_delete::SoftDelete,
+    tenancy::{self, Access},
+    validation::Validator,
+    yields,
+};
+
+fn default_final() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+pub struct CreateHarvestRequest {
+    // Default: sekarang
+    pub harvested_at: Option<NaiveDateTime>,
+    pub quantity: f64,
+    pub unit: YieldUnit,
+    pub quality_grade: Option<QualityGrade>,
+    pub notes: Option<String>,
+    // Panen terakhir menutup tanaman; isi false untuk panen bertahap
+    #[serde(default = "default_final", rename = "final")]
+    pub is_final: bool,
+}
+
+#[derive(Deserialize)]
+pub struct YieldReportQuery {
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
+async fn plant_access(data: &AppState, auth: &AuthUser, plant_id: i32, access: Access) -> Result<plant::Model, ApiError> {
+    let p = plant::Entity::find_active_by_id(plant_id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Plant"))?;
+    tenancy::land_access(&data.db, auth, p.land_id, access).await?;
+    Ok(p)
+}
+
+// Catat hasil panen; panen terakhir sekaligus menutup tanaman
+#[post("/plants/{plant_id}/harvests")]
+pub async fn create_harvest(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    form: web::Json<CreateHarvestRequest>,
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let p = plant_access(&data, &auth, path.into_inner(), Access::Write).await?;
+    if p.closed_at.is_some() {
+        return Err(ApiError::conflict("Plant has already been harvested").with_code("plant_closed"));
+    }
+
+    let now = Local::now().naive_local();
+    let harvested_at = form.harvested_at.unwrap_or(now);
+
+    let mut v = Validator::new(&data.db);
+    if !form.quantity.is_finite() || form.quantity <= 0.0 {
+        v.add("quantity", "Must be greater than 0");
+    }
+    if harvested_at < p.planted_at {
+        v.add("harvested_at", "Cannot be before the plant was planted");
+    }
+    if harvested_at > now {
+        v.add("harvested_at", "Cannot be in the future");
+    }
+    if let Some(notes) = &form.notes {
+        v.max_length("notes", notes, 2000);
+    }
+    v.finish()?;
+
+    let txn = data.db.begin().await?;
+    let h = harvest::ActiveModel {
+        plant_id: Set(p.id),
+        harvested_at: Set(harvested_at),
+        quantity: Set(form.quantity),
+        unit: Set(form.unit),
+        quality_grade: Set(form.quality_grade),
+        notes: Set(form.notes.clone()),
+        closes_plant: Set(form.is_final),
+        recorded_by: Set(Some(auth.id)),
+        created_at: Set(now),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    let closed = if form.is_final {
+        let mut active: plant::ActiveModel = p.clone().into();
+        active.closed_at = Set(Some(harvested_at));
+        Some(active.update(&txn).await?)
+    } else {
+        None
+    };
+    txn.commit().await?;
+
+    audit::created(&data.db, &auth, "harvest", h.id, &h).await;
+    if let Some(closed) = &closed {
+        audit::updated(&data.db, &auth, "plant", closed.id, &p, closed).await;
+    }
+
+    Ok(response::ok_with_message(
+        "Harvest recorded successfully",
+        serde_json::json!({
+            "harvest": h,
+            "plant": closed.unwrap_or(p)
+        }),
+    ))
+}
+
+#[get("/plants/{plant_id}/harvests")]
+pub async fn get_harvests_by_plant(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    let p = plant_access(&data, &auth, path.into_inner(), Access::Read).await?;
+
+    let harvests = harvest::Entity::find()
+        .filter(harvest::Column::PlantId.eq(p.id))
+        .order_by_asc(harvest::Column::HarvestedAt)
+        .all(&data.db)
+        .await?;
+
+    Ok(response::ok(harvests))
+}
+
+// Hapus catatan panen; jika panen itu yang menutup tanaman, tanaman dibuka kembali
+#[delete("/harvests/{id}")]
+pub async fn delete_harvest(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let h = harvest::Entity::find_by_id(path.into_inner())
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Harvest"))?;
+    let p = plant_access(&data, &auth, h.plant_id, Access::Write).await?;
+
+    let txn = data.db.begin().await?;
+    harvest::Entity::delete_by_id(h.id).exec(&txn).await?;
+    let reopened = if h.closes_plant && p.closed_at.is_some() {
+        let mut active: plant::ActiveModel = p.clone().into();
+        active.closed_at = Set(None);
+        Some(active.update(&txn).await?)
+    } else {
+        None
+    };
+    txn.commit().await?;
+
+    audit::deleted(&data.db, &auth, "harvest", h.id, &h).await;
+    if let Some(reopened) = &reopened {
+        audit::updated(&data.db, &auth, "plant", reopened.id, &p, reopened).await;
+    }
+
+    Ok(response::message("Harvest deleted successfully"))
+}
+
+// Hasil panen land per musim tanam, relatif terhadap luas land & air yang dipakai
+#[get("/lands/{land_id}/yield-report")]
+pub async fn get_land_yield_report(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    query: web::Query<YieldReportQuery>,
+) -> ApiResult {
+    let land_id = path.into_inner();
+    tenancy::land_access(&data.db, &auth, land_id, Access::Read).await?;
+
+    let mut select = yields::harvests_with_plants().filter(plant::Column::LandId.eq(land_id));
+    if let Some(from) = query.from {
+        select = select.filter(harvest::Column::HarvestedAt.gte(from));
+    }
+    if let Some(to) = query.to {
+        select = select.filter(harvest::Column::HarvestedAt.lte(to));
+    }
+    let rows = select.all(&data.db).await?;
+
+    Ok(response::ok(yields::report(&data.db, rows).await?))
+}
+
+// Hasil panen satu benih di semua land yang bisa dilihat caller
+#[get("/seeds/{seed_id}/yield-report")]
+pub async fn get_seed_yield_report(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    query: web::Query<YieldReportQuery>,
+) -> ApiResult {
+    let seed_id = path.into_inner();
+    let s = seed::Entity::find_by_id(seed_id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Seed"))?;
+
+    let mut select = yields::harvests_with_plants().filter(plant::Column::SeedId.eq(seed_id));
+    if let Some(ids) = tenancy::visible_land_ids(&data.db, &auth).await? {
+        select = select.filter(plant::Column::LandId.is_in(ids));
+    }
+    if let Some(from) = query.from {
+        select = select.filter(harvest::Column::HarvestedAt.gte(from));
+    }
+    if let Some(to) = query.to {
+        select = select.filter(harvest::Column::HarvestedAt.lte(to));
+    }
+    let rows = select.all(&data.db).await?;
+
+    Ok(response::ok(serde_json::json!({
+        "seed": s,
+        "lands": yields::report(&data.db, rows).await?
+    })))
+}
@@ -0,0 +1,133 @@
+use actix_web::{get, post, web};
+use chrono::Local;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    audit,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    importer,
+    models::import_job::{self, ImportStatus},
+    permissions,
+    response,
+    tenancy::{self, Access},
+};
+
+// Batas ukuran body upload CSV (dipasang sebagai PayloadConfig di main)
+pub const MAX_UPLOAD_BYTES: usize = 20 * 1024 * 1024;
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    pub filename: Option<String>,
+}
+
+// Job beserta persentase progress
+fn job_json(job: &import_job::Model) -> serde_json::Value {
+    let percent = if job.total_rows > 0 {
+        (job.processed_rows as f64 * 100.0 / job.total_rows as f64).round()
+    } else {
+        100.0
+    };
+    let mut value = serde_json::json!(job);
+    value["progress_percent"] = serde_json::json!(percent);
+    value
+}
+
+// Job import beserta cek akses ke land-nya
+async fn job_access(data: &AppState, auth: &AuthUser, job_id: i32, access: Access) -> Result<import_job::Model, ApiError> {
+    let job = import_job::Entity::find_by_id(job_id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Import job"))?;
+    tenancy::land_access(&data.db, auth, job.land_id, access).await?;
+    Ok(job)
+}
+
+// Body berupa CSV mentah dengan kolom timestamp, sensor (nama atau id) dan value.
+// Job diproses di latar belakang; pantau lewat GET /imports/{id}.
+#[post("/lands/{land_id}/sensor-history/import")]
+pub async fn import_sensor_history(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    query: web::Query<ImportQuery>,
+    body: web::Bytes,
+) -> ApiResult {
+    auth.require(permissions::SENSOR_INGEST)?;
+    let land_id = path.into_inner();
+    tenancy::land_access(&data.db, &auth, land_id, Access::Write).await?;
+
+    let payload = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::bad_request("CSV must be UTF-8 encoded").with_code("invalid_csv"))?;
+    let total_rows = importer::inspect(&payload).map_err(|e| ApiError::bad_request(e).with_code("invalid_csv"))?;
+
+    let job = import_job::ActiveModel {
+        land_id: Set(land_id),
+        created_by: Set(Some(auth.id)),
+        filename: Set(query.filename.clone()),
+        status: Set(ImportStatus::Pending),
+        payload: Set(payload),
+        total_rows: Set(total_rows),
+        processed_rows: Set(0),
+        imported_rows: Set(0),
+        duplicate_rows: Set(0),
+        error_count: Set(0),
+        errors: Set(serde_json::json!([])),
+        created_at: Set(Local::now().naive_local()),
+        ..Default::default()
+    }
+    .insert(&data.db)
+    .await?;
+
+    audit::created(&data.db, &auth, "import_job", job.id, &job).await;
+    importer::spawn(data.db.clone(), data.retention.clone(), job.id);
+
+    Ok(response::ok_with_message("Import started", job_json(&job)))
+}
+
+#[get("/lands/{land_id}/imports")]
+pub async fn get_imports_by_land(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    let land_id = path.into_inner();
+    tenancy::land_access(&data.db, &auth, land_id, Access::Read).await?;
+
+    let jobs = import_job::Entity::find()
+        .filter(import_job::Column::LandId.eq(land_id))
+        .order_by_desc(import_job::Column::CreatedAt)
+        .all(&data.db)
+        .await?;
+
+    Ok(response::ok(jobs.iter().map(job_json).collect::<Vec<_>>()))
+}
+
+#[get("/imports/{id}")]
+pub async fn get_import(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    let job = job_access(&data, &auth, path.into_inner(), Access::Read).await?;
+    Ok(response::ok(job_json(&job)))
+}
+
+// Lanjutkan job yang gagal dari baris terakhir yang sudah tersimpan
+#[post("/imports/{id}/resume")]
+pub async fn resume_import(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    auth.require(permissions::SENSOR_INGEST)?;
+    let job = job_access(&data, &auth, path.into_inner(), Access::Write).await?;
+    if job.status != ImportStatus::Failed {
+        return Err(ApiError::conflict("Only failed imports can be resumed").with_code("not_failed"));
+    }
+
+    importer::spawn(data.db.clone(), data.retention.clone(), job.id);
+    Ok(response::ok_with_message("Import resumed", job_json(&job)))
+}
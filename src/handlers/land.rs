@@ -1,14 +1,31 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, DeleteResult};
+use actix_web::{get, post, put, delete, web};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
 use serde::Deserialize;
 
-use crate::{AppState, models::land};
+use crate::{
+    AppState,
+    audit,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::land,
+    permissions,
+    response,
+    soft_delete::{self, SoftDelete},
+    tenancy::{self, Access},
+    geo::{self, Ring},
+    validation::Validator,
+    zones,
+};
 
 #[derive(Deserialize)]
 pub struct CreateLandRequest {
     pub location_name: String,
     pub size: f64,
     pub user_id: i32,
+    pub organization_id: i32,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub boundary: Option<Ring>,
 }
 
 #[derive(Deserialize)]
@@ -16,103 +33,160 @@ pub struct UpdateLandRequest {
     pub location_name: Option<String>,
     pub size: Option<f64>,
     pub user_id: Option<i32>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub boundary: Option<Ring>,
+    // Hapus boundary yang tersimpan
+    #[serde(default)]
+    pub clear_boundary: bool,
+}
+
+// Bounding box dalam derajat; land dicocokkan lewat titik lokasinya
+#[derive(Deserialize)]
+pub struct LandSearchQuery {
+    pub min_lat: f64,
+    pub min_lng: f64,
+    pub max_lat: f64,
+    pub max_lng: f64,
 }
 
 #[post("/lands")]
 pub async fn create_land(
+    auth: AuthUser,
     data: web::Data<AppState>,
     form: web::Json<CreateLandRequest>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    tenancy::require_org(&auth, form.organization_id, Access::Write, "Organization")?;
+
+    // Penanggung jawab land harus anggota organisasi pemiliknya
+    let mut v = Validator::new(&data.db);
+    v.member_of_org("user_id", form.user_id, form.organization_id).await?;
+    v.coordinates("", form.latitude, form.longitude);
+    let boundary = form.boundary.clone().map(geo::normalize);
+    if let Some(ring) = &boundary {
+        v.polygon("boundary", ring, form.size);
+    }
+    v.finish()?;
+
+    let (latitude, longitude) = geo::point_or_centroid(form.latitude, form.longitude, boundary.as_ref());
     let new_land = land::ActiveModel {
         location_name: Set(form.location_name.clone()),
         size: Set(form.size),
         user_id: Set(form.user_id),
+        organization_id: Set(form.organization_id),
+        latitude: Set(latitude),
+        longitude: Set(longitude),
+        boundary: Set(boundary.as_ref().map(geo::to_json)),
         ..Default::default()
     };
 
-    match new_land.insert(&data.db).await {
-        Ok(l) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "message": "Land created successfully",
-            "data": l
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+    let l = new_land.insert(&data.db).await?;
+    audit::created(&data.db, &auth, "land", l.id, &l).await;
+    Ok(response::ok_with_message("Land created successfully", l))
 }
 
+// Hanya land di organisasi caller yang ikut dikembalikan
 #[get("/users/{user_id}/lands")]
 pub async fn get_user_lands(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
     let user_id = path.into_inner();
 
-    match land::Entity::find()
-        .filter(land::Column::UserId.eq(user_id)) 
+    let lands = tenancy::scope_lands(land::Entity::find_active(), &auth)
+        .filter(land::Column::UserId.eq(user_id))
         .all(&data.db)
-        .await 
-    {
-        Ok(lands) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": lands
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
+        .await?;
+
+    Ok(response::ok(lands))
+}
+
+// Land yang titik lokasinya berada di dalam bounding box (land tanpa koordinat tidak ikut)
+#[get("/lands/search")]
+pub async fn search_lands(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    query: web::Query<LandSearchQuery>,
+) -> ApiResult {
+    let mut v = Validator::new(&data.db);
+    for (field, value, limit) in [
+        ("min_lat", query.min_lat, 90.0),
+        ("max_lat", query.max_lat, 90.0),
+        ("min_lng", query.min_lng, 180.0),
+        ("max_lng", query.max_lng, 180.0),
+    ] {
+        if !(-limit..=limit).contains(&value) {
+            v.add(field, format!("Must be within ±{}", limit));
+        }
+    }
+    if query.min_lat > query.max_lat {
+        v.add("min_lat", "Must not be greater than max_lat");
     }
+    if query.min_lng > query.max_lng {
+        v.add("min_lng", "Must not be greater than max_lng");
+    }
+    v.finish()?;
+
+    let lands = tenancy::scope_lands(land::Entity::find_active(), &auth)
+        .filter(land::Column::Latitude.between(query.min_lat, query.max_lat))
+        .filter(land::Column::Longitude.between(query.min_lng, query.max_lng))
+        .all(&data.db)
+        .await?;
+
+    Ok(response::ok(lands))
 }
 
 #[get("/lands/{id}")]
 pub async fn get_land_by_id(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
-    let id = path.into_inner();
-
-    match land::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(l)) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": l
-        })),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "success": false,
-            "error": "Land not found"
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+) -> ApiResult {
+    let l = tenancy::land_access(&data.db, &auth, path.into_inner(), Access::Read).await?;
+
+    Ok(response::ok(l))
 }
 
 #[put("/lands/{id}")]
 pub async fn update_land(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
     form: web::Json<UpdateLandRequest>,
-) -> impl Responder {
-    let id = path.into_inner();
-
-    let existing_land = match land::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(l)) => l,
-        Ok(None) => {
-            return HttpResponse::NotFound().json(serde_json::json!({
-                "success": false,
-                "error": "Land not found"
-            }))
-        }
-        Err(e) => {
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "success": false,
-                "error": format!("Database error: {:?}", e)
-            }))
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let existing_land = tenancy::land_access(&data.db, &auth, path.into_inner(), Access::Write).await?;
+
+    let mut v = Validator::new(&data.db);
+    if let Some(user_id) = form.user_id {
+        v.member_of_org("user_id", user_id, existing_land.organization_id).await?;
+    }
+    // Land tidak boleh lebih kecil dari total luas zonanya
+    if let Some(size) = form.size {
+        let allocated = zones::allocated_area(&data.db, existing_land.id, None).await?;
+        if size < allocated {
+            v.add("size", format!("Zones already cover {}", allocated));
         }
+    }
+    if form.latitude.is_some() || form.longitude.is_some() {
+        v.coordinates("", form.latitude, form.longitude);
+    }
+    // Boundary (baru atau yang tersimpan) harus tetap cocok dengan luas land
+    let boundary = match (&form.boundary, form.clear_boundary) {
+        (Some(ring), _) => Some(geo::normalize(ring.clone())),
+        (None, true) => None,
+        (None, false) => existing_land.boundary.as_ref().and_then(geo::from_json),
     };
+    if let Some(ring) = &boundary
+        && (form.boundary.is_some() || form.size.is_some())
+    {
+        v.polygon("boundary", ring, form.size.unwrap_or(existing_land.size));
+    }
+    v.finish()?;
 
+    let before = existing_land.clone();
     let mut active_model: land::ActiveModel = existing_land.into();
 
     if let Some(location_name) = &form.location_name {
@@ -127,54 +201,63 @@ pub async fn update_land(
         active_model.user_id = Set(user_id);
     }
 
-    match active_model.update(&data.db).await {
-        Ok(l) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "message": "Land updated successfully",
-            "data": l
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
+    if form.boundary.is_some() || form.clear_boundary {
+        active_model.boundary = Set(boundary.as_ref().map(geo::to_json));
+    }
+    if form.latitude.is_some() {
+        active_model.latitude = Set(form.latitude);
+        active_model.longitude = Set(form.longitude);
+    } else if form.boundary.is_some()
+        && let Some(ring) = &boundary
+    {
+        let (lat, lng) = geo::centroid(ring);
+        active_model.latitude = Set(Some(lat));
+        active_model.longitude = Set(Some(lng));
     }
+
+    let l = active_model.update(&data.db).await?;
+    audit::updated(&data.db, &auth, "land", l.id, &before, &l).await;
+    Ok(response::ok_with_message("Land updated successfully", l))
 }
 
 #[delete("/lands/{id}")]
 pub async fn delete_land(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
-    let id = path.into_inner();
-
-    match land::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(l)) => {
-            let active_land: land::ActiveModel = l.into();
-
-            match active_land.delete(&data.db).await {
-                Ok(DeleteResult { rows_affected }) if rows_affected > 0 => {
-                    HttpResponse::Ok().json(serde_json::json!({
-                        "success": true,
-                        "message": "Land deleted successfully"
-                    }))
-                }
-                Ok(_) => HttpResponse::NotFound().json(serde_json::json!({
-                    "success": false,
-                    "error": "Land not found or already deleted"
-                })),
-                Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-                    "success": false,
-                    "error": format!("Database error: {:?}", e)
-                })),
-            }
-        }
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "success": false,
-            "error": "Land not found"
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
-}
\ No newline at end of file
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let l = tenancy::land_access(&data.db, &auth, path.into_inner(), Access::Manage).await?;
+
+    // Soft delete: sensor, tanaman, pompa, katup & automation di land ini ikut masuk trash
+    let txn = data.db.begin().await?;
+    soft_delete::delete_land(&txn, l.id, soft_delete::now()).await?;
+    txn.commit().await?;
+    audit::deleted(&data.db, &auth, "land", l.id, &l).await;
+
+    Ok(response::message("Land deleted successfully"))
+}
+
+#[post("/lands/{id}/restore")]
+pub async fn restore_land(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let l = tenancy::land_access_with_deleted(&data.db, &auth, path.into_inner(), Access::Manage).await?;
+    let Some(deleted_at) = l.deleted_at else {
+        return Err(ApiError::conflict("Land is not deleted").with_code("not_deleted"));
+    };
+
+    let txn = data.db.begin().await?;
+    soft_delete::restore_land(&txn, l.id, deleted_at).await?;
+    txn.commit().await?;
+
+    let restored = land::Entity::find_by_id(l.id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Land"))?;
+    audit::updated(&data.db, &auth, "land", l.id, &l, &restored).await;
+    Ok(response::ok_with_message("Land restored successfully", restored))
+}
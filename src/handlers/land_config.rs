@@ -0,0 +1,161 @@
+use actix_web::{get, http::header, post, web, HttpRequest, HttpResponse};
+use sea_orm::TransactionTrait;
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    audit,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    land_config::{self, LandConfig},
+    permissions,
+    response,
+    tenancy::{self, Access},
+    validation::Validator,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigFormat {
+    Json,
+    Yaml,
+}
+
+impl ConfigFormat {
+    // Tanpa ?format=, ikuti Content-Type request (default JSON)
+    fn from_request(req: &HttpRequest, format: Option<ConfigFormat>) -> Self {
+        format.unwrap_or_else(|| {
+            let content_type = req
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default();
+            if content_type.contains("yaml") {
+                ConfigFormat::Yaml
+            } else {
+                ConfigFormat::Json
+            }
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ExportConfigQuery {
+    pub format: Option<ConfigFormat>,
+}
+
+#[derive(Deserialize)]
+pub struct ImportConfigQuery {
+    pub organization_id: i32,
+    // Penanggung jawab land baru; default caller
+    pub user_id: Option<i32>,
+    pub format: Option<ConfigFormat>,
+    // Hanya validasi dokumen, tanpa menyimpan apa pun
+    #[serde(default)]
+    pub validate_only: bool,
+}
+
+// Konfigurasi land (sensor, pompa, katup, tanaman & automation) sebagai dokumen yang bisa di-import ulang
+#[get("/lands/{id}/config")]
+pub async fn export_land_config(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    query: web::Query<ExportConfigQuery>,
+) -> ApiResult {
+    let l = tenancy::land_access(&data.db, &auth, path.into_inner(), Access::Read).await?;
+    let doc = land_config::export(&data.db, &l).await?;
+
+    let (body, content_type, extension) = match query.format.unwrap_or(ConfigFormat::Json) {
+        ConfigFormat::Json => (
+            serde_json::to_string_pretty(&doc).map_err(|_| ApiError::internal())?,
+            "application/json",
+            "json",
+        ),
+        ConfigFormat::Yaml => (
+            serde_yaml::to_string(&doc).map_err(|_| ApiError::internal())?,
+            "application/yaml",
+            "yaml",
+        ),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"land-{}.{}\"", l.id, extension),
+        ))
+        .body(body))
+}
+
+// Buat land baru beserta seluruh isinya dari dokumen konfigurasi dalam satu transaksi
+#[post("/lands/import")]
+pub async fn import_land_config(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    req: HttpRequest,
+    query: web::Query<ImportConfigQuery>,
+    body: web::Bytes,
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    tenancy::require_org(&auth, query.organization_id, Access::Write, "Organization")?;
+
+    let doc: LandConfig = match ConfigFormat::from_request(&req, query.format) {
+        ConfigFormat::Json => serde_json::from_slice(&body).map_err(|e| e.to_string()),
+        ConfigFormat::Yaml => serde_yaml::from_slice(&body).map_err(|e| e.to_string()),
+    }
+    .map_err(|e| ApiError::bad_request(e).with_code("invalid_config"))?;
+
+    if !doc.automations.is_empty() {
+        auth.require(permissions::AUTOMATION_WRITE)?;
+    }
+
+    let user_id = query.user_id.unwrap_or(auth.id);
+    let mut v = Validator::new(&data.db);
+    v.member_of_org("user_id", user_id, query.organization_id).await?;
+    let seeds = land_config::validate(&mut v, &data.db, &doc).await?;
+    v.finish()?;
+
+    if query.validate_only {
+        return Ok(response::ok_with_message(
+            "Configuration is valid",
+            serde_json::json!({
+                "zones": doc.zones.len(),
+                "sensors": doc.sensors.len(),
+                "pumps": doc.pumps.len(),
+                "valves": doc.valves.len(),
+                "plants": doc.plants.len(),
+                "automations": doc.automations.len()
+            }),
+        ));
+    }
+
+    let txn = data.db.begin().await?;
+    let created = land_config::apply(&txn, &doc, query.organization_id, user_id, &seeds).await?;
+    txn.commit().await?;
+
+    audit::created(&data.db, &auth, "land", created.land.id, &created.land).await;
+    for z in &created.zones {
+        audit::created(&data.db, &auth, "zone", z.id, z).await;
+    }
+    for s in &created.sensors {
+        audit::created(&data.db, &auth, "sensor", s.id, s).await;
+    }
+    for p in &created.pumps {
+        audit::created(&data.db, &auth, "pump", p.id, p).await;
+    }
+    for x in &created.valves {
+        audit::created(&data.db, &auth, "valve", x.id, x).await;
+    }
+    for p in &created.plants {
+        audit::created(&data.db, &auth, "plant", p.id, p).await;
+    }
+    for d in &created.derived_sensors {
+        audit::created(&data.db, &auth, "derived_sensor", d.id, d).await;
+    }
+    for a in &created.automations {
+        audit::created(&data.db, &auth, "automation", a.id, a).await;
+    }
+
+    Ok(response::ok_with_message("Land imported successfully", created))
+}
@@ -0,0 +1,132 @@
+use actix_session::Session;
+use actix_web::{get, put, web};
+use bcrypt::verify;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    audit,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    handlers::{
+        auth::session_user_id,
+        user::{apply_profile, audit_snapshots, check_password_policy, hash_password, user_json, validate_profile, ProfileFields},
+    },
+    models::{land, notification, user},
+    response,
+    session_store::revoke_user_sessions,
+    soft_delete::SoftDelete,
+    tenancy,
+    tokens::{issue_token_pair, revoke_user_refresh_tokens},
+    validation::Validator,
+};
+
+#[derive(Deserialize)]
+pub struct UpdateProfileRequest {
+    pub username: Option<String>,
+    #[serde(flatten)]
+    pub profile: ProfileFields,
+}
+
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+async fn find_me(data: &AppState, auth: &AuthUser) -> Result<user::Model, ApiError> {
+    user::Entity::find_active_by_id(auth.id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::unauthorized("Not authenticated"))
+}
+
+#[get("/me")]
+pub async fn me(auth: AuthUser, data: web::Data<AppState>) -> ApiResult {
+    let u = find_me(&data, &auth).await?;
+
+    let mut result = user_json(&u);
+    result["role"] = serde_json::json!(auth.role);
+    Ok(response::ok(result))
+}
+
+// Role, status & password tidak bisa diubah lewat sini
+#[put("/me")]
+pub async fn update_me(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    form: web::Json<UpdateProfileRequest>,
+) -> ApiResult {
+    let mut v = Validator::new(&data.db);
+    validate_profile(&mut v, form.username.as_deref(), &form.profile);
+    v.finish()?;
+
+    let existing_user = find_me(&data, &auth).await?;
+    let before = user_json(&existing_user);
+    let mut active_model: user::ActiveModel = existing_user.into();
+
+    if let Some(username) = &form.username {
+        active_model.username = Set(username.clone());
+    }
+    apply_profile(&mut active_model, &form.profile);
+
+    let u = active_model.update(&data.db).await?;
+    audit::updated(&data.db, &auth, "user", u.id, &before, &user_json(&u)).await;
+    Ok(response::ok_with_message("Profile updated successfully", user_json(&u)))
+}
+
+#[put("/me/password")]
+pub async fn change_my_password(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    session: Session,
+    form: web::Json<ChangePasswordRequest>,
+) -> ApiResult {
+    let existing_user = find_me(&data, &auth).await?;
+
+    if !verify(&form.current_password, &existing_user.password).unwrap_or(false) {
+        return Err(ApiError::unauthorized("Current password is incorrect").with_code("invalid_credentials"));
+    }
+    check_password_policy(&data, &form.new_password, &existing_user.username)?;
+
+    let before = existing_user.clone();
+    let mut active_model: user::ActiveModel = existing_user.into();
+    active_model.password = Set(hash_password(&form.new_password)?);
+    let u = active_model.update(&data.db).await?;
+    let (b, a) = audit_snapshots(&before, &u);
+    audit::updated(&data.db, &auth, "user", u.id, &b, &a).await;
+
+    // Semua session & refresh token lama dicabut, lalu caller langsung diberi kredensial baru
+    revoke_user_sessions(&data.db, u.id).await?;
+    revoke_user_refresh_tokens(&data.db, u.id).await?;
+
+    if session_user_id(&session) == Some(u.id) {
+        session.renew();
+        return Ok(response::message("Password changed successfully"));
+    }
+
+    let tokens = issue_token_pair(&data.db, &data.security, u.id).await?;
+    Ok(response::ok_with_message("Password changed successfully", serde_json::json!({ "token": tokens })))
+}
+
+#[get("/me/lands")]
+pub async fn get_my_lands(auth: AuthUser, data: web::Data<AppState>) -> ApiResult {
+    let lands = tenancy::scope_lands(land::Entity::find_active(), &auth)
+        .filter(land::Column::UserId.eq(auth.id))
+        .all(&data.db)
+        .await?;
+
+    Ok(response::ok(lands))
+}
+
+#[get("/me/notifications")]
+pub async fn get_my_notifications(auth: AuthUser, data: web::Data<AppState>) -> ApiResult {
+    let notes = notification::Entity::find()
+        .filter(notification::Column::UserId.eq(auth.id))
+        .order_by_desc(notification::Column::Id)
+        .all(&data.db)
+        .await?;
+
+    Ok(response::ok(notes))
+}
@@ -1,9 +1,22 @@
 pub mod auth;
+pub mod me;
+pub mod password_reset;
 pub mod user;
+pub mod role;
+pub mod session;
+pub mod organization;
 pub mod land;
+pub mod land_config;
+pub mod zone;
+pub mod geojson;
+pub mod weather;
 pub mod sensor;
+pub mod derived_sensor;
 pub mod sensor_history;
+pub mod export;
+pub mod import;
 pub mod plant;
+pub mod harvest;
 pub mod valve;
 pub mod pump;
 pub mod automation;
@@ -11,4 +24,9 @@ pub mod automation_history;
 pub mod seed;
 pub mod recommendation;
 pub mod pest_control;
-pub mod notification;
\ No newline at end of file
+pub mod task;
+pub mod notification;
+pub mod audit;
+pub mod trash;
+pub mod retention;
+pub mod anomaly;
\ No newline at end of file
@@ -1,44 +1,32 @@
-use actix_web::{get, web, HttpResponse, Responder};
+use actix_web::{get, web};
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
 
-use crate::{AppState, models::notification};
+use crate::{AppState, error::ApiResult, extractors::AuthUser, models::notification, permissions, response, tenancy};
 
 // GET /users/{user_id}/notifications
 #[get("/users/{user_id}/notifications")]
 pub async fn get_notifications_by_user(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
     let user_id = path.into_inner();
+    tenancy::require_user_admin(&data.db, &auth, user_id).await?;
 
     // Cari notifikasi milik user tertentu
-    match notification::Entity::find()
+    let notes = notification::Entity::find()
         .filter(notification::Column::UserId.eq(user_id))
         .all(&data.db)
-        .await
-    {
-        Ok(notes) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": notes
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": e.to_string()
-        })),
-    }
+        .await?;
+
+    Ok(response::ok(notes))
 }
 
-// Opsional: GET All Notifications (Untuk Admin/Debug)
+// Opsional: GET All Notifications (Untuk Admin/Debug), lintas organisasi
 #[get("/notifications")]
-pub async fn get_all_notifications(data: web::Data<AppState>) -> impl Responder {
-    match notification::Entity::find().all(&data.db).await {
-        Ok(notes) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": notes
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": e.to_string()
-        })),
-    }
-}
\ No newline at end of file
+pub async fn get_all_notifications(auth: AuthUser, data: web::Data<AppState>) -> ApiResult {
+    auth.require(permissions::NOTIFICATION_READ_ALL)?;
+    auth.require(permissions::PLATFORM_ADMIN)?;
+    let notes = notification::Entity::find().all(&data.db).await?;
+    Ok(response::ok(notes))
+}
@@ -0,0 +1,333 @@
+use actix_web::{delete, get, post, put, web};
+use chrono::Local;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    audit,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::{
+        land, organization,
+        organization_member::{self, OrgRole},
+        user,
+    },
+    permissions,
+    response,
+    soft_delete::SoftDelete,
+    tenancy::{self, Access},
+    validation::Validator,
+};
+
+#[derive(Deserialize)]
+pub struct CreateOrgRequest {
+    pub name: String,
+    // Owner pertama organisasi (default: caller)
+    pub owner_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateOrgRequest {
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct AddMemberRequest {
+    pub user_id: i32,
+    pub role: OrgRole,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateMemberRequest {
+    pub role: OrgRole,
+}
+
+async fn find_org(data: &AppState, auth: &AuthUser, id: i32, access: Access) -> Result<organization::Model, ApiError> {
+    tenancy::require_org(auth, id, access, "Organization")?;
+    organization::Entity::find_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Organization"))
+}
+
+async fn find_member(data: &AppState, org_id: i32, user_id: i32) -> Result<organization_member::Model, ApiError> {
+    organization_member::Entity::find()
+        .filter(organization_member::Column::OrganizationId.eq(org_id))
+        .filter(organization_member::Column::UserId.eq(user_id))
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Member"))
+}
+
+// Hanya owner (atau super-admin) yang boleh memberi / mencabut role Owner
+fn require_owner_for(auth: &AuthUser, org_id: i32, roles: &[OrgRole]) -> Result<(), ApiError> {
+    if roles.contains(&OrgRole::Owner) && !auth.is_super_admin() && auth.org_role(org_id) != Some(OrgRole::Owner) {
+        return Err(ApiError::forbidden("Only an owner can grant or revoke the owner role"));
+    }
+    Ok(())
+}
+
+// Organisasi harus selalu punya minimal satu owner
+async fn ensure_other_owner(data: &AppState, org_id: i32, user_id: i32) -> Result<(), ApiError> {
+    let other_owners = organization_member::Entity::find()
+        .filter(organization_member::Column::OrganizationId.eq(org_id))
+        .filter(organization_member::Column::Role.eq(OrgRole::Owner))
+        .filter(organization_member::Column::UserId.ne(user_id))
+        .count(&data.db)
+        .await?;
+    if other_owners == 0 {
+        return Err(ApiError::conflict("Organization must keep at least one owner").with_code("last_owner"));
+    }
+    Ok(())
+}
+
+#[post("/organizations")]
+pub async fn create_organization(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    form: web::Json<CreateOrgRequest>,
+) -> ApiResult {
+    auth.require(permissions::PLATFORM_ADMIN)?;
+    let owner_id = form.owner_id.unwrap_or(auth.id);
+
+    let mut v = Validator::new(&data.db);
+    v.max_length("name", &form.name, 100);
+    if user::Entity::find_active_by_id(owner_id).one(&data.db).await?.is_none() {
+        v.add("owner_id", format!("User {} does not exist", owner_id));
+    }
+    v.finish()?;
+
+    let txn = data.db.begin().await?;
+    let org = organization::ActiveModel {
+        name: Set(form.name.trim().to_string()),
+        created_at: Set(Local::now().naive_local()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    let owner = organization_member::ActiveModel {
+        organization_id: Set(org.id),
+        user_id: Set(owner_id),
+        role: Set(OrgRole::Owner),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    txn.commit().await?;
+
+    audit::created(&data.db, &auth, "organization", org.id, &org).await;
+    audit::created(&data.db, &auth, "organization_member", owner.id, &owner).await;
+
+    Ok(response::ok_with_message("Organization created successfully", org))
+}
+
+// Organisasi milik caller beserta role-nya; super-admin melihat semua
+#[get("/organizations")]
+pub async fn get_organizations(auth: AuthUser, data: web::Data<AppState>) -> ApiResult {
+    let mut select = organization::Entity::find().order_by_asc(organization::Column::Name);
+    if !auth.is_super_admin() {
+        let ids: Vec<i32> = auth.memberships.iter().map(|(org, _)| *org).collect();
+        select = select.filter(organization::Column::Id.is_in(ids));
+    }
+
+    let orgs = select.all(&data.db).await?;
+    let result: Vec<_> = orgs
+        .iter()
+        .map(|o| {
+            serde_json::json!({
+                "id": o.id,
+                "name": o.name,
+                "created_at": o.created_at,
+                "my_role": auth.org_role(o.id)
+            })
+        })
+        .collect();
+    Ok(response::ok(result))
+}
+
+#[get("/organizations/{id}")]
+pub async fn get_organization_by_id(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    Ok(response::ok(find_org(&data, &auth, path.into_inner(), Access::Read).await?))
+}
+
+#[put("/organizations/{id}")]
+pub async fn update_organization(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    form: web::Json<UpdateOrgRequest>,
+) -> ApiResult {
+    let org = find_org(&data, &auth, path.into_inner(), Access::Manage).await?;
+
+    let mut v = Validator::new(&data.db);
+    v.max_length("name", &form.name, 100);
+    v.finish()?;
+
+    let before = org.clone();
+    let mut active: organization::ActiveModel = org.into();
+    active.name = Set(form.name.trim().to_string());
+    let org = active.update(&data.db).await?;
+    audit::updated(&data.db, &auth, "organization", org.id, &before, &org).await;
+    Ok(response::ok_with_message("Organization updated successfully", org))
+}
+
+// Organisasi yang masih punya land tidak bisa dihapus (termasuk land di trash yang belum di-purge)
+#[delete("/organizations/{id}")]
+pub async fn delete_organization(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    auth.require(permissions::PLATFORM_ADMIN)?;
+    let org = find_org(&data, &auth, path.into_inner(), Access::Manage).await?;
+
+    let lands = land::Entity::find()
+        .filter(land::Column::OrganizationId.eq(org.id))
+        .count(&data.db)
+        .await?;
+    if lands > 0 {
+        return Err(ApiError::conflict(format!("Organization still owns {} lands", lands)).with_code("organization_in_use"));
+    }
+
+    organization::Entity::delete_by_id(org.id).exec(&data.db).await?;
+    audit::deleted(&data.db, &auth, "organization", org.id, &org).await;
+    Ok(response::message("Organization deleted successfully"))
+}
+
+#[get("/organizations/{id}/lands")]
+pub async fn get_organization_lands(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    let org = find_org(&data, &auth, path.into_inner(), Access::Read).await?;
+    let lands = land::Entity::find_active()
+        .filter(land::Column::OrganizationId.eq(org.id))
+        .all(&data.db)
+        .await?;
+    Ok(response::ok(lands))
+}
+
+#[get("/organizations/{id}/members")]
+pub async fn get_members(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    let org = find_org(&data, &auth, path.into_inner(), Access::Read).await?;
+
+    let members = organization_member::Entity::find()
+        .filter(organization_member::Column::OrganizationId.eq(org.id))
+        .find_also_related(user::Entity)
+        .all(&data.db)
+        .await?;
+
+    let result: Vec<_> = members
+        .into_iter()
+        .map(|(m, u)| {
+            serde_json::json!({
+                "user_id": m.user_id,
+                "username": u.as_ref().map(|u| u.username.clone()),
+                "full_name": u.and_then(|u| u.full_name),
+                "role": m.role
+            })
+        })
+        .collect();
+    Ok(response::ok(result))
+}
+
+#[post("/organizations/{id}/members")]
+pub async fn add_member(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    form: web::Json<AddMemberRequest>,
+) -> ApiResult {
+    let org = find_org(&data, &auth, path.into_inner(), Access::Manage).await?;
+    require_owner_for(&auth, org.id, &[form.role])?;
+
+    if user::Entity::find_active_by_id(form.user_id).one(&data.db).await?.is_none() {
+        let mut v = Validator::new(&data.db);
+        v.add("user_id", format!("User {} does not exist", form.user_id));
+        v.finish()?;
+    }
+    if find_member(&data, org.id, form.user_id).await.is_ok() {
+        return Err(ApiError::conflict("User is already a member").with_code("duplicate"));
+    }
+    // Anggota organisasi lain tidak boleh ditarik masuk begitu saja: setelah jadi anggota,
+    // pengelola organisasi ini ikut bisa mengelola akunnya (lihat `require_user_admin`)
+    let other_orgs = organization_member::Entity::find()
+        .filter(organization_member::Column::UserId.eq(form.user_id))
+        .count(&data.db)
+        .await?;
+    if other_orgs > 0 && !auth.is_super_admin() {
+        return Err(ApiError::forbidden("Only a platform admin can add a user who belongs to another organization")
+            .with_code("user_in_other_organization"));
+    }
+
+    let member = organization_member::ActiveModel {
+        organization_id: Set(org.id),
+        user_id: Set(form.user_id),
+        role: Set(form.role),
+        ..Default::default()
+    }
+    .insert(&data.db)
+    .await?;
+    audit::created(&data.db, &auth, "organization_member", member.id, &member).await;
+    Ok(response::ok_with_message("Member added successfully", member))
+}
+
+#[put("/organizations/{id}/members/{user_id}")]
+pub async fn update_member(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<(i32, i32)>,
+    form: web::Json<UpdateMemberRequest>,
+) -> ApiResult {
+    let (org_id, user_id) = path.into_inner();
+    let org = find_org(&data, &auth, org_id, Access::Manage).await?;
+    let member = find_member(&data, org.id, user_id).await?;
+    require_owner_for(&auth, org.id, &[member.role, form.role])?;
+
+    if member.role == OrgRole::Owner && form.role != OrgRole::Owner {
+        ensure_other_owner(&data, org.id, user_id).await?;
+    }
+
+    let before = member.clone();
+    let mut active: organization_member::ActiveModel = member.into();
+    active.role = Set(form.role);
+    let member = active.update(&data.db).await?;
+    audit::updated(&data.db, &auth, "organization_member", member.id, &before, &member).await;
+    Ok(response::ok_with_message("Member updated successfully", member))
+}
+
+// Pengelola mengeluarkan anggota, atau anggota keluar sendiri
+#[delete("/organizations/{id}/members/{user_id}")]
+pub async fn remove_member(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<(i32, i32)>,
+) -> ApiResult {
+    let (org_id, user_id) = path.into_inner();
+    let access = if user_id == auth.id { Access::Read } else { Access::Manage };
+    let org = find_org(&data, &auth, org_id, access).await?;
+    let member = find_member(&data, org.id, user_id).await?;
+
+    if member.role == OrgRole::Owner {
+        if user_id != auth.id {
+            require_owner_for(&auth, org.id, &[member.role])?;
+        }
+        ensure_other_owner(&data, org.id, user_id).await?;
+    }
+
+    organization_member::Entity::delete_by_id(member.id).exec(&data.db).await?;
+    audit::deleted(&data.db, &auth, "organization_member", member.id, &member).await;
+    Ok(response::message("Member removed successfully"))
+}
@@ -0,0 +1,160 @@
+use actix_web::{post, web, HttpRequest};
+use chrono::{Duration, Local};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    audit,
+    error::{ApiError, ApiResult},
+    handlers::{
+        auth::client_ip,
+        user::{audit_snapshots, check_password_policy, hash_password},
+    },
+    messaging::OutboundMessage,
+    models::{audit_log::AuditAction, password_reset_token, user},
+    response,
+    security::{random_token, sha256_hex},
+    session_store::revoke_user_sessions,
+    soft_delete::SoftDelete,
+    tokens::revoke_user_refresh_tokens,
+};
+
+#[derive(Deserialize)]
+pub struct ResetRequest {
+    pub username: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResetConfirmRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+// Tandai semua token reset milik user yang belum terpakai sebagai sudah dipakai
+async fn invalidate_reset_tokens(data: &AppState, user_id: i32) -> Result<u64, ApiError> {
+    let res = password_reset_token::Entity::update_many()
+        .col_expr(password_reset_token::Column::UsedAt, Local::now().naive_local().into())
+        .filter(password_reset_token::Column::UserId.eq(user_id))
+        .filter(password_reset_token::Column::UsedAt.is_null())
+        .exec(&data.db)
+        .await?;
+    Ok(res.rows_affected)
+}
+
+// Respons selalu sama agar tidak bisa dipakai untuk menebak username yang terdaftar
+#[post("/password-reset/request")]
+pub async fn request_password_reset(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    form: web::Json<ResetRequest>,
+) -> ApiResult {
+    let keys = [
+        format!("reset-ip:{}", client_ip(&req)),
+        format!("reset-user:{}", form.username.to_lowercase()),
+    ];
+    if let Err(retry_after) = data.login_throttle.hit(&keys) {
+        return Err(ApiError::too_many_requests(format!(
+            "Too many reset requests, try again in {} seconds",
+            retry_after
+        )));
+    }
+
+    let user = user::Entity::find_active()
+        .filter(user::Column::Username.eq(&form.username))
+        .one(&data.db)
+        .await?;
+
+    if let Some(u) = user {
+        // Hanya token terbaru yang berlaku
+        invalidate_reset_tokens(&data, u.id).await?;
+
+        let token = random_token(48);
+        let now = Local::now().naive_local();
+        let ttl = data.security.password_reset_ttl_minutes;
+        password_reset_token::ActiveModel {
+            token_hash: Set(sha256_hex(&token)),
+            user_id: Set(u.id),
+            created_at: Set(now),
+            expires_at: Set(now + Duration::minutes(ttl)),
+            used_at: Set(None),
+            ..Default::default()
+        }
+        .insert(&data.db)
+        .await?;
+
+        let message = OutboundMessage {
+            user_id: u.id,
+            username: u.username,
+            email: u.email,
+            phone: u.phone,
+            subject: "Password reset".to_string(),
+            body: format!(
+                "Use this code to reset your password: {}\nThe code expires in {} minutes and can only be used once.",
+                token, ttl
+            ),
+        };
+        // Gagal kirim hanya dicatat, respons ke client tetap sama
+        if let Err(e) = data.messenger.send(&message).await {
+            eprintln!("Failed to send password reset message: {:?}", e);
+        }
+    }
+
+    Ok(response::message(
+        "If the account exists, password reset instructions have been sent",
+    ))
+}
+
+#[post("/password-reset/confirm")]
+pub async fn confirm_password_reset(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    form: web::Json<ResetConfirmRequest>,
+) -> ApiResult {
+    let invalid = || ApiError::bad_request("Invalid or expired reset token").with_code("invalid_token");
+    let now = Local::now().naive_local();
+
+    let row = password_reset_token::Entity::find()
+        .filter(password_reset_token::Column::TokenHash.eq(sha256_hex(&form.token)))
+        .filter(password_reset_token::Column::UsedAt.is_null())
+        .filter(password_reset_token::Column::ExpiresAt.gt(now))
+        .one(&data.db)
+        .await?
+        .ok_or_else(invalid)?;
+
+    let existing_user = user::Entity::find_active_by_id(row.user_id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(invalid)?;
+
+    // Cek policy sebelum token dipakai, supaya token tidak hangus karena password ditolak
+    check_password_policy(&data, &form.new_password, &existing_user.username)?;
+
+    // Klaim token secara atomik: request paralel dengan token yang sama hanya satu yang lolos
+    let claimed = password_reset_token::Entity::update_many()
+        .col_expr(password_reset_token::Column::UsedAt, now.into())
+        .filter(password_reset_token::Column::Id.eq(row.id))
+        .filter(password_reset_token::Column::UsedAt.is_null())
+        .exec(&data.db)
+        .await?;
+    if claimed.rows_affected == 0 {
+        return Err(invalid());
+    }
+
+    let before = existing_user.clone();
+    let mut active_model: user::ActiveModel = existing_user.into();
+    active_model.password = Set(hash_password(&form.new_password)?);
+    active_model.failed_login_attempts = Set(0);
+    active_model.locked_until = Set(None);
+    let u = active_model.update(&data.db).await?;
+
+    // Tidak ada session di sini; pelaku dicatat sebagai pemilik token
+    let (b, a) = audit_snapshots(&before, &u);
+    audit::record(&data.db, Some(u.id), Some(&client_ip(&req)), AuditAction::Update, "user", u.id, Some(b), Some(a)).await;
+
+    invalidate_reset_tokens(&data, u.id).await?;
+    revoke_user_sessions(&data.db, u.id).await?;
+    revoke_user_refresh_tokens(&data.db, u.id).await?;
+
+    Ok(response::message("Password has been reset"))
+}
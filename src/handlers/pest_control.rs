@@ -1,8 +1,8 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, DeleteResult};
+use actix_web::{get, post, put, delete, web};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
 use serde::Deserialize;
 
-use crate::{AppState, models::pest_control::{self, PestControlStatus}};
+use crate::{AppState, audit, error::{ApiError, ApiResult}, extractors::AuthUser, models::pest_control::{self, PestControlStatus}, permissions, response, tenancy::{self, Access}};
 
 #[derive(Deserialize)]
 pub struct CreatePestRequest {
@@ -19,9 +19,13 @@ pub struct UpdatePestRequest {
 
 #[post("/pest-controls")]
 pub async fn create_pest_control(
+    auth: AuthUser,
     data: web::Data<AppState>,
     form: web::Json<CreatePestRequest>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::PEST_CONTROL_WRITE)?;
+    tenancy::land_access(&data.db, &auth, form.land_id, Access::Write).await?;
+
     let new_pest = pest_control::ActiveModel {
         name: Set(form.name.clone()),
         status: Set(form.status.clone()),
@@ -29,70 +33,65 @@ pub async fn create_pest_control(
         ..Default::default()
     };
 
-    match new_pest.insert(&data.db).await {
-        Ok(res) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": res })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
+    let p = new_pest.insert(&data.db).await?;
+    audit::created(&data.db, &auth, "pest_control", p.id, &p).await;
+    Ok(response::ok(p))
 }
 
 // Ganti get_pest_controls biasa dengan get_pest_controls_by_land
 #[get("/lands/{land_id}/pest-controls")]
 pub async fn get_pest_controls_by_land(
-    data: web::Data<AppState>, 
+    auth: AuthUser,
+    data: web::Data<AppState>,
     path: web::Path<i32>
-) -> impl Responder {
+) -> ApiResult {
     let land_id = path.into_inner();
-    
+    tenancy::land_access(&data.db, &auth, land_id, Access::Read).await?;
+
     // Filter berdasarkan Land ID
-    match pest_control::Entity::find()
+    let res = pest_control::Entity::find()
         .filter(pest_control::Column::LandId.eq(land_id))
         .all(&data.db)
-        .await 
-    {
-        Ok(res) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": res })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
+        .await?;
+
+    Ok(response::ok(res))
 }
 
 #[get("/pest-controls/{id}")]
-pub async fn get_pest_control_by_id(data: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
-    match pest_control::Entity::find_by_id(path.into_inner()).one(&data.db).await {
-        Ok(Some(res)) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": res })),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({ "success": false, "error": "Not found" })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
+pub async fn get_pest_control_by_id(auth: AuthUser, data: web::Data<AppState>, path: web::Path<i32>) -> ApiResult {
+    let res = pest_control::Entity::find_by_id(path.into_inner()).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Pest control"))?;
+    tenancy::land_access(&data.db, &auth, res.land_id, Access::Read).await?;
+    Ok(response::ok(res))
 }
 
 #[put("/pest-controls/{id}")]
 pub async fn update_pest_control(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
     form: web::Json<UpdatePestRequest>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::PEST_CONTROL_WRITE)?;
     let id = path.into_inner();
-    let existing = match pest_control::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(res)) => res,
-        Ok(None) => return HttpResponse::NotFound().json(serde_json::json!({ "success": false, "error": "Not found" })),
-        Err(e) => return HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    };
+    let existing = pest_control::Entity::find_by_id(id).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Pest control"))?;
+    tenancy::land_access(&data.db, &auth, existing.land_id, Access::Write).await?;
 
+    let before = existing.clone();
     let mut active: pest_control::ActiveModel = existing.into();
     if let Some(name) = &form.name { active.name = Set(name.clone()); }
     if let Some(status) = &form.status { active.status = Set(status.clone()); }
 
-    match active.update(&data.db).await {
-        Ok(res) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": res })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
+    let p = active.update(&data.db).await?;
+    audit::updated(&data.db, &auth, "pest_control", p.id, &before, &p).await;
+    Ok(response::ok(p))
 }
 
 #[delete("/pest-controls/{id}")]
-pub async fn delete_pest_control(data: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
-    match pest_control::Entity::delete_by_id(path.into_inner()).exec(&data.db).await {
-        Ok(DeleteResult { rows_affected }) if rows_affected > 0 => {
-            HttpResponse::Ok().json(serde_json::json!({ "success": true, "message": "Deleted" }))
-        }
-        Ok(_) => HttpResponse::NotFound().json(serde_json::json!({ "success": false, "error": "Not found" })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
-}
\ No newline at end of file
+pub async fn delete_pest_control(auth: AuthUser, data: web::Data<AppState>, path: web::Path<i32>) -> ApiResult {
+    auth.require(permissions::PEST_CONTROL_WRITE)?;
+    let existing = pest_control::Entity::find_by_id(path.into_inner()).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Pest control"))?;
+    tenancy::land_access(&data.db, &auth, existing.land_id, Access::Write).await?;
+    pest_control::Entity::delete_by_id(existing.id).exec(&data.db).await?;
+    audit::deleted(&data.db, &auth, "pest_control", existing.id, &existing).await;
+    Ok(response::message("Deleted"))
+}
@@ -1,10 +1,21 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, DeleteResult};
+use actix_web::{get, post, put, delete, web};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
 use serde::Deserialize;
 // Pastikan DateTime sesuai dengan definisi di model (biasanya NaiveDateTime untuk SeaORM)
-use chrono::NaiveDateTime; 
-
-use crate::{AppState, models::plant};
+use chrono::NaiveDateTime;
+
+use crate::{
+    AppState,
+    audit,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::plant,
+    permissions,
+    response,
+    soft_delete::{self, SoftDelete},
+    tenancy::{self, Access},
+    validation::Validator,
+};
 
 // Struct untuk request Create
 #[derive(Deserialize)]
@@ -28,9 +39,16 @@ pub struct UpdatePlantRequest {
 
 #[post("/plants")]
 pub async fn create_plant(
+    auth: AuthUser,
     data: web::Data<AppState>,
     form: web::Json<CreatePlantRequest>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    tenancy::land_access(&data.db, &auth, form.land_id, Access::Write).await?;
+    let mut v = Validator::new(&data.db);
+    v.seed_exists("seed_id", form.seed_id).await?;
+    v.finish()?;
+
     let new_plant = plant::ActiveModel {
         name: Set(form.name.clone()),
         quantity: Set(form.quantity),
@@ -40,135 +58,136 @@ pub async fn create_plant(
         ..Default::default()
     };
 
-    match new_plant.insert(&data.db).await {
-        Ok(p) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "message": "Plant created successfully",
-            "data": p
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+    let p = new_plant.insert(&data.db).await?;
+    audit::created(&data.db, &auth, "plant", p.id, &p).await;
+    Ok(response::ok_with_message("Plant created successfully", p))
 }
 
 #[get("/lands/{land_id}/plants")]
 pub async fn get_plants_by_land(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
     let land_id = path.into_inner();
+    tenancy::land_access(&data.db, &auth, land_id, Access::Read).await?;
 
-    match plant::Entity::find()
+    let plants = plant::Entity::find_active()
         .filter(plant::Column::LandId.eq(land_id))
         .all(&data.db)
-        .await
-    {
-        Ok(plants) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": plants
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+        .await?;
+
+    Ok(response::ok(plants))
 }
 
 #[get("/plants/{id}")]
 pub async fn get_plant_by_id(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
     let id = path.into_inner();
 
-    match plant::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(p)) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": p
-        })),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "success": false,
-            "error": "Plant not found"
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+    let p = plant::Entity::find_active_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Plant"))?;
+    tenancy::land_access(&data.db, &auth, p.land_id, Access::Read).await?;
+
+    Ok(response::ok(p))
 }
 
 #[put("/plants/{id}")]
 pub async fn update_plant(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
     form: web::Json<UpdatePlantRequest>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
     let id = path.into_inner();
 
-    let existing_plant = match plant::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(p)) => p,
-        Ok(None) => return HttpResponse::NotFound().json(serde_json::json!({"success": false, "error": "Plant not found"})),
-        Err(e) => return HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "error": format!("{:?}", e)})),
-    };
+    let existing_plant = plant::Entity::find_active_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Plant"))?;
+
+    // Pindah land butuh akses tulis di land asal dan land tujuan
+    tenancy::land_access(&data.db, &auth, existing_plant.land_id, Access::Write).await?;
+    if let Some(land_id) = form.land_id { tenancy::land_access(&data.db, &auth, land_id, Access::Write).await?; }
+
+    let mut v = Validator::new(&data.db);
+    if let Some(seed_id) = form.seed_id { v.seed_exists("seed_id", seed_id).await?; }
+    v.finish()?;
 
+    let before = existing_plant.clone();
     let mut active_model: plant::ActiveModel = existing_plant.into();
 
     if let Some(name) = &form.name { active_model.name = Set(name.clone()); }
     if let Some(quantity) = form.quantity { active_model.quantity = Set(quantity); }
-    if let Some(land_id) = form.land_id { active_model.land_id = Set(land_id); }
+    // Zona milik land lama tidak ikut pindah
+    if let Some(land_id) = form.land_id && land_id != before.land_id {
+        active_model.land_id = Set(land_id);
+        active_model.zone_id = Set(None);
+    }
     if let Some(seed_id) = form.seed_id { active_model.seed_id = Set(seed_id); } // <--- Ditambahkan
     if let Some(planted_at) = form.planted_at { active_model.planted_at = Set(planted_at); }
 
-    match active_model.update(&data.db).await {
-        Ok(p) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "message": "Plant updated successfully",
-            "data": p
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+    let p = active_model.update(&data.db).await?;
+    audit::updated(&data.db, &auth, "plant", p.id, &before, &p).await;
+    Ok(response::ok_with_message("Plant updated successfully", p))
 }
 
 #[delete("/plants/{id}")]
 pub async fn delete_plant(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
     let id = path.into_inner();
 
-    match plant::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(p)) => {
-            let active_plant: plant::ActiveModel = p.into();
-
-            match active_plant.delete(&data.db).await {
-                Ok(DeleteResult { rows_affected }) if rows_affected > 0 => {
-                    HttpResponse::Ok().json(serde_json::json!({
-                        "success": true,
-                        "message": "Plant deleted successfully"
-                    }))
-                }
-                Ok(_) => HttpResponse::NotFound().json(serde_json::json!({
-                    "success": false,
-                    "error": "Plant not found or already deleted"
-                })),
-                Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-                    "success": false,
-                    "error": format!("Database error: {:?}", e)
-                })),
-            }
-        }
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "success": false,
-            "error": "Plant not found"
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
-}
\ No newline at end of file
+    let p = plant::Entity::find_active_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Plant"))?;
+    tenancy::land_access(&data.db, &auth, p.land_id, Access::Write).await?;
+
+    // Sensor GDD tanaman ini ikut masuk trash
+    let txn = data.db.begin().await?;
+    soft_delete::delete_plant(&txn, id, soft_delete::now()).await?;
+    txn.commit().await?;
+    audit::deleted(&data.db, &auth, "plant", id, &p).await;
+
+    Ok(response::message("Plant deleted successfully"))
+}
+
+#[post("/plants/{id}/restore")]
+pub async fn restore_plant(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let id = path.into_inner();
+
+    let p = plant::Entity::find_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Plant"))?;
+    tenancy::restorable_land(&data.db, &auth, p.land_id).await?;
+    let Some(deleted_at) = p.deleted_at else {
+        return Err(ApiError::conflict("Plant is not deleted").with_code("not_deleted"));
+    };
+
+    let txn = data.db.begin().await?;
+    soft_delete::restore_plant(&txn, id, deleted_at).await?;
+    txn.commit().await?;
+
+    let restored = plant::Entity::find_active_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Plant"))?;
+    audit::updated(&data.db, &auth, "plant", id, &p, &restored).await;
+    Ok(response::ok_with_message("Plant restored successfully", restored))
+}
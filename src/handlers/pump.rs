@@ -1,98 +1,161 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, DeleteResult};
+use actix_web::{get, post, put, delete, web};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
 use serde::Deserialize;
 
-use crate::{AppState, models::pump};
+use crate::{AppState, audit, error::{ApiError, ApiResult}, extractors::AuthUser, models::{automation, pump}, permissions, response, soft_delete::{self, SoftDelete}, tenancy::{self, Access}, validation::Validator};
 
 #[derive(Deserialize)]
 pub struct CreatePumpRequest {
     pub name: String,
     pub land_id: i32,
+    pub flow_rate: Option<f64>,
+    pub max_runtime_seconds: Option<i32>,
+    pub max_daily_volume: Option<f64>,
+    pub cooldown_seconds: Option<i32>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
 }
 
+// PUT mengganti seluruh konfigurasi; batas yang tidak dikirim berarti tidak dibatasi, lokasi yang tidak dikirim dihapus
 #[derive(Deserialize)]
 pub struct UpdatePumpRequest {
     pub name: String,
+    pub flow_rate: Option<f64>,
+    pub max_runtime_seconds: Option<i32>,
+    pub max_daily_volume: Option<f64>,
+    pub cooldown_seconds: Option<i32>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
 }
 
 #[post("/pumps")]
 pub async fn create_pump(
+    auth: AuthUser,
     data: web::Data<AppState>,
     form: web::Json<CreatePumpRequest>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    tenancy::land_access(&data.db, &auth, form.land_id, Access::Write).await?;
+    let mut v = Validator::new(&data.db);
+    v.coordinates("", form.latitude, form.longitude);
+    v.positive("flow_rate", form.flow_rate);
+    v.positive("max_runtime_seconds", form.max_runtime_seconds.map(f64::from));
+    v.positive("max_daily_volume", form.max_daily_volume);
+    v.positive("cooldown_seconds", form.cooldown_seconds.map(f64::from));
+    v.finish()?;
+
     let new_pump = pump::ActiveModel {
         name: Set(form.name.clone()),
         land_id: Set(form.land_id),
+        flow_rate: Set(form.flow_rate),
+        max_runtime_seconds: Set(form.max_runtime_seconds),
+        max_daily_volume: Set(form.max_daily_volume),
+        cooldown_seconds: Set(form.cooldown_seconds),
+        latitude: Set(form.latitude),
+        longitude: Set(form.longitude),
         ..Default::default()
     };
 
-    match new_pump.insert(&data.db).await {
-        Ok(p) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": p })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
+    let p = new_pump.insert(&data.db).await?;
+    audit::created(&data.db, &auth, "pump", p.id, &p).await;
+    Ok(response::ok(p))
 }
 
 #[get("/lands/{land_id}/pumps")]
 pub async fn get_pumps_by_land(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
     let land_id = path.into_inner();
-    match pump::Entity::find().filter(pump::Column::LandId.eq(land_id)).all(&data.db).await {
-        Ok(pumps) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": pumps })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
+    tenancy::land_access(&data.db, &auth, land_id, Access::Read).await?;
+    let pumps = pump::Entity::find_active().filter(pump::Column::LandId.eq(land_id)).all(&data.db).await?;
+    Ok(response::ok(pumps))
 }
 
 #[get("/pumps/{id}")]
 pub async fn get_pump_by_id(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
     let id = path.into_inner();
-    match pump::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(p)) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": p })),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({ "success": false, "error": "Pump not found" })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
+    let p = pump::Entity::find_active_by_id(id).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Pump"))?;
+    tenancy::land_access(&data.db, &auth, p.land_id, Access::Read).await?;
+    Ok(response::ok(p))
 }
 
 #[put("/pumps/{id}")]
 pub async fn update_pump(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
     form: web::Json<UpdatePumpRequest>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
     let id = path.into_inner();
-    let existing = match pump::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(p)) => p,
-        Ok(None) => return HttpResponse::NotFound().finish(),
-        Err(_) => return HttpResponse::InternalServerError().finish(),
-    };
+    let existing = pump::Entity::find_active_by_id(id).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Pump"))?;
+    tenancy::land_access(&data.db, &auth, existing.land_id, Access::Write).await?;
+    let mut v = Validator::new(&data.db);
+    v.coordinates("", form.latitude, form.longitude);
+    v.positive("flow_rate", form.flow_rate);
+    v.positive("max_runtime_seconds", form.max_runtime_seconds.map(f64::from));
+    v.positive("max_daily_volume", form.max_daily_volume);
+    v.positive("cooldown_seconds", form.cooldown_seconds.map(f64::from));
+    v.finish()?;
 
+    let before = existing.clone();
     let mut active: pump::ActiveModel = existing.into();
     active.name = Set(form.name.clone());
+    active.flow_rate = Set(form.flow_rate);
+    active.max_runtime_seconds = Set(form.max_runtime_seconds);
+    active.max_daily_volume = Set(form.max_daily_volume);
+    active.cooldown_seconds = Set(form.cooldown_seconds);
+    active.latitude = Set(form.latitude);
+    active.longitude = Set(form.longitude);
 
-    match active.update(&data.db).await {
-        Ok(p) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": p })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
+    let p = active.update(&data.db).await?;
+    audit::updated(&data.db, &auth, "pump", p.id, &before, &p).await;
+    Ok(response::ok(p))
 }
 
 #[delete("/pumps/{id}")]
 pub async fn delete_pump(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
     let id = path.into_inner();
-    match pump::Entity::delete_by_id(id).exec(&data.db).await {
-        Ok(res) => {
-            if res.rows_affected > 0 {
-                HttpResponse::Ok().json(serde_json::json!({ "success": true }))
-            } else {
-                HttpResponse::NotFound().json(serde_json::json!({ "success": false, "error": "Not found" }))
-            }
-        }
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
-}
\ No newline at end of file
+    let existing = pump::Entity::find_active_by_id(id).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Pump"))?;
+    tenancy::land_access(&data.db, &auth, existing.land_id, Access::Write).await?;
+    // Automation yang memakai pompa ini ikut masuk trash
+    let txn = data.db.begin().await?;
+    soft_delete::delete_device::<pump::Entity, _>(&txn, id, automation::Column::PumpId, soft_delete::now()).await?;
+    txn.commit().await?;
+    audit::deleted(&data.db, &auth, "pump", id, &existing).await;
+    Ok(response::message("Pump deleted successfully"))
+}
+
+#[post("/pumps/{id}/restore")]
+pub async fn restore_pump(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let id = path.into_inner();
+    let existing = pump::Entity::find_by_id(id).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Pump"))?;
+    tenancy::restorable_land(&data.db, &auth, existing.land_id).await?;
+    let Some(deleted_at) = existing.deleted_at else {
+        return Err(ApiError::conflict("Pump is not deleted").with_code("not_deleted"));
+    };
+
+    let txn = data.db.begin().await?;
+    soft_delete::restore_device::<pump::Entity, _>(&txn, id, automation::Column::PumpId, deleted_at).await?;
+    txn.commit().await?;
+
+    let p = pump::Entity::find_active_by_id(id).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Pump"))?;
+    audit::updated(&data.db, &auth, "pump", id, &existing, &p).await;
+    Ok(response::ok_with_message("Pump restored successfully", p))
+}
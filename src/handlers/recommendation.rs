@@ -1,8 +1,8 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use sea_orm::{ActiveModelTrait, EntityTrait, Set, DeleteResult};
+use actix_web::{get, post, put, delete, web};
+use sea_orm::{ActiveModelTrait, EntityTrait, Set};
 use serde::Deserialize;
 
-use crate::{AppState, models::recommendation::{self, RecommendationType}};
+use crate::{AppState, audit, error::{ApiError, ApiResult}, extractors::AuthUser, models::recommendation::{self, RecommendationType}, permissions, response};
 
 // Struct Create: seed_id wajib i32
 #[derive(Deserialize)]
@@ -24,10 +24,12 @@ pub struct UpdateRecRequest {
 
 #[post("/recommendations")]
 pub async fn create_recommendation(
+    auth: AuthUser,
     data: web::Data<AppState>,
     form: web::Json<CreateRecRequest>,
-) -> impl Responder {
-    
+) -> ApiResult {
+    auth.require(permissions::RECOMMENDATION_WRITE)?;
+
     let new_rec = recommendation::ActiveModel {
         name: Set(form.name.clone()),
         description: Set(form.description.clone()),
@@ -36,51 +38,37 @@ pub async fn create_recommendation(
         ..Default::default()
     };
 
-    match new_rec.insert(&data.db).await {
-        Ok(rec) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "message": "Recommendation created successfully",
-            "data": rec
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+    let rec = new_rec.insert(&data.db).await?;
+    audit::created(&data.db, &auth, "recommendation", rec.id, &rec).await;
+    Ok(response::ok_with_message("Recommendation created successfully", rec))
 }
 
 #[get("/recommendations")]
-pub async fn get_recommendations(data: web::Data<AppState>) -> impl Responder {
+pub async fn get_recommendations(data: web::Data<AppState>) -> ApiResult {
     // Optional: Bisa tambah .find().find_with_related(seed::Entity) jika ingin return data seed juga
-    match recommendation::Entity::find().all(&data.db).await {
-        Ok(recs) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": recs })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": format!("{:?}", e) })),
-    }
+    let recs = recommendation::Entity::find().all(&data.db).await?;
+    Ok(response::ok(recs))
 }
 
 #[get("/recommendations/{id}")]
-pub async fn get_recommendation_by_id(data: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
-    match recommendation::Entity::find_by_id(path.into_inner()).one(&data.db).await {
-        Ok(Some(rec)) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": rec })),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({ "success": false, "error": "Not found" })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": format!("{:?}", e) })),
-    }
+pub async fn get_recommendation_by_id(data: web::Data<AppState>, path: web::Path<i32>) -> ApiResult {
+    let rec = recommendation::Entity::find_by_id(path.into_inner()).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Recommendation"))?;
+    Ok(response::ok(rec))
 }
 
 #[put("/recommendations/{id}")]
 pub async fn update_recommendation(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
     form: web::Json<UpdateRecRequest>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::RECOMMENDATION_WRITE)?;
     let id = path.into_inner();
 
-    let existing_rec = match recommendation::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(rec)) => rec,
-        Ok(None) => return HttpResponse::NotFound().json(serde_json::json!({"success": false, "error": "Not found"})),
-        Err(e) => return HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "error": e.to_string()})),
-    };
+    let existing_rec = recommendation::Entity::find_by_id(id).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Recommendation"))?;
 
+    let before = existing_rec.clone();
     let mut active_model: recommendation::ActiveModel = existing_rec.into();
 
     if let Some(name) = &form.name { active_model.name = Set(name.clone()); }
@@ -88,26 +76,16 @@ pub async fn update_recommendation(
     if let Some(rtype) = &form.rec_type { active_model.rec_type = Set(rtype.clone()); }
     if let Some(sid) = form.seed_id { active_model.seed_id = Set(sid); } // Update ID
 
-    match active_model.update(&data.db).await {
-        Ok(rec) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "message": "Recommendation updated successfully",
-            "data": rec
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+    let rec = active_model.update(&data.db).await?;
+    audit::updated(&data.db, &auth, "recommendation", rec.id, &before, &rec).await;
+    Ok(response::ok_with_message("Recommendation updated successfully", rec))
 }
 
 #[delete("/recommendations/{id}")]
-pub async fn delete_recommendation(data: web::Data<AppState>, path: web::Path<i32>) -> impl Responder {
-    match recommendation::Entity::delete_by_id(path.into_inner()).exec(&data.db).await {
-        Ok(DeleteResult { rows_affected }) if rows_affected > 0 => {
-            HttpResponse::Ok().json(serde_json::json!({ "success": true, "message": "Deleted" }))
-        }
-        Ok(_) => HttpResponse::NotFound().json(serde_json::json!({ "success": false, "error": "Not found" })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": format!("{:?}", e) })),
-    }
-}
\ No newline at end of file
+pub async fn delete_recommendation(auth: AuthUser, data: web::Data<AppState>, path: web::Path<i32>) -> ApiResult {
+    auth.require(permissions::RECOMMENDATION_WRITE)?;
+    let existing = recommendation::Entity::find_by_id(path.into_inner()).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Recommendation"))?;
+    recommendation::Entity::delete_by_id(existing.id).exec(&data.db).await?;
+    audit::deleted(&data.db, &auth, "recommendation", existing.id, &existing).await;
+    Ok(response::message("Deleted"))
+}
@@ -0,0 +1,132 @@
+use actix_web::{delete, get, put, web};
+use sea_orm::{ActiveModelTrait, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, Set};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    audit,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::{retention_policy, sensor::SensorType},
+    permissions,
+    response,
+    rollup::Policy,
+    tenancy::{self, Access},
+    validation::Validator,
+};
+
+#[derive(Deserialize)]
+pub struct SetPolicyRequest {
+    pub land_id: Option<i32>,
+    pub sensor_type: Option<SensorType>,
+    pub raw_days: i32,
+    pub hourly_days: i32,
+}
+
+// Policy untuk satu land butuh hak kelola land; policy global hanya super-admin
+async fn require_policy_admin(data: &AppState, auth: &AuthUser, land_id: Option<i32>) -> Result<(), ApiError> {
+    match land_id {
+        Some(land_id) => {
+            auth.require(permissions::LAND_WRITE)?;
+            tenancy::land_access(&data.db, auth, land_id, Access::Manage).await?;
+            Ok(())
+        }
+        None => auth.require(permissions::PLATFORM_ADMIN),
+    }
+}
+
+// Policy global + policy land yang boleh dilihat caller, beserta default dari config
+#[get("/retention-policies")]
+pub async fn get_retention_policies(auth: AuthUser, data: web::Data<AppState>) -> ApiResult {
+    let mut select = retention_policy::Entity::find().order_by_asc(retention_policy::Column::Id);
+    if let Some(land_ids) = tenancy::visible_land_ids(&data.db, &auth).await? {
+        select = select.filter(
+            Condition::any()
+                .add(retention_policy::Column::LandId.is_null())
+                .add(retention_policy::Column::LandId.is_in(land_ids)),
+        );
+    }
+
+    Ok(response::ok(serde_json::json!({
+        "defaults": Policy::defaults(&data.retention),
+        "policies": select.all(&data.db).await?
+    })))
+}
+
+// Buat atau ganti policy untuk kombinasi land + tipe sensor
+#[put("/retention-policies")]
+pub async fn set_retention_policy(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    form: web::Json<SetPolicyRequest>,
+) -> ApiResult {
+    require_policy_admin(&data, &auth, form.land_id).await?;
+
+    let mut v = Validator::new(&data.db);
+    if form.raw_days < 0 {
+        v.add("raw_days", "Must be 0 (keep forever) or more");
+    }
+    if form.hourly_days < 0 {
+        v.add("hourly_days", "Must be 0 (keep forever) or more");
+    }
+    // Agregat per jam dipakai setelah data mentah dihapus, jadi tidak boleh lebih pendek
+    if form.raw_days > 0 && form.hourly_days > 0 && form.hourly_days < form.raw_days {
+        v.add("hourly_days", "Must not be shorter than raw_days");
+    }
+    v.finish()?;
+
+    let existing = retention_policy::Entity::find()
+        .filter(match form.land_id {
+            Some(land_id) => retention_policy::Column::LandId.eq(land_id),
+            None => retention_policy::Column::LandId.is_null(),
+        })
+        .filter(match &form.sensor_type {
+            Some(t) => retention_policy::Column::SensorType.eq(t.clone()),
+            None => retention_policy::Column::SensorType.is_null(),
+        })
+        .one(&data.db)
+        .await?;
+
+    let policy = match existing {
+        Some(before) => {
+            let mut active: retention_policy::ActiveModel = before.clone().into();
+            active.raw_days = Set(form.raw_days);
+            active.hourly_days = Set(form.hourly_days);
+            let policy = active.update(&data.db).await?;
+            audit::updated(&data.db, &auth, "retention_policy", policy.id, &before, &policy).await;
+            policy
+        }
+        None => {
+            let policy = retention_policy::ActiveModel {
+                land_id: Set(form.land_id),
+                sensor_type: Set(form.sensor_type.clone()),
+                raw_days: Set(form.raw_days),
+                hourly_days: Set(form.hourly_days),
+                ..Default::default()
+            }
+            .insert(&data.db)
+            .await?;
+            audit::created(&data.db, &auth, "retention_policy", policy.id, &policy).await;
+            policy
+        }
+    };
+
+    Ok(response::ok_with_message("Retention policy saved", policy))
+}
+
+#[delete("/retention-policies/{id}")]
+pub async fn delete_retention_policy(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    let policy = retention_policy::Entity::find_by_id(path.into_inner())
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Retention policy"))?;
+    require_policy_admin(&data, &auth, policy.land_id).await?;
+
+    retention_policy::Entity::delete_by_id(policy.id).exec(&data.db).await?;
+    audit::deleted(&data.db, &auth, "retention_policy", policy.id, &policy).await;
+    Ok(response::message("Retention policy deleted"))
+}
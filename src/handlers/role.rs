@@ -0,0 +1,184 @@
+use actix_web::{delete, get, post, put, web};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    audit,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::{permission, user, user_role},
+    permissions::{self, role_permission_names, set_role_permissions},
+    response,
+    validation::FieldError,
+};
+
+#[derive(Deserialize)]
+pub struct CreateRoleRequest {
+    pub name: String,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SetPermissionsRequest {
+    pub permissions: Vec<String>,
+}
+
+fn unknown_permissions(unknown: Vec<String>) -> ApiError {
+    ApiError::validation(vec![FieldError {
+        field: "permissions".to_string(),
+        message: format!("Unknown permissions: {}", unknown.join(", ")),
+    }])
+}
+
+// Caller hanya boleh memberikan permission yang dimilikinya sendiri (aturan yang sama dengan
+// `check_role_assignable`); karena itu platform:admin hanya bisa diberikan oleh platform admin
+fn check_grantable(auth: &AuthUser, permissions: &[String]) -> Result<(), ApiError> {
+    if auth.is_super_admin() {
+        return Ok(());
+    }
+    match permissions.iter().find(|p| !auth.has(p)) {
+        Some(p) => Err(ApiError::forbidden(format!("Cannot grant {} permission", p)).with_code("permission_not_held")),
+        None => Ok(()),
+    }
+}
+
+async fn role_json(data: &AppState, role: &user_role::Model) -> Result<serde_json::Value, ApiError> {
+    Ok(serde_json::json!({
+        "id": role.id,
+        "name": role.name,
+        "permissions": role_permission_names(&data.db, role.id).await?
+    }))
+}
+
+#[get("/permissions")]
+pub async fn get_permissions(auth: AuthUser, data: web::Data<AppState>) -> ApiResult {
+    auth.require(permissions::ROLE_MANAGE)?;
+    let perms = permission::Entity::find()
+        .order_by_asc(permission::Column::Name)
+        .all(&data.db)
+        .await?;
+    Ok(response::ok(perms))
+}
+
+#[get("/roles")]
+pub async fn get_roles(auth: AuthUser, data: web::Data<AppState>) -> ApiResult {
+    auth.require(permissions::ROLE_MANAGE)?;
+    let roles = user_role::Entity::find()
+        .order_by_asc(user_role::Column::Id)
+        .all(&data.db)
+        .await?;
+
+    let mut result = Vec::with_capacity(roles.len());
+    for role in &roles {
+        result.push(role_json(&data, role).await?);
+    }
+    Ok(response::ok(result))
+}
+
+#[post("/roles")]
+pub async fn create_role(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    form: web::Json<CreateRoleRequest>,
+) -> ApiResult {
+    auth.require(permissions::ROLE_MANAGE)?;
+    let name = form.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::validation(vec![FieldError {
+            field: "name".to_string(),
+            message: "Must not be empty".to_string(),
+        }]));
+    }
+
+    check_grantable(&auth, &form.permissions)?;
+
+    let txn = data.db.begin().await?;
+    let role = user_role::ActiveModel {
+        name: Set(name.to_string()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    set_role_permissions(&txn, role.id, &form.permissions)
+        .await?
+        .map_err(unknown_permissions)?;
+    txn.commit().await?;
+
+    let result = role_json(&data, &role).await?;
+    audit::created(&data.db, &auth, "user_role", role.id, &result).await;
+    Ok(response::ok_with_message("Role created successfully", result))
+}
+
+// Ganti seluruh permission role. Role milik caller tidak boleh kehilangan role:manage
+// agar admin tidak mengunci dirinya sendiri.
+#[put("/roles/{id}/permissions")]
+pub async fn set_permissions(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    form: web::Json<SetPermissionsRequest>,
+) -> ApiResult {
+    auth.require(permissions::ROLE_MANAGE)?;
+    let id = path.into_inner();
+
+    let role = user_role::Entity::find_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Role"))?;
+
+    // Role yang punya permission di luar milik caller juga tidak boleh diubah
+    check_grantable(&auth, &form.permissions)?;
+    check_grantable(&auth, &role_permission_names(&data.db, role.id).await?)?;
+
+    let caller_role_id = user::Entity::find_by_id(auth.id)
+        .one(&data.db)
+        .await?
+        .map(|u| u.user_role_id);
+    if caller_role_id == Some(role.id) && !form.permissions.iter().any(|p| p == permissions::ROLE_MANAGE) {
+        return Err(ApiError::conflict("Cannot remove role:manage from your own role").with_code("self_lockout"));
+    }
+
+    let before = role_json(&data, &role).await?;
+    let txn = data.db.begin().await?;
+    set_role_permissions(&txn, role.id, &form.permissions)
+        .await?
+        .map_err(unknown_permissions)?;
+    txn.commit().await?;
+
+    let result = role_json(&data, &role).await?;
+    audit::updated(&data.db, &auth, "user_role", role.id, &before, &result).await;
+    Ok(response::ok_with_message("Role permissions updated", result))
+}
+
+#[delete("/roles/{id}")]
+pub async fn delete_role(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    auth.require(permissions::ROLE_MANAGE)?;
+    let id = path.into_inner();
+
+    let members = user::Entity::find()
+        .filter(user::Column::UserRoleId.eq(id))
+        .count(&data.db)
+        .await?;
+    if members > 0 {
+        return Err(ApiError::conflict(format!("Role is still assigned to {} users", members)).with_code("role_in_use"));
+    }
+
+    let role = user_role::Entity::find_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Role"))?;
+    let before = role_json(&data, &role).await?;
+
+    user_role::Entity::delete_by_id(id).exec(&data.db).await?;
+    audit::deleted(&data.db, &auth, "user_role", id, &before).await;
+
+    Ok(response::message("Role deleted successfully"))
+}
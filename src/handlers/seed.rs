@@ -1,159 +1,117 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use sea_orm::{ActiveModelTrait, EntityTrait, Set, DeleteResult};
+use actix_web::{get, post, put, delete, web};
+use sea_orm::{ActiveModelTrait, EntityTrait, Set};
 use serde::Deserialize;
 
-use crate::{AppState, models::seed};
+use crate::{
+    AppState,
+    audit,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::seed,
+    permissions,
+    response,
+};
 
 #[derive(Deserialize)]
 pub struct CreateSeedRequest {
     pub name: String,
+    pub base_temperature: Option<f64>,
 }
 
 #[derive(Deserialize)]
 pub struct UpdateSeedRequest {
     pub name: Option<String>,
+    pub base_temperature: Option<f64>,
 }
 
 #[post("/seeds")]
 pub async fn create_seed(
+    auth: AuthUser,
     data: web::Data<AppState>,
     form: web::Json<CreateSeedRequest>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::SEED_MANAGE)?;
     let new_seed = seed::ActiveModel {
         name: Set(form.name.clone()),
+        base_temperature: Set(form.base_temperature),
         ..Default::default()
     };
 
-    match new_seed.insert(&data.db).await {
-        Ok(s) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "message": "Seed created successfully",
-            "data": s
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+    let s = new_seed.insert(&data.db).await?;
+    audit::created(&data.db, &auth, "seed", s.id, &s).await;
+    Ok(response::ok_with_message("Seed created successfully", s))
 }
 
 #[get("/seeds")]
 pub async fn get_seeds(
     data: web::Data<AppState>,
-) -> impl Responder {
-    match seed::Entity::find().all(&data.db).await {
-        Ok(seeds) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": seeds
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+) -> ApiResult {
+    let seeds = seed::Entity::find().all(&data.db).await?;
+    Ok(response::ok(seeds))
 }
 
 #[get("/seeds/{id}")]
 pub async fn get_seed_by_id(
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
     let id = path.into_inner();
 
-    match seed::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(s)) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": s
-        })),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "success": false,
-            "error": "Seed not found"
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+    let s = seed::Entity::find_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Seed"))?;
+
+    Ok(response::ok(s))
 }
 
 #[put("/seeds/{id}")]
 pub async fn update_seed(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
     form: web::Json<UpdateSeedRequest>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::SEED_MANAGE)?;
     let id = path.into_inner();
 
-    let existing_seed = match seed::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(s)) => s,
-        Ok(None) => {
-            return HttpResponse::NotFound().json(serde_json::json!({
-                "success": false,
-                "error": "Seed not found"
-            }))
-        }
-        Err(e) => {
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "success": false,
-                "error": format!("Database error: {:?}", e)
-            }))
-        }
-    };
+    let existing_seed = seed::Entity::find_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Seed"))?;
 
+    let before = existing_seed.clone();
     let mut active_model: seed::ActiveModel = existing_seed.into();
 
     if let Some(name) = &form.name {
         active_model.name = Set(name.clone());
     }
 
-    match active_model.update(&data.db).await {
-        Ok(s) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "message": "Seed updated successfully",
-            "data": s
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
+    if let Some(base_temperature) = form.base_temperature {
+        active_model.base_temperature = Set(Some(base_temperature));
     }
+
+    let s = active_model.update(&data.db).await?;
+    audit::updated(&data.db, &auth, "seed", s.id, &before, &s).await;
+    Ok(response::ok_with_message("Seed updated successfully", s))
 }
 
 #[delete("/seeds/{id}")]
 pub async fn delete_seed(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::SEED_MANAGE)?;
     let id = path.into_inner();
 
-    match seed::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(s)) => {
-            let active_seed: seed::ActiveModel = s.into();
-
-            match active_seed.delete(&data.db).await {
-                Ok(DeleteResult { rows_affected }) if rows_affected > 0 => {
-                    HttpResponse::Ok().json(serde_json::json!({
-                        "success": true,
-                        "message": "Seed deleted successfully"
-                    }))
-                }
-                Ok(_) => HttpResponse::NotFound().json(serde_json::json!({
-                    "success": false,
-                    "error": "Seed not found or already deleted"
-                })),
-                Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-                    "success": false,
-                    "error": format!("Database error: {:?}", e)
-                })),
-            }
-        }
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "success": false,
-            "error": "Seed not found"
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
-}
\ No newline at end of file
+    let s = seed::Entity::find_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Seed"))?;
+
+    seed::Entity::delete_by_id(id).exec(&data.db).await?;
+    audit::deleted(&data.db, &auth, "seed", id, &s).await;
+
+    Ok(response::message("Seed deleted successfully"))
+}
@@ -1,204 +1,221 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, DeleteResult};
+use actix_web::{get, post, put, delete, web};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
 use serde::Deserialize;
 
-use crate::{AppState, models::sensor::{self, SensorType}};
+use crate::{
+    AppState,
+    audit,
+    derived,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::sensor::{self, SensorType},
+    permissions,
+    response,
+    soft_delete::{self, SoftDelete},
+    tenancy::{self, Access},
+    validation::Validator,
+};
 
 #[derive(Deserialize)]
 pub struct CreateSensorRequest {
     pub name: String,
     pub sensor_type: String,
     pub land_id: i32,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
 }
 
 #[derive(Deserialize)]
 pub struct UpdateSensorRequest {
     pub name: Option<String>,
     pub sensor_type: Option<String>,
+    // Dikirim berpasangan
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
 }
 
-fn parse_sensor_type(type_str: &str) -> Result<SensorType, String> {
+fn parse_sensor_type(type_str: &str) -> Result<SensorType, ApiError> {
     match type_str {
         "Temperature" => Ok(SensorType::Temperature),
         "Humidity" => Ok(SensorType::Humidity),
         "SoilMoisture" => Ok(SensorType::SoilMoisture),
         "PH" => Ok(SensorType::PH),
         "LightIntensity" => Ok(SensorType::LightIntensity),
-        _ => Err(format!("Invalid sensor type: {}", type_str)),
+        _ => Err(ApiError::bad_request(format!("Invalid sensor type: {}", type_str))),
     }
 }
 
 #[post("/sensors")]
 pub async fn create_sensor(
+    auth: AuthUser,
     data: web::Data<AppState>,
     form: web::Json<CreateSensorRequest>,
-) -> impl Responder {
-    let type_enum = match parse_sensor_type(&form.sensor_type) {
-        Ok(t) => t,
-        Err(e) => return HttpResponse::BadRequest().json(serde_json::json!({
-            "success": false,
-            "error": e
-        })),
-    };
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let type_enum = parse_sensor_type(&form.sensor_type)?;
+
+    tenancy::land_access(&data.db, &auth, form.land_id, Access::Write).await?;
+    let mut v = Validator::new(&data.db);
+    v.coordinates("", form.latitude, form.longitude);
+    v.finish()?;
 
     let new_sensor = sensor::ActiveModel {
         name: Set(form.name.clone()),
         sensor_type: Set(type_enum),
         land_id: Set(form.land_id),
+        latitude: Set(form.latitude),
+        longitude: Set(form.longitude),
         ..Default::default()
     };
 
-    match new_sensor.insert(&data.db).await {
-        Ok(s) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "message": "Sensor created successfully",
-            "data": s
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+    let s = new_sensor.insert(&data.db).await?;
+    audit::created(&data.db, &auth, "sensor", s.id, &s).await;
+    Ok(response::ok_with_message("Sensor created successfully", s))
 }
 
+// Semua sensor di land yang boleh dilihat caller
 #[get("/sensors")]
-pub async fn get_sensors(data: web::Data<AppState>) -> impl Responder {
-    match sensor::Entity::find().all(&data.db).await {
-        Ok(sensors) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": sensors
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
+pub async fn get_sensors(auth: AuthUser, data: web::Data<AppState>) -> ApiResult {
+    let mut select = sensor::Entity::find_active();
+    if let Some(land_ids) = tenancy::visible_land_ids(&data.db, &auth).await? {
+        select = select.filter(sensor::Column::LandId.is_in(land_ids));
     }
+    let sensors = select.all(&data.db).await?;
+    Ok(response::ok(sensors))
 }
 
 #[get("/lands/{land_id}/sensors")]
 pub async fn get_land_sensors(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
     let land_id = path.into_inner();
+    tenancy::land_access(&data.db, &auth, land_id, Access::Read).await?;
 
-    match sensor::Entity::find()
+    let sensors = sensor::Entity::find_active()
         .filter(sensor::Column::LandId.eq(land_id))
         .all(&data.db)
-        .await
-    {
-        Ok(sensors) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": sensors
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+        .await?;
+
+    Ok(response::ok(sensors))
 }
 
 #[get("/sensors/{id}")]
 pub async fn get_sensor_by_id(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
     let id = path.into_inner();
 
-    match sensor::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(s)) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": s
-        })),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "success": false,
-            "error": "Sensor not found"
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+    let s = sensor::Entity::find_active_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Sensor"))?;
+    tenancy::land_access(&data.db, &auth, s.land_id, Access::Read).await?;
+
+    Ok(response::ok(s))
 }
 
 #[put("/sensors/{id}")]
 pub async fn update_sensor(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
     form: web::Json<UpdateSensorRequest>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
     let id = path.into_inner();
 
-    let existing_sensor = match sensor::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(s)) => s,
-        Ok(None) => return HttpResponse::NotFound().json(serde_json::json!({
-            "success": false, "error": "Sensor not found"
-        })),
-        Err(e) => return HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false, "error": format!("Database error: {:?}", e)
-        })),
-    };
+    let existing_sensor = sensor::Entity::find_active_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Sensor"))?;
+    tenancy::land_access(&data.db, &auth, existing_sensor.land_id, Access::Write).await?;
+    let mut v = Validator::new(&data.db);
+    v.coordinates("", form.latitude, form.longitude);
+    v.finish()?;
 
+    let before = existing_sensor.clone();
     let mut active_model: sensor::ActiveModel = existing_sensor.into();
 
     if let Some(name) = &form.name {
         active_model.name = Set(name.clone());
     }
 
+    if form.latitude.is_some() {
+        active_model.latitude = Set(form.latitude);
+        active_model.longitude = Set(form.longitude);
+    }
+
     if let Some(type_str) = &form.sensor_type {
-        match parse_sensor_type(type_str) {
-            Ok(t) => {
-                active_model.sensor_type = Set(t);
-            },
-            Err(e) => return HttpResponse::BadRequest().json(serde_json::json!({
-                "success": false, "error": e
-            })),
+        if derived::is_derived(&data.db, id).await? {
+            return Err(ApiError::bad_request("The type of a derived sensor cannot be changed").with_code("derived_sensor"));
         }
+        active_model.sensor_type = Set(parse_sensor_type(type_str)?);
     }
 
-    match active_model.update(&data.db).await {
-        Ok(s) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "message": "Sensor updated successfully",
-            "data": s
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+    let s = active_model.update(&data.db).await?;
+    audit::updated(&data.db, &auth, "sensor", s.id, &before, &s).await;
+    Ok(response::ok_with_message("Sensor updated successfully", s))
 }
 
 #[delete("/sensors/{id}")]
 pub async fn delete_sensor(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
     let id = path.into_inner();
 
-    match sensor::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(s)) => {
-            let active_sensor: sensor::ActiveModel = s.into();
-            match active_sensor.delete(&data.db).await {
-                Ok(DeleteResult { rows_affected }) if rows_affected > 0 => {
-                    HttpResponse::Ok().json(serde_json::json!({
-                        "success": true,
-                        "message": "Sensor deleted successfully"
-                    }))
-                }
-                Ok(_) => HttpResponse::NotFound().json(serde_json::json!({
-                    "success": false, "error": "Sensor not found or already deleted"
-                })),
-                Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-                    "success": false, "error": format!("Database error: {:?}", e)
-                })),
-            }
-        }
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "success": false, "error": "Sensor not found"
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false, "error": format!("Database error: {:?}", e)
-        })),
+    let s = sensor::Entity::find_active_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Sensor"))?;
+    tenancy::land_access(&data.db, &auth, s.land_id, Access::Write).await?;
+
+    // Automation yang membaca sensor ini dan sensor virtual turunannya ikut masuk trash
+    let txn = data.db.begin().await?;
+    soft_delete::delete_sensor(&txn, id, soft_delete::now()).await?;
+    txn.commit().await?;
+    audit::deleted(&data.db, &auth, "sensor", id, &s).await;
+
+    Ok(response::message("Sensor deleted successfully"))
+}
+
+#[post("/sensors/{id}/restore")]
+pub async fn restore_sensor(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let id = path.into_inner();
+
+    let s = sensor::Entity::find_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Sensor"))?;
+    tenancy::restorable_land(&data.db, &auth, s.land_id).await?;
+    let Some(deleted_at) = s.deleted_at else {
+        return Err(ApiError::conflict("Sensor is not deleted").with_code("not_deleted"));
+    };
+
+    let missing = soft_delete::missing_derived_sources(&data.db, id).await?;
+    if !missing.is_empty() {
+        return Err(ApiError::conflict(format!("Restore the virtual sensor's {} first", missing.join(", "))).with_code("parent_deleted"));
     }
-}
\ No newline at end of file
+
+    let txn = data.db.begin().await?;
+    soft_delete::restore_sensor(&txn, id, deleted_at).await?;
+    txn.commit().await?;
+
+    let restored = sensor::Entity::find_active_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Sensor"))?;
+    audit::updated(&data.db, &auth, "sensor", id, &s, &restored).await;
+    Ok(response::ok_with_message("Sensor restored successfully", restored))
+}
@@ -1,9 +1,40 @@
-use actix_web::{get, post, web, HttpResponse, Responder};
+use actix_web::{get, post, web};
 use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
 use serde::Deserialize;
-use chrono::Local;
+use chrono::{Duration, Local, NaiveDateTime};
 
-use crate::{AppState, models::sensor_history};
+use crate::{
+    AppState,
+    anomaly,
+    derived,
+    engine,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::{sensor, sensor_history},
+    permissions,
+    response,
+    rollup::{self, Resolution},
+    soft_delete::SoftDelete,
+    tenancy::{self, Access},
+};
+
+// Sensor beserta cek akses ke land-nya
+pub async fn sensor_access(data: &AppState, auth: &AuthUser, sensor_id: i32, access: Access) -> Result<sensor::Model, ApiError> {
+    let s = sensor::Entity::find_active_by_id(sensor_id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Sensor"))?;
+    tenancy::land_access(&data.db, auth, s.land_id, access).await?;
+    Ok(s)
+}
+
+// Tanpa from/to: 24 jam terakhir. Tanpa resolution: dipilih otomatis dari panjang rentang.
+#[derive(Deserialize)]
+pub struct HistoryQuery {
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+    pub resolution: Option<Resolution>,
+}
 
 #[derive(Deserialize)]
 pub struct CreateHistoryRequest {
@@ -11,54 +42,134 @@ pub struct CreateHistoryRequest {
     pub value: f64,
 }
 
+// Terima pembacaan sensor baru lalu evaluasi automation yang terkait
+#[post("/sensor-history")]
+pub async fn create_history(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    form: web::Json<CreateHistoryRequest>,
+) -> ApiResult {
+    auth.require(permissions::SENSOR_INGEST)?;
+    let s = sensor_access(&data, &auth, form.sensor_id, Access::Write).await?;
+    if derived::is_derived(&data.db, s.id).await? {
+        return Err(ApiError::bad_request("Derived sensors are computed from their source sensors").with_code("derived_sensor"));
+    }
+
+    let now = Local::now().naive_local();
+    let anomaly = anomaly::score(&data.db, &data.anomaly, &s, form.value, now).await?;
+
+    let new_history = sensor_history::ActiveModel {
+        sensor_id: Set(form.sensor_id),
+        value: Set(form.value),
+        recorded_at: Set(now),
+        rolled_up: Set(false),
+        anomaly: Set(anomaly.as_ref().map(|a| a.kind)),
+        anomaly_detail: Set(anomaly.as_ref().map(|a| a.detail.clone())),
+        ..Default::default()
+    };
+
+    let history = new_history.insert(&data.db).await?;
+
+    // Pembacaan yang mencurigakan disimpan tapi tidak boleh memicu automation
+    let mut derived_readings = Vec::new();
+    let events = match &anomaly {
+        Some(a) => {
+            anomaly::notify(&data.db, &s, history.value, a).await?;
+            Vec::new()
+        }
+        None => {
+            let mut events = engine::evaluate_reading(&data.db, history.sensor_id, history.value).await?;
+            // Sensor virtual yang bergantung pada sensor ini ikut diperbarui & dievaluasi
+            let derived = derived::on_reading(&data.db, &history).await?;
+            for reading in &derived {
+                events.extend(engine::evaluate_reading(&data.db, reading.sensor_id, reading.value).await?);
+            }
+            derived_readings = derived;
+            events
+        }
+    };
+
+    Ok(response::ok(serde_json::json!({
+        "reading": history,
+        "derived": derived_readings,
+        "automations": events
+    })))
+}
+
 #[get("/sensors/{sensor_id}/history")]
 pub async fn get_history_by_sensor(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+    query: web::Query<HistoryQuery>,
+) -> ApiResult {
     let sensor_id = path.into_inner();
+    let s = sensor_access(&data, &auth, sensor_id, Access::Read).await?;
 
-    match sensor_history::Entity::find()
-        .filter(sensor_history::Column::SensorId.eq(sensor_id))
-        .order_by_desc(sensor_history::Column::RecordedAt)
-        .all(&data.db)
-        .await
-    {
-        Ok(histories) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": histories
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
+    let now = Local::now().naive_local();
+    let to = query.to.unwrap_or(now);
+    let from = query.from.unwrap_or(to - Duration::days(1));
+    if from >= to {
+        return Err(ApiError::bad_request("from must be before to").with_code("invalid_range"));
     }
+
+    let policy = rollup::effective_policy(&data.db, &data.retention, &s).await?;
+    let resolution = query
+        .resolution
+        .unwrap_or_else(|| rollup::pick_resolution(from, to, &policy, now));
+    let points = rollup::history_points(&data.db, sensor_id, resolution, from, to).await?;
+
+    Ok(response::ok(serde_json::json!({
+        "sensor_id": sensor_id,
+        "resolution": resolution,
+        "from": from,
+        "to": to,
+        "retention": policy,
+        "points": points
+    })))
 }
 
 #[get("/sensors/{sensor_id}/latest")]
 pub async fn get_latest_history_by_sensor(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
     let sensor_id = path.into_inner();
+    sensor_access(&data, &auth, sensor_id, Access::Read).await?;
 
-    match sensor_history::Entity::find()
+    let history = sensor_history::Entity::find()
         .filter(sensor_history::Column::SensorId.eq(sensor_id))
         .order_by_desc(sensor_history::Column::RecordedAt)
         .one(&data.db)
-        .await
-    {
-        Ok(Some(history)) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": history
-        })),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "success": false,
-            "error": "No data recorded yet"
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
-}
\ No newline at end of file
+        .await?
+        .ok_or_else(|| ApiError::not_found("Sensor reading").with_code("no_data"))?;
+
+    Ok(response::ok(history))
+}
+
+// Pembacaan yang ditandai anomali; tanpa from/to: 7 hari terakhir
+#[get("/sensors/{sensor_id}/anomalies")]
+pub async fn get_anomalies_by_sensor(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    query: web::Query<HistoryQuery>,
+) -> ApiResult {
+    let sensor_id = path.into_inner();
+    sensor_access(&data, &auth, sensor_id, Access::Read).await?;
+
+    let to = query.to.unwrap_or_else(|| Local::now().naive_local());
+    let from = query.from.unwrap_or(to - Duration::days(7));
+
+    let readings = sensor_history::Entity::find()
+        .filter(sensor_history::Column::SensorId.eq(sensor_id))
+        .filter(sensor_history::Column::Anomaly.is_not_null())
+        .filter(sensor_history::Column::RecordedAt.gte(from))
+        .filter(sensor_history::Column::RecordedAt.lt(to))
+        .order_by_desc(sensor_history::Column::RecordedAt)
+        .all(&data.db)
+        .await?;
+
+    Ok(response::ok(readings))
+}
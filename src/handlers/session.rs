@@ -0,0 +1,95 @@
+use actix_web::{delete, get, web};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+
+use crate::{
+    AppState,
+    audit,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::user_session,
+    response,
+    session_store::revoke_user_sessions,
+    tenancy,
+    tokens::revoke_user_refresh_tokens,
+};
+
+// Daftar session aktif milik user (user itu sendiri atau pengelola user di organisasinya)
+#[get("/users/{user_id}/sessions")]
+pub async fn get_user_sessions(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    let user_id = path.into_inner();
+    tenancy::require_user_admin(&data.db, &auth, user_id).await?;
+
+    let sessions = user_session::Entity::find()
+        .filter(user_session::Column::UserId.eq(user_id))
+        .order_by_desc(user_session::Column::LastSeenAt)
+        .all(&data.db)
+        .await?;
+
+    Ok(response::ok(sessions))
+}
+
+#[delete("/users/{user_id}/sessions/{session_id}")]
+pub async fn revoke_user_session(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<(i32, i32)>,
+) -> ApiResult {
+    let (user_id, session_id) = path.into_inner();
+    tenancy::require_user_admin(&data.db, &auth, user_id).await?;
+
+    let session = user_session::Entity::find()
+        .filter(user_session::Column::Id.eq(session_id))
+        .filter(user_session::Column::UserId.eq(user_id))
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Session"))?;
+
+    user_session::Entity::delete_by_id(session.id).exec(&data.db).await?;
+    audit::deleted(&data.db, &auth, "user_session", session.id, &session).await;
+
+    Ok(response::message("Session revoked"))
+}
+
+#[delete("/users/{user_id}/sessions")]
+pub async fn revoke_all_user_sessions(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    let user_id = path.into_inner();
+    tenancy::require_user_admin(&data.db, &auth, user_id).await?;
+
+    let sessions = user_session::Entity::find()
+        .filter(user_session::Column::UserId.eq(user_id))
+        .all(&data.db)
+        .await?;
+
+    // "Logout di semua perangkat" juga mencabut refresh token aplikasi mobile / script
+    let revoked = revoke_user_sessions(&data.db, user_id).await?;
+    let tokens_revoked = revoke_user_refresh_tokens(&data.db, user_id).await?;
+
+    // Satu entri per session seperti pencabutan satu per satu; refresh token cukup dicatat jumlahnya
+    for session in &sessions {
+        audit::deleted(&data.db, &auth, "user_session", session.id, session).await;
+    }
+    if tokens_revoked > 0 {
+        audit::updated(
+            &data.db,
+            &auth,
+            "user",
+            user_id,
+            &serde_json::json!({ "active_refresh_tokens": tokens_revoked }),
+            &serde_json::json!({ "active_refresh_tokens": 0 }),
+        )
+        .await;
+    }
+
+    Ok(response::ok_with_message(
+        "Sessions revoked",
+        serde_json::json!({ "revoked": revoked, "tokens_revoked": tokens_revoked }),
+    ))
+}
@@ -0,0 +1,348 @@
+use actix_web::{delete, get, post, put, web};
+use chrono::{Local, NaiveDateTime};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    audit,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::{
+        land,
+        task::{self, TaskPriority, TaskStatus},
+        task_comment,
+    },
+    notifier,
+    permissions,
+    response,
+    tasks,
+    tenancy::{self, Access},
+    validation::Validator,
+};
+
+// Objek lapangan yang dikerjakan task; semuanya harus di land yang sama
+#[derive(Deserialize, Default)]
+pub struct TaskLinks {
+    pub plant_id: Option<i32>,
+    pub pump_id: Option<i32>,
+    pub valve_id: Option<i32>,
+    pub sensor_id: Option<i32>,
+    pub pest_control_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateTaskRequest {
+    pub title: String,
+    pub description: Option<String>,
+    pub priority: Option<TaskPriority>,
+    pub due_at: Option<NaiveDateTime>,
+    pub assignee_id: Option<i32>,
+    #[serde(flatten)]
+    pub links: TaskLinks,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateTaskRequest {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub priority: Option<TaskPriority>,
+    pub status: Option<TaskStatus>,
+    pub due_at: Option<NaiveDateTime>,
+    pub assignee_id: Option<i32>,
+    // Hapus due date / assignee
+    #[serde(default)]
+    pub clear_due_at: bool,
+    #[serde(default)]
+    pub unassign: bool,
+}
+
+#[derive(Deserialize)]
+pub struct TaskListQuery {
+    pub status: Option<TaskStatus>,
+    pub assignee_id: Option<i32>,
+    // Hanya task terbuka yang lewat due date
+    #[serde(default)]
+    pub overdue: bool,
+}
+
+#[derive(Deserialize)]
+pub struct CreateCommentRequest {
+    pub body: String,
+}
+
+#[derive(Deserialize)]
+pub struct FromRecommendationsRequest {
+    pub assignee_id: Option<i32>,
+    pub due_at: Option<NaiveDateTime>,
+}
+
+async fn check_links(v: &mut Validator<'_>, links: &TaskLinks, land_id: i32) -> Result<(), ApiError> {
+    if let Some(id) = links.plant_id { v.plant_on_land("plant_id", id, land_id).await?; }
+    if let Some(id) = links.pump_id { v.pump_on_land("pump_id", id, land_id).await?; }
+    if let Some(id) = links.valve_id { v.valve_on_land("valve_id", id, land_id).await?; }
+    if let Some(id) = links.sensor_id { v.sensor_on_land("sensor_id", id, land_id).await?; }
+    if let Some(id) = links.pest_control_id { v.pest_control_on_land("pest_control_id", id, land_id).await?; }
+    Ok(())
+}
+
+// Ambil task beserta land-nya dan pastikan caller punya akses
+async fn task_access(data: &AppState, auth: &AuthUser, id: i32, access: Access) -> Result<(task::Model, land::Model), ApiError> {
+    let t = task::Entity::find_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Task"))?;
+    let l = tenancy::land_access(&data.db, auth, t.land_id, access).await?;
+    Ok((t, l))
+}
+
+// Beri tahu assignee baru, kecuali ia menugaskan dirinya sendiri
+async fn notify_assignee(data: &AppState, auth: &AuthUser, t: &task::Model) -> Result<(), ApiError> {
+    if let Some(assignee_id) = t.assignee_id
+        && assignee_id != auth.id
+    {
+        notifier::notify_user(&data.db, assignee_id, format!("You have been assigned task '{}'", t.title)).await?;
+    }
+    Ok(())
+}
+
+fn apply_filters(mut select: sea_orm::Select<task::Entity>, query: &TaskListQuery) -> sea_orm::Select<task::Entity> {
+    if let Some(status) = query.status {
+        select = select.filter(task::Column::Status.eq(status));
+    }
+    if let Some(assignee_id) = query.assignee_id {
+        select = select.filter(task::Column::AssigneeId.eq(assignee_id));
+    }
+    if query.overdue {
+        select = select
+            .filter(task::Column::Status.is_in(TaskStatus::OPEN))
+            .filter(task::Column::DueAt.lt(Local::now().naive_local()));
+    }
+    select.order_by_asc(task::Column::DueAt).order_by_asc(task::Column::Id)
+}
+
+#[post("/lands/{land_id}/tasks")]
+pub async fn create_task(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    form: web::Json<CreateTaskRequest>,
+) -> ApiResult {
+    auth.require(permissions::TASK_WRITE)?;
+    let l = tenancy::land_access(&data.db, &auth, path.into_inner(), Access::Write).await?;
+
+    let mut v = Validator::new(&data.db);
+    v.max_length("title", &form.title, 255);
+    if let Some(assignee_id) = form.assignee_id {
+        v.member_of_org("assignee_id", assignee_id, l.organization_id).await?;
+    }
+    check_links(&mut v, &form.links, l.id).await?;
+    v.finish()?;
+
+    let now = Local::now().naive_local();
+    let t = task::ActiveModel {
+        land_id: Set(l.id),
+        title: Set(form.title.clone()),
+        description: Set(form.description.clone()),
+        priority: Set(form.priority.unwrap_or(TaskPriority::Medium)),
+        status: Set(TaskStatus::Open),
+        due_at: Set(form.due_at),
+        assignee_id: Set(form.assignee_id),
+        created_by: Set(Some(auth.id)),
+        plant_id: Set(form.links.plant_id),
+        pump_id: Set(form.links.pump_id),
+        valve_id: Set(form.links.valve_id),
+        sensor_id: Set(form.links.sensor_id),
+        pest_control_id: Set(form.links.pest_control_id),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    }
+    .insert(&data.db)
+    .await?;
+
+    audit::created(&data.db, &auth, "task", t.id, &t).await;
+    notify_assignee(&data, &auth, &t).await?;
+    Ok(response::ok_with_message("Task created successfully", t))
+}
+
+// Buat task dari rekomendasi benih tanaman-tanaman aktif di land
+#[post("/lands/{land_id}/tasks/from-recommendations")]
+pub async fn create_tasks_from_recommendations(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    form: web::Json<FromRecommendationsRequest>,
+) -> ApiResult {
+    auth.require(permissions::TASK_WRITE)?;
+    let l = tenancy::land_access(&data.db, &auth, path.into_inner(), Access::Write).await?;
+
+    let mut v = Validator::new(&data.db);
+    if let Some(assignee_id) = form.assignee_id {
+        v.member_of_org("assignee_id", assignee_id, l.organization_id).await?;
+    }
+    v.finish()?;
+
+    let created = tasks::from_recommendations(&data.db, l.id, auth.id, form.assignee_id, form.due_at).await?;
+    for t in &created {
+        audit::created(&data.db, &auth, "task", t.id, t).await;
+    }
+
+    Ok(response::ok_with_message(
+        &format!("{} tasks created from recommendations", created.len()),
+        created,
+    ))
+}
+
+#[get("/lands/{land_id}/tasks")]
+pub async fn get_tasks_by_land(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    query: web::Query<TaskListQuery>,
+) -> ApiResult {
+    let land_id = path.into_inner();
+    tenancy::land_access(&data.db, &auth, land_id, Access::Read).await?;
+
+    let select = task::Entity::find().filter(task::Column::LandId.eq(land_id));
+    let result = apply_filters(select, &query).all(&data.db).await?;
+    Ok(response::ok(result))
+}
+
+// Task yang ditugaskan ke caller di semua land yang masih bisa dilihat
+#[get("/me/tasks")]
+pub async fn get_my_tasks(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    query: web::Query<TaskListQuery>,
+) -> ApiResult {
+    let mut select = task::Entity::find().filter(task::Column::AssigneeId.eq(auth.id));
+    if let Some(ids) = tenancy::visible_land_ids(&data.db, &auth).await? {
+        select = select.filter(task::Column::LandId.is_in(ids));
+    }
+    let result = apply_filters(select, &query).all(&data.db).await?;
+    Ok(response::ok(result))
+}
+
+#[get("/tasks/{id}")]
+pub async fn get_task_by_id(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    let (t, _) = task_access(&data, &auth, path.into_inner(), Access::Read).await?;
+
+    let comments = task_comment::Entity::find()
+        .filter(task_comment::Column::TaskId.eq(t.id))
+        .order_by_asc(task_comment::Column::CreatedAt)
+        .all(&data.db)
+        .await?;
+
+    Ok(response::ok(serde_json::json!({
+        "task": t,
+        "comments": comments
+    })))
+}
+
+// Pemegang task:write boleh mengubah semua field; assignee tanpa permission itu hanya boleh mengubah status
+#[put("/tasks/{id}")]
+pub async fn update_task(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    form: web::Json<UpdateTaskRequest>,
+) -> ApiResult {
+    let id = path.into_inner();
+    let can_manage = auth.has(permissions::TASK_WRITE);
+    let (existing, l) = task_access(&data, &auth, id, if can_manage { Access::Write } else { Access::Read }).await?;
+
+    if !can_manage {
+        let only_status = form.title.is_none()
+            && form.description.is_none()
+            && form.priority.is_none()
+            && form.due_at.is_none()
+            && form.assignee_id.is_none()
+            && !form.clear_due_at
+            && !form.unassign;
+        if existing.assignee_id != Some(auth.id) || !only_status {
+            auth.require(permissions::TASK_WRITE)?;
+        }
+    }
+
+    let mut v = Validator::new(&data.db);
+    if let Some(title) = &form.title { v.max_length("title", title, 255); }
+    if let Some(assignee_id) = form.assignee_id {
+        v.member_of_org("assignee_id", assignee_id, l.organization_id).await?;
+    }
+    v.finish()?;
+
+    let now = Local::now().naive_local();
+    let before = existing.clone();
+    let mut active_model: task::ActiveModel = existing.into();
+
+    if let Some(title) = &form.title { active_model.title = Set(title.clone()); }
+    if let Some(description) = &form.description { active_model.description = Set(Some(description.clone())); }
+    if let Some(priority) = form.priority { active_model.priority = Set(priority); }
+    if form.unassign { active_model.assignee_id = Set(None); }
+    if let Some(assignee_id) = form.assignee_id { active_model.assignee_id = Set(Some(assignee_id)); }
+    if form.clear_due_at || form.due_at.is_some() {
+        active_model.due_at = Set(form.due_at);
+        // Due date baru: boleh diingatkan lagi
+        active_model.overdue_notified_at = Set(None);
+    }
+    if let Some(status) = form.status {
+        active_model.status = Set(status);
+        active_model.completed_at = Set((status == TaskStatus::Done).then_some(before.completed_at.unwrap_or(now)));
+    }
+    active_model.updated_at = Set(now);
+
+    let t = active_model.update(&data.db).await?;
+    audit::updated(&data.db, &auth, "task", t.id, &before, &t).await;
+    if t.assignee_id != before.assignee_id {
+        notify_assignee(&data, &auth, &t).await?;
+    }
+    Ok(response::ok_with_message("Task updated successfully", t))
+}
+
+#[delete("/tasks/{id}")]
+pub async fn delete_task(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    auth.require(permissions::TASK_WRITE)?;
+    let (t, _) = task_access(&data, &auth, path.into_inner(), Access::Write).await?;
+
+    task::Entity::delete_by_id(t.id).exec(&data.db).await?;
+    audit::deleted(&data.db, &auth, "task", t.id, &t).await;
+
+    Ok(response::message("Task deleted successfully"))
+}
+
+// Siapa pun yang bisa melihat land boleh berkomentar
+#[post("/tasks/{id}/comments")]
+pub async fn create_task_comment(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    form: web::Json<CreateCommentRequest>,
+) -> ApiResult {
+    let (t, _) = task_access(&data, &auth, path.into_inner(), Access::Read).await?;
+
+    let mut v = Validator::new(&data.db);
+    v.max_length("body", &form.body, 4000);
+    v.finish()?;
+
+    let c = task_comment::ActiveModel {
+        task_id: Set(t.id),
+        user_id: Set(Some(auth.id)),
+        body: Set(form.body.clone()),
+        created_at: Set(Local::now().naive_local()),
+        ..Default::default()
+    }
+    .insert(&data.db)
+    .await?;
+
+    audit::created(&data.db, &auth, "task_comment", c.id, &c).await;
+    Ok(response::ok_with_message("Comment added successfully", c))
+}
@@ -0,0 +1,95 @@
+use actix_web::{get, post, web};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QuerySelect};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    error::ApiResult,
+    extractors::AuthUser,
+    models::{automation, land, plant, pump, sensor, valve, zone},
+    permissions,
+    response,
+    soft_delete::{self, SoftDelete},
+    tenancy::{self, Access},
+};
+
+#[derive(Deserialize)]
+pub struct TrashQuery {
+    pub land_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct PurgeRequest {
+    // Default: SOFT_DELETE_RETENTION_DAYS
+    pub older_than_days: Option<i64>,
+}
+
+// Data yang di-soft delete dan masih bisa di-restore, dari land yang boleh dilihat caller
+#[get("/trash")]
+pub async fn get_trash(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    query: web::Query<TrashQuery>,
+) -> ApiResult {
+    let land_ids: Option<Vec<i32>> = match query.land_id {
+        Some(land_id) => {
+            tenancy::land_access_with_deleted(&data.db, &auth, land_id, Access::Read).await?;
+            Some(vec![land_id])
+        }
+        None if auth.is_super_admin() => None,
+        None => Some(
+            tenancy::scope_lands(land::Entity::find(), &auth)
+                .select_only()
+                .column(land::Column::Id)
+                .into_tuple()
+                .all(&data.db)
+                .await?,
+        ),
+    };
+
+    let mut lands = land::Entity::find_deleted();
+    let mut sensors = sensor::Entity::find_deleted();
+    let mut plants = plant::Entity::find_deleted();
+    let mut pumps = pump::Entity::find_deleted();
+    let mut valves = valve::Entity::find_deleted();
+    let mut zones = zone::Entity::find_deleted();
+    let mut automations = automation::Entity::find_deleted();
+    if let Some(ids) = land_ids {
+        lands = lands.filter(land::Column::Id.is_in(ids.clone()));
+        sensors = sensors.filter(sensor::Column::LandId.is_in(ids.clone()));
+        plants = plants.filter(plant::Column::LandId.is_in(ids.clone()));
+        pumps = pumps.filter(pump::Column::LandId.is_in(ids.clone()));
+        valves = valves.filter(valve::Column::LandId.is_in(ids.clone()));
+        zones = zones.filter(zone::Column::LandId.is_in(ids.clone()));
+        automations = automations.filter(automation::Column::LandId.is_in(ids));
+    }
+
+    Ok(response::ok(serde_json::json!({
+        "retention_days": data.retention.soft_delete_days,
+        "lands": lands.all(&data.db).await?,
+        "sensors": sensors.all(&data.db).await?,
+        "plants": plants.all(&data.db).await?,
+        "pumps": pumps.all(&data.db).await?,
+        "valves": valves.all(&data.db).await?,
+        "zones": zones.all(&data.db).await?,
+        "automations": automations.all(&data.db).await?
+    })))
+}
+
+// Hapus permanen data di trash yang lebih tua dari masa retensi (juga dijalankan job berkala).
+// Setiap baris yang dihapus tercatat di audit log; yang tidak bisa dihapus dilaporkan di `kept`.
+#[post("/trash/purge")]
+pub async fn purge_trash(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    form: Option<web::Json<PurgeRequest>>,
+) -> ApiResult {
+    auth.require(permissions::PLATFORM_ADMIN)?;
+    let days = form
+        .and_then(|f| f.into_inner().older_than_days)
+        .unwrap_or(data.retention.soft_delete_days)
+        .max(0);
+
+    let report = soft_delete::purge_older_than(&data.db, days, Some(&auth)).await?;
+    Ok(response::ok_with_message(&format!("Purged {} deleted records", report.total()), report))
+}
@@ -1,15 +1,55 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use bcrypt::hash;
-use sea_orm::{ActiveModelTrait, EntityTrait, Set, DeleteResult};
+use actix_web::{get, post, put, delete, web};
+use bcrypt::{hash, DEFAULT_COST};
+use sea_orm::{
+    sea_query::{extension::postgres::PgExpr, Expr},
+    ActiveModelTrait, ColumnTrait, Condition, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    Set, TransactionTrait,
+};
 use serde::Deserialize;
 
-use crate::{AppState, models::user};
+use crate::{
+    AppState,
+    audit,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::{
+        land,
+        organization_member::{self, OrgRole},
+        user::{self, UserStatus},
+    },
+    permissions::{self, role_permission_names},
+    response,
+    session_store::revoke_user_sessions,
+    soft_delete::{self, SoftDelete},
+    tenancy::{self, Access},
+    tokens::revoke_user_refresh_tokens,
+    validation::Validator,
+};
+
+const DEFAULT_LANGUAGE: &str = "id";
+const DEFAULT_TIMEZONE: &str = "Asia/Jakarta";
+
+// Field profil yang bisa diisi saat create/update dan lewat PUT /me.
+// String kosong pada full_name/phone/email berarti menghapus nilainya.
+#[derive(Deserialize)]
+pub struct ProfileFields {
+    pub full_name: Option<String>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub language: Option<String>,
+    pub timezone: Option<String>,
+}
 
 #[derive(Deserialize)]
 pub struct CreateUserRequest {
     pub username: String,
     pub password: String,
     pub user_role_id: i32,
+    // Langsung daftarkan user ke organisasi (wajib kecuali super-admin)
+    pub organization_id: Option<i32>,
+    pub org_role: Option<OrgRole>,
+    #[serde(flatten)]
+    pub profile: ProfileFields,
 }
 
 #[derive(Deserialize)]
@@ -17,123 +57,304 @@ pub struct UpdateUserRequest {
     pub username: Option<String>,
     pub password: Option<String>,
     pub user_role_id: Option<i32>,
+    pub status: Option<UserStatus>,
+    #[serde(flatten)]
+    pub profile: ProfileFields,
+}
+
+#[derive(Deserialize)]
+pub struct UserQuery {
+    // Cari di username, nama lengkap, dan email
+    pub q: Option<String>,
+    pub user_role_id: Option<i32>,
+    pub status: Option<UserStatus>,
+    pub page: Option<u64>,
+    pub per_page: Option<u64>,
+}
+
+fn optional(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+pub fn validate_profile(v: &mut Validator, username: Option<&str>, profile: &ProfileFields) {
+    if let Some(username) = username {
+        v.max_length("username", username, 50);
+    }
+    if let Some(full_name) = profile.full_name.as_deref().and_then(optional) {
+        v.max_length("full_name", &full_name, 100);
+    }
+    if let Some(phone) = profile.phone.as_deref().and_then(optional) {
+        v.phone("phone", &phone);
+    }
+    if let Some(email) = profile.email.as_deref().and_then(optional) {
+        v.email("email", &email);
+    }
+    if let Some(language) = &profile.language {
+        v.language("language", language);
+    }
+    if let Some(timezone) = &profile.timezone {
+        v.timezone("timezone", timezone);
+    }
+}
+
+pub fn apply_profile(active_model: &mut user::ActiveModel, profile: &ProfileFields) {
+    if let Some(full_name) = &profile.full_name {
+        active_model.full_name = Set(optional(full_name));
+    }
+    if let Some(phone) = &profile.phone {
+        active_model.phone = Set(optional(phone));
+    }
+    if let Some(email) = &profile.email {
+        active_model.email = Set(optional(email).map(|e| e.to_lowercase()));
+    }
+    if let Some(language) = &profile.language {
+        active_model.language = Set(language.clone());
+    }
+    if let Some(timezone) = &profile.timezone {
+        active_model.timezone = Set(timezone.clone());
+    }
+}
+
+// Caller tidak boleh memberi role yang permission-nya melebihi miliknya sendiri
+async fn check_role_assignable(data: &AppState, auth: &AuthUser, role_id: i32) -> Result<(), ApiError> {
+    if auth.is_super_admin() {
+        return Ok(());
+    }
+    let role_permissions = role_permission_names(&data.db, role_id).await?;
+    match role_permissions.iter().find(|p| !auth.has(p)) {
+        Some(p) => Err(ApiError::forbidden(format!("Cannot assign a role with {} permission", p))),
+        None => Ok(()),
+    }
+}
+
+// Password, status & role user lain hanya boleh diubah jika semua permission user tersebut
+// juga dimiliki caller, agar pengelola organisasi tidak bisa mengambil alih akun yang lebih tinggi
+async fn check_user_outranked(data: &AppState, auth: &AuthUser, target: &user::Model) -> Result<(), ApiError> {
+    if auth.is_super_admin() || auth.id == target.id {
+        return Ok(());
+    }
+    let target_permissions = role_permission_names(&data.db, target.user_role_id).await?;
+    match target_permissions.iter().find(|p| !auth.has(p)) {
+        Some(p) => Err(ApiError::forbidden(format!("Cannot change the password, status or role of a user with {} permission", p))),
+        None => Ok(()),
+    }
+}
+
+pub fn hash_password(password: &str) -> Result<String, ApiError> {
+    hash(password, DEFAULT_COST).map_err(|e| {
+        eprintln!("Failed to hash password: {:?}", e);
+        ApiError::internal()
+    })
+}
+
+// Tolak password yang tidak memenuhi policy dengan 422
+pub fn check_password_policy(data: &AppState, password: &str, username: &str) -> Result<(), ApiError> {
+    let problems = data.security.password.check("password", password, username);
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::validation(problems))
+    }
+}
+
+pub fn user_json(u: &user::Model) -> serde_json::Value {
+    serde_json::json!({
+        "id": u.id,
+        "username": u.username,
+        "user_role_id": u.user_role_id,
+        "full_name": u.full_name,
+        "phone": u.phone,
+        "email": u.email,
+        "language": u.language,
+        "timezone": u.timezone,
+        "status": u.status,
+        "locked_until": u.locked_until
+    })
+}
+
+// Pasangan snapshot user untuk audit log. Hash password tidak ikut disimpan,
+// cukup ditandai bahwa password berubah.
+pub fn audit_snapshots(before: &user::Model, after: &user::Model) -> (serde_json::Value, serde_json::Value) {
+    let (mut b, mut a) = (user_json(before), user_json(after));
+    if before.password != after.password {
+        b["password"] = serde_json::json!("[redacted]");
+        a["password"] = serde_json::json!("[changed]");
+    }
+    (b, a)
 }
 
 #[post("/users")]
 pub async fn create_user(
+    auth: AuthUser,
     data: web::Data<AppState>,
     form: web::Json<CreateUserRequest>,
-) -> impl Responder {
-    let hashed_password = match hash(&form.password, 12) {
-        Ok(h) => h,
-        Err(_) => {
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "success": false,
-                "error": "Failed to hash password"
-            }))
+) -> ApiResult {
+    auth.require(permissions::USER_MANAGE)?;
+    let org_role = form.org_role.unwrap_or(OrgRole::Member);
+    match form.organization_id {
+        Some(org_id) => {
+            tenancy::require_org(&auth, org_id, Access::Manage, "Organization")?;
+            if org_role == OrgRole::Owner && !auth.is_super_admin() && auth.org_role(org_id) != Some(OrgRole::Owner) {
+                return Err(ApiError::forbidden("Only an owner can add another owner"));
+            }
         }
-    };
+        None if auth.is_super_admin() => {}
+        None => return Err(ApiError::bad_request("organization_id is required").with_code("organization_required")),
+    }
+    check_role_assignable(&data, &auth, form.user_role_id).await?;
 
-    let new_user = user::ActiveModel {
+    let mut v = Validator::new(&data.db);
+    validate_profile(&mut v, Some(&form.username), &form.profile);
+    v.finish()?;
+    check_password_policy(&data, &form.password, &form.username)?;
+
+    let mut new_user = user::ActiveModel {
         username: Set(form.username.clone()),
-        password: Set(hashed_password),
+        password: Set(hash_password(&form.password)?),
         user_role_id: Set(form.user_role_id),
+        failed_login_attempts: Set(0),
+        locked_until: Set(None),
+        full_name: Set(None),
+        phone: Set(None),
+        email: Set(None),
+        language: Set(DEFAULT_LANGUAGE.to_string()),
+        timezone: Set(DEFAULT_TIMEZONE.to_string()),
+        status: Set(UserStatus::Active),
         ..Default::default()
     };
+    apply_profile(&mut new_user, &form.profile);
 
-    match new_user.insert(&data.db).await {
-        Ok(u) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "message": "User created successfully",
-            "data": {
-                "id": u.id,
-                "username": u.username,
-                "user_role_id": u.user_role_id
+    let txn = data.db.begin().await?;
+    let u = new_user.insert(&txn).await?;
+    let member = match form.organization_id {
+        Some(org_id) => Some(
+            organization_member::ActiveModel {
+                organization_id: Set(org_id),
+                user_id: Set(u.id),
+                role: Set(org_role),
+                ..Default::default()
             }
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
+            .insert(&txn)
+            .await?,
+        ),
+        None => None,
+    };
+    txn.commit().await?;
+
+    audit::created(&data.db, &auth, "user", u.id, &user_json(&u)).await;
+    if let Some(m) = member {
+        audit::created(&data.db, &auth, "organization_member", m.id, &m).await;
     }
+
+    Ok(response::ok_with_message("User created successfully", user_json(&u)))
 }
 
+// GET /users?q=&user_role_id=&status=&page=&per_page=
+// Selain super-admin, hanya anggota organisasi yang dikelola caller yang terlihat
 #[get("/users")]
-pub async fn get_users(data: web::Data<AppState>) -> impl Responder {
-    match user::Entity::find().all(&data.db).await {
-        Ok(users) => {
-            let result: Vec<_> = users
-                .into_iter()
-                .map(|u| serde_json::json!({
-                    "id": u.id,
-                    "username": u.username,
-                    "user_role_id": u.user_role_id
-                }))
-                .collect();
-
-            HttpResponse::Ok().json(serde_json::json!({
-                "success": true,
-                "data": result
-            }))
-        }
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
+pub async fn get_users(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    query: web::Query<UserQuery>,
+) -> ApiResult {
+    auth.require(permissions::USER_MANAGE)?;
+
+    let mut select = user::Entity::find_active().order_by_asc(user::Column::Username);
+
+    if let Some(member_ids) = tenancy::managed_user_ids(&data.db, &auth).await? {
+        select = select.filter(user::Column::Id.is_in(member_ids));
+    }
+
+    if let Some(q) = query.q.as_deref().and_then(optional) {
+        let pattern = format!("%{}%", q);
+        select = select.filter(
+            Condition::any()
+                .add(Expr::col(user::Column::Username).ilike(pattern.clone()))
+                .add(Expr::col(user::Column::FullName).ilike(pattern.clone()))
+                .add(Expr::col(user::Column::Email).ilike(pattern)),
+        );
     }
+    if let Some(role_id) = query.user_role_id {
+        select = select.filter(user::Column::UserRoleId.eq(role_id));
+    }
+    if let Some(status) = query.status {
+        select = select.filter(user::Column::Status.eq(status));
+    }
+
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(20).clamp(1, 100);
+    let paginator = select.paginate(&data.db, per_page);
+    let total = paginator.num_items().await?;
+    let users = paginator.fetch_page(page - 1).await?;
+
+    Ok(response::ok(serde_json::json!({
+        "items": users.iter().map(user_json).collect::<Vec<_>>(),
+        "page": page,
+        "per_page": per_page,
+        "total": total,
+        "total_pages": total.div_ceil(per_page)
+    })))
 }
 
 #[get("/users/{id}")]
 pub async fn get_user_by_id(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
     let id = path.into_inner();
+    tenancy::require_user_admin(&data.db, &auth, id).await?;
 
-    match user::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(u)) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "data": {
-                "id": u.id,
-                "username": u.username,
-                "user_role_id": u.user_role_id
-            }
-        })),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "success": false,
-            "error": "User not found"
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
-    }
+    let u = user::Entity::find_active_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("User"))?;
+
+    Ok(response::ok(user_json(&u)))
 }
 
+// Mengubah user lain (termasuk role) butuh user:manage; user biasa memakai /me
 #[put("/users/{id}")]
 pub async fn update_user(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
     form: web::Json<UpdateUserRequest>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::USER_MANAGE)?;
     let id = path.into_inner();
+    tenancy::require_user_admin(&data.db, &auth, id).await?;
 
-    let existing_user = match user::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(u)) => u,
-        Ok(None) => {
-            return HttpResponse::NotFound().json(serde_json::json!({
-                "success": false,
-                "error": "User not found"
-            }))
-        }
-        Err(e) => {
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "success": false,
-                "error": format!("Database error: {:?}", e)
-            }))
-        }
-    };
+    let existing_user = user::Entity::find_active_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("User"))?;
+
+    // Username dipakai untuk login, jadi menggantinya sama sensitifnya dengan ganti password
+    let username_changed = form.username.as_deref().is_some_and(|u| u != existing_user.username);
+    if username_changed || form.password.is_some() || form.status.is_some() || form.user_role_id.is_some() {
+        check_user_outranked(&data, &auth, &existing_user).await?;
+    }
+    if let Some(role_id) = form.user_role_id {
+        check_role_assignable(&data, &auth, role_id).await?;
+    }
+
+    let mut v = Validator::new(&data.db);
+    validate_profile(&mut v, form.username.as_deref(), &form.profile);
+    v.finish()?;
+
+    if let Some(password) = &form.password {
+        let username = form.username.as_deref().unwrap_or(&existing_user.username);
+        check_password_policy(&data, password, username)?;
+    }
 
+    // Ganti password / role / suspend membuat semua session & refresh token lama tidak berlaku
+    let revoke_sessions = form.password.is_some()
+        || form.user_role_id.is_some_and(|r| r != existing_user.user_role_id)
+        || form.status == Some(UserStatus::Suspended);
+
+    let before = existing_user.clone();
     let mut active_model: user::ActiveModel = existing_user.into();
 
     if let Some(username) = &form.username {
@@ -141,74 +362,117 @@ pub async fn update_user(
     }
 
     if let Some(password) = &form.password {
-        match hash(password, 12) {
-            Ok(h) => active_model.password = Set(h),
-            Err(_) => {
-                return HttpResponse::InternalServerError().json(serde_json::json!({
-                    "success": false,
-                    "error": "Failed to hash password"
-                }))
-            }
-        }
+        active_model.password = Set(hash_password(password)?);
     }
 
     if let Some(role_id) = form.user_role_id {
         active_model.user_role_id = Set(role_id);
     }
 
-    match active_model.update(&data.db).await {
-        Ok(u) => HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "message": "User updated successfully",
-            "data": {
-                "id": u.id,
-                "username": u.username,
-                "user_role_id": u.user_role_id
-            }
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
+    if let Some(status) = form.status {
+        active_model.status = Set(status);
+    }
+
+    apply_profile(&mut active_model, &form.profile);
+
+    let u = active_model.update(&data.db).await?;
+    let (b, a) = audit_snapshots(&before, &u);
+    audit::updated(&data.db, &auth, "user", u.id, &b, &a).await;
+    if revoke_sessions {
+        revoke_user_sessions(&data.db, u.id).await?;
+        revoke_user_refresh_tokens(&data.db, u.id).await?;
     }
+    Ok(response::ok_with_message("User updated successfully", user_json(&u)))
 }
 
+// Akun bisa jadi anggota beberapa organisasi, jadi penghapusan hanya oleh super-admin.
+// Admin organisasi cukup mengeluarkan user dari keanggotaan.
+// Akun di-soft delete: land yang menjadi tanggung jawabnya dialihkan ke owner lain organisasi
+// pemilik land, keanggotaannya dicabut, dan baris user tetap ada untuk land, audit & riwayat.
 #[delete("/users/{id}")]
 pub async fn delete_user(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::USER_MANAGE)?;
+    auth.require(permissions::PLATFORM_ADMIN)?;
     let id = path.into_inner();
+    if id == auth.id {
+        return Err(ApiError::conflict("Cannot delete your own account").with_code("self_delete"));
+    }
 
-    match user::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(u)) => {
-            // Konversi ke ActiveModel agar bisa dihapus
-            let active_user: user::ActiveModel = u.into();
-
-            match active_user.delete(&data.db).await {
-                Ok(DeleteResult { rows_affected }) if rows_affected > 0 => {
-                    HttpResponse::Ok().json(serde_json::json!({
-                        "success": true,
-                        "message": "User deleted successfully"
-                    }))
-                }
-                Ok(_) => HttpResponse::NotFound().json(serde_json::json!({
-                    "success": false,
-                    "error": "User not found or already deleted"
-                })),
-                Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-                    "success": false,
-                    "error": format!("Database error: {:?}", e)
-                })),
-            }
-        }
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({
-            "success": false,
-            "error": "User not found"
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": format!("Database error: {:?}", e)
-        })),
+    let u = user::Entity::find_active_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("User"))?;
+
+    let txn = data.db.begin().await?;
+    let lands = land::Entity::find().filter(land::Column::UserId.eq(id)).all(&txn).await?;
+    let mut reassigned = Vec::new();
+    for l in lands {
+        let Some(owner) = organization_member::Entity::find()
+            .filter(organization_member::Column::OrganizationId.eq(l.organization_id))
+            .filter(organization_member::Column::Role.eq(OrgRole::Owner))
+            .filter(organization_member::Column::UserId.ne(id))
+            .order_by_asc(organization_member::Column::Id)
+            .one(&txn)
+            .await?
+        else {
+            return Err(ApiError::conflict(format!(
+                "Land \"{}\" has no other organization owner to take it over",
+                l.location_name
+            ))
+            .with_code("land_without_owner"));
+        };
+        let before = l.clone();
+        let mut active: land::ActiveModel = l.into();
+        active.user_id = Set(owner.user_id);
+        reassigned.push((before, active.update(&txn).await?));
+    }
+
+    organization_member::Entity::delete_many()
+        .filter(organization_member::Column::UserId.eq(id))
+        .exec(&txn)
+        .await?;
+    let mut active: user::ActiveModel = u.clone().into();
+    active.status = Set(UserStatus::Suspended);
+    active.deleted_at = Set(Some(soft_delete::now()));
+    active.update(&txn).await?;
+    txn.commit().await?;
+
+    revoke_user_sessions(&data.db, id).await?;
+    revoke_user_refresh_tokens(&data.db, id).await?;
+    for (before, after) in &reassigned {
+        audit::updated(&data.db, &auth, "land", after.id, before, after).await;
     }
+    audit::deleted(&data.db, &auth, "user", id, &user_json(&u)).await;
+
+    Ok(response::message("User deleted successfully"))
+}
+
+// Admin membuka kunci akun yang terkena lockout
+#[post("/users/{id}/unlock")]
+pub async fn unlock_user(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    auth.require(permissions::USER_MANAGE)?;
+    let id = path.into_inner();
+    tenancy::require_user_admin(&data.db, &auth, id).await?;
+
+    let existing_user = user::Entity::find_active_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("User"))?;
+
+    let before = existing_user.clone();
+    let mut active_model: user::ActiveModel = existing_user.into();
+    active_model.failed_login_attempts = Set(0);
+    active_model.locked_until = Set(None);
+
+    let u = active_model.update(&data.db).await?;
+    audit::updated(&data.db, &auth, "user", u.id, &user_json(&before), &user_json(&u)).await;
+    Ok(response::ok_with_message("User unlocked successfully", user_json(&u)))
 }
@@ -1,98 +1,160 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, DeleteResult};
+use actix_web::{get, post, put, delete, web};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
 use serde::Deserialize;
 
-use crate::{AppState, models::valve};
+use crate::{AppState, audit, error::{ApiError, ApiResult}, extractors::AuthUser, models::{automation, valve}, permissions, response, soft_delete::{self, SoftDelete}, tenancy::{self, Access}, validation::Validator};
 
 #[derive(Deserialize)]
 pub struct CreateValveRequest {
     pub name: String,
     pub land_id: i32,
+    #[serde(default)]
+    pub is_open: bool,
+    pub max_runtime_seconds: Option<i32>,
+    pub max_daily_volume: Option<f64>,
+    pub cooldown_seconds: Option<i32>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
 }
 
+// PUT mengganti seluruh konfigurasi; batas yang tidak dikirim berarti tidak dibatasi, lokasi yang tidak dikirim dihapus
 #[derive(Deserialize)]
 pub struct UpdateValveRequest {
     pub name: String,
+    pub is_open: Option<bool>,
+    pub max_runtime_seconds: Option<i32>,
+    pub max_daily_volume: Option<f64>,
+    pub cooldown_seconds: Option<i32>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
 }
 
 #[post("/valves")]
 pub async fn create_valve(
+    auth: AuthUser,
     data: web::Data<AppState>,
     form: web::Json<CreateValveRequest>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    tenancy::land_access(&data.db, &auth, form.land_id, Access::Write).await?;
+    let mut v = Validator::new(&data.db);
+    v.coordinates("", form.latitude, form.longitude);
+    v.positive("max_runtime_seconds", form.max_runtime_seconds.map(f64::from));
+    v.positive("max_daily_volume", form.max_daily_volume);
+    v.positive("cooldown_seconds", form.cooldown_seconds.map(f64::from));
+    v.finish()?;
+
     let new_valve = valve::ActiveModel {
         name: Set(form.name.clone()),
         land_id: Set(form.land_id),
+        is_open: Set(form.is_open),
+        max_runtime_seconds: Set(form.max_runtime_seconds),
+        max_daily_volume: Set(form.max_daily_volume),
+        cooldown_seconds: Set(form.cooldown_seconds),
+        latitude: Set(form.latitude),
+        longitude: Set(form.longitude),
         ..Default::default()
     };
 
-    match new_valve.insert(&data.db).await {
-        Ok(v) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": v })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
+    let v = new_valve.insert(&data.db).await?;
+    audit::created(&data.db, &auth, "valve", v.id, &v).await;
+    Ok(response::ok(v))
 }
 
 #[get("/lands/{land_id}/valves")]
 pub async fn get_valves_by_land(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
     let land_id = path.into_inner();
-    match valve::Entity::find().filter(valve::Column::LandId.eq(land_id)).all(&data.db).await {
-        Ok(valves) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": valves })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
+    tenancy::land_access(&data.db, &auth, land_id, Access::Read).await?;
+    let valves = valve::Entity::find_active().filter(valve::Column::LandId.eq(land_id)).all(&data.db).await?;
+    Ok(response::ok(valves))
 }
 
 #[get("/valves/{id}")]
 pub async fn get_valve_by_id(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
     let id = path.into_inner();
-    match valve::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(v)) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": v })),
-        Ok(None) => HttpResponse::NotFound().json(serde_json::json!({ "success": false, "error": "Valve not found" })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
+    let v = valve::Entity::find_active_by_id(id).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Valve"))?;
+    tenancy::land_access(&data.db, &auth, v.land_id, Access::Read).await?;
+    Ok(response::ok(v))
 }
 
 #[put("/valves/{id}")]
 pub async fn update_valve(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
     form: web::Json<UpdateValveRequest>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
     let id = path.into_inner();
-    let existing = match valve::Entity::find_by_id(id).one(&data.db).await {
-        Ok(Some(v)) => v,
-        Ok(None) => return HttpResponse::NotFound().finish(),
-        Err(_) => return HttpResponse::InternalServerError().finish(),
-    };
+    let existing = valve::Entity::find_active_by_id(id).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Valve"))?;
+    tenancy::land_access(&data.db, &auth, existing.land_id, Access::Write).await?;
+    let mut v = Validator::new(&data.db);
+    v.coordinates("", form.latitude, form.longitude);
+    v.positive("max_runtime_seconds", form.max_runtime_seconds.map(f64::from));
+    v.positive("max_daily_volume", form.max_daily_volume);
+    v.positive("cooldown_seconds", form.cooldown_seconds.map(f64::from));
+    v.finish()?;
 
+    let before = existing.clone();
     let mut active: valve::ActiveModel = existing.into();
     active.name = Set(form.name.clone());
+    if let Some(is_open) = form.is_open { active.is_open = Set(is_open); }
+    active.max_runtime_seconds = Set(form.max_runtime_seconds);
+    active.max_daily_volume = Set(form.max_daily_volume);
+    active.cooldown_seconds = Set(form.cooldown_seconds);
+    active.latitude = Set(form.latitude);
+    active.longitude = Set(form.longitude);
 
-    match active.update(&data.db).await {
-        Ok(v) => HttpResponse::Ok().json(serde_json::json!({ "success": true, "data": v })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
+    let v = active.update(&data.db).await?;
+    audit::updated(&data.db, &auth, "valve", v.id, &before, &v).await;
+    Ok(response::ok(v))
 }
 
 #[delete("/valves/{id}")]
 pub async fn delete_valve(
+    auth: AuthUser,
     data: web::Data<AppState>,
     path: web::Path<i32>,
-) -> impl Responder {
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
     let id = path.into_inner();
-    match valve::Entity::delete_by_id(id).exec(&data.db).await {
-        Ok(res) => {
-            if res.rows_affected > 0 {
-                HttpResponse::Ok().json(serde_json::json!({ "success": true }))
-            } else {
-                HttpResponse::NotFound().json(serde_json::json!({ "success": false, "error": "Not found" }))
-            }
-        }
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": e.to_string() })),
-    }
-}
\ No newline at end of file
+    let existing = valve::Entity::find_active_by_id(id).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Valve"))?;
+    tenancy::land_access(&data.db, &auth, existing.land_id, Access::Write).await?;
+    // Automation yang memakai katup ini ikut masuk trash
+    let txn = data.db.begin().await?;
+    soft_delete::delete_device::<valve::Entity, _>(&txn, id, automation::Column::ValveId, soft_delete::now()).await?;
+    txn.commit().await?;
+    audit::deleted(&data.db, &auth, "valve", id, &existing).await;
+    Ok(response::message("Valve deleted successfully"))
+}
+
+#[post("/valves/{id}/restore")]
+pub async fn restore_valve(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let id = path.into_inner();
+    let existing = valve::Entity::find_by_id(id).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Valve"))?;
+    tenancy::restorable_land(&data.db, &auth, existing.land_id).await?;
+    let Some(deleted_at) = existing.deleted_at else {
+        return Err(ApiError::conflict("Valve is not deleted").with_code("not_deleted"));
+    };
+
+    let txn = data.db.begin().await?;
+    soft_delete::restore_device::<valve::Entity, _>(&txn, id, automation::Column::ValveId, deleted_at).await?;
+    txn.commit().await?;
+
+    let v = valve::Entity::find_active_by_id(id).one(&data.db).await?.ok_or_else(|| ApiError::not_found("Valve"))?;
+    audit::updated(&data.db, &auth, "valve", id, &existing, &v).await;
+    Ok(response::ok_with_message("Valve restored successfully", v))
+}
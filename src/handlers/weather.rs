@@ -0,0 +1,168 @@
+use actix_web::{get, post, web};
+use chrono::{Duration, Local, NaiveDateTime};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    models::{
+        automation::{self, WeatherMode},
+        weather::{self as weather_model, WeatherKind},
+    },
+    permissions,
+    response,
+    soft_delete::SoftDelete,
+    tenancy::{self, Access},
+    validation::Validator,
+    weather::{self, WeatherReading},
+};
+
+// Data dari stasiun cuaca lokal atau sistem lain; `source` default "manual"
+#[derive(Deserialize)]
+pub struct IngestWeatherRequest {
+    pub source: Option<String>,
+    pub readings: Vec<WeatherReading>,
+}
+
+// Tanpa from/to: 24 jam terakhir sampai 48 jam ke depan
+#[derive(Deserialize)]
+pub struct WeatherQuery {
+    pub kind: Option<WeatherKind>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
+#[derive(Deserialize)]
+pub struct OutlookQuery {
+    pub lookback_hours: Option<i32>,
+    pub forecast_hours: Option<i32>,
+}
+
+#[post("/lands/{land_id}/weather")]
+pub async fn ingest_weather(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    form: web::Json<IngestWeatherRequest>,
+) -> ApiResult {
+    auth.require(permissions::SENSOR_INGEST)?;
+    let l = tenancy::land_access(&data.db, &auth, path.into_inner(), Access::Write).await?;
+
+    let mut v = Validator::new(&data.db);
+    if form.readings.is_empty() {
+        v.add("readings", "Must not be empty");
+    }
+    for (i, r) in form.readings.iter().enumerate() {
+        for (field, message) in weather::reading_errors(r) {
+            v.add(&format!("readings[{}].{}", i, field), message);
+        }
+    }
+    let source = form.source.clone().unwrap_or_else(|| "manual".to_string());
+    v.max_length("source", &source, 50);
+    v.finish()?;
+
+    let stored = weather::store(&data.db, l.id, &source, &form.readings).await?;
+    Ok(response::ok_with_message("Weather data stored", serde_json::json!({ "stored": stored })))
+}
+
+#[get("/lands/{land_id}/weather")]
+pub async fn get_land_weather(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    query: web::Query<WeatherQuery>,
+) -> ApiResult {
+    let l = tenancy::land_access(&data.db, &auth, path.into_inner(), Access::Read).await?;
+
+    let now = Local::now().naive_local();
+    let from = query.from.unwrap_or(now - Duration::days(1));
+    let to = query.to.unwrap_or(now + Duration::days(2));
+    if from >= to {
+        return Err(ApiError::bad_request("from must be before to").with_code("invalid_range"));
+    }
+
+    let mut select = weather_model::Entity::find()
+        .filter(weather_model::Column::LandId.eq(l.id))
+        .filter(weather_model::Column::ValidAt.between(from, to));
+    if let Some(kind) = query.kind {
+        select = select.filter(weather_model::Column::Kind.eq(kind));
+    }
+    let records = select
+        .order_by_asc(weather_model::Column::ValidAt)
+        .order_by_asc(weather_model::Column::Kind)
+        .all(&data.db)
+        .await?;
+
+    Ok(response::ok(records))
+}
+
+// Ringkasan hujan land beserta keputusan setiap automation yang punya aturan hujan saat ini
+#[get("/lands/{land_id}/weather/outlook")]
+pub async fn get_weather_outlook(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    query: web::Query<OutlookQuery>,
+) -> ApiResult {
+    let l = tenancy::land_access(&data.db, &auth, path.into_inner(), Access::Read).await?;
+
+    let lookback_hours = query.lookback_hours.unwrap_or(weather::DEFAULT_LOOKBACK_HOURS);
+    let forecast_hours = query.forecast_hours.unwrap_or(weather::DEFAULT_FORECAST_HOURS);
+    let mut v = Validator::new(&data.db);
+    for (field, hours) in [("lookback_hours", lookback_hours), ("forecast_hours", forecast_hours)] {
+        if !(0..=weather::MAX_WINDOW_HOURS).contains(&hours) {
+            v.add(field, format!("Must be between 0 and {}", weather::MAX_WINDOW_HOURS));
+        }
+    }
+    v.finish()?;
+
+    let now = Local::now().naive_local();
+    let rain = weather::outlook(&data.db, l.id, lookback_hours, forecast_hours, now).await?;
+
+    let automations = automation::Entity::find_active()
+        .filter(automation::Column::LandId.eq(l.id))
+        .filter(automation::Column::WeatherMode.ne(WeatherMode::Ignore))
+        .all(&data.db)
+        .await?;
+    let mut decisions = Vec::new();
+    for a in &automations {
+        decisions.push(serde_json::json!({
+            "automation_id": a.id,
+            "name": a.name,
+            "weather_mode": a.weather_mode,
+            "rain_threshold_mm": a.rain_threshold_mm,
+            "result": weather::assess(&data.db, a, now).await?
+        }));
+    }
+
+    Ok(response::ok(serde_json::json!({
+        "land_id": l.id,
+        "rain": rain,
+        "automations": decisions
+    })))
+}
+
+// Ambil data dari provider sekarang juga, tanpa menunggu job berkala
+#[post("/lands/{land_id}/weather/refresh")]
+pub async fn refresh_weather(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let l = tenancy::land_access(&data.db, &auth, path.into_inner(), Access::Write).await?;
+    let Some(provider) = &data.weather_provider else {
+        return Err(ApiError::bad_request("No weather provider is configured").with_code("weather_provider_disabled"));
+    };
+
+    let stored = weather::refresh(&data.db, provider.as_ref(), &l).await.map_err(|e| {
+        eprintln!("Weather: failed to refresh land #{}: {:?}", l.id, e);
+        ApiError::internal().with_code("weather_provider_failed")
+    })?;
+    Ok(response::ok_with_message(
+        "Weather data refreshed",
+        serde_json::json!({ "provider": provider.name(), "stored": stored }),
+    ))
+}
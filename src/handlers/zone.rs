@@ -0,0 +1,328 @@
+use actix_web::{delete, get, post, put, web};
+use sea_orm::{
+    ActiveModelBehavior, ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel, QueryFilter,
+    Set, TransactionTrait,
+};
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    audit,
+    error::{ApiError, ApiResult},
+    extractors::AuthUser,
+    geo::{self, Ring},
+    models::{automation, land, plant, sensor, valve, zone},
+    permissions,
+    response,
+    soft_delete::{self, SoftDelete},
+    tenancy::{self, Access},
+    validation::{FieldError, Validator},
+    zones,
+};
+
+#[derive(Deserialize)]
+pub struct CreateZoneRequest {
+    pub name: String,
+    pub area: f64,
+    pub polygon: Option<Ring>,
+}
+
+// PUT mengganti seluruh data zona; polygon yang tidak dikirim berarti dihapus
+#[derive(Deserialize)]
+pub struct UpdateZoneRequest {
+    pub name: String,
+    pub area: f64,
+    pub polygon: Option<Ring>,
+}
+
+// Tempatkan perangkat & tanaman ke satu zona (zone_id null = keluarkan dari zona)
+#[derive(Deserialize)]
+pub struct ZoneAssignmentRequest {
+    pub zone_id: Option<i32>,
+    #[serde(default)]
+    pub plant_ids: Vec<i32>,
+    #[serde(default)]
+    pub sensor_ids: Vec<i32>,
+    #[serde(default)]
+    pub valve_ids: Vec<i32>,
+}
+
+// Nama, luas (tidak melebihi sisa luas land) & polygon yang luasnya cocok
+async fn validate_zone(
+    data: &AppState,
+    l: &land::Model,
+    except: Option<i32>,
+    name: &str,
+    area: f64,
+    polygon: Option<&Ring>,
+) -> Result<(), ApiError> {
+    let mut v = Validator::new(&data.db);
+    v.max_length("name", name, 255);
+    if !area.is_finite() || area <= 0.0 {
+        v.add("area", "Must be greater than 0");
+    } else {
+        let allocated = zones::allocated_area(&data.db, l.id, except).await?;
+        if allocated + area > l.size {
+            v.add(
+                "area",
+                format!("Zones would cover {} but the land size is {}", allocated + area, l.size),
+            );
+        }
+    }
+    if let Some(ring) = polygon {
+        v.polygon("polygon", ring, area);
+    }
+    v.finish()
+}
+
+async fn zone_access(data: &AppState, auth: &AuthUser, id: i32, access: Access) -> Result<(zone::Model, land::Model), ApiError> {
+    let z = zone::Entity::find_active_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Zone"))?;
+    let l = tenancy::land_access(&data.db, auth, z.land_id, access).await?;
+    Ok((z, l))
+}
+
+#[post("/lands/{land_id}/zones")]
+pub async fn create_zone(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    form: web::Json<CreateZoneRequest>,
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let l = tenancy::land_access(&data.db, &auth, path.into_inner(), Access::Write).await?;
+    let form = form.into_inner();
+    let polygon = form.polygon.map(geo::normalize);
+    validate_zone(&data, &l, None, &form.name, form.area, polygon.as_ref()).await?;
+
+    let z = zone::ActiveModel {
+        land_id: Set(l.id),
+        name: Set(form.name),
+        area: Set(form.area),
+        polygon: Set(polygon.as_ref().map(geo::to_json)),
+        ..Default::default()
+    }
+    .insert(&data.db)
+    .await?;
+
+    audit::created(&data.db, &auth, "zone", z.id, &z).await;
+    Ok(response::ok_with_message("Zone created successfully", z))
+}
+
+#[get("/lands/{land_id}/zones")]
+pub async fn get_zones_by_land(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    let l = tenancy::land_access(&data.db, &auth, path.into_inner(), Access::Read).await?;
+
+    let result = zone::Entity::find_active()
+        .filter(zone::Column::LandId.eq(l.id))
+        .all(&data.db)
+        .await?;
+
+    Ok(response::ok(result))
+}
+
+// Zona beserta tanaman, sensor, katup & automation di dalamnya
+#[get("/zones/{id}")]
+pub async fn get_zone_by_id(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    let (z, _) = zone_access(&data, &auth, path.into_inner(), Access::Read).await?;
+
+    let plants = plant::Entity::find_active().filter(plant::Column::ZoneId.eq(z.id)).all(&data.db).await?;
+    let sensors = sensor::Entity::find_active().filter(sensor::Column::ZoneId.eq(z.id)).all(&data.db).await?;
+    let valves = valve::Entity::find_active().filter(valve::Column::ZoneId.eq(z.id)).all(&data.db).await?;
+    let automations = automation::Entity::find_active()
+        .filter(automation::Column::ZoneId.eq(z.id))
+        .all(&data.db)
+        .await?;
+
+    Ok(response::ok(serde_json::json!({
+        "zone": z,
+        "plants": plants,
+        "sensors": sensors,
+        "valves": valves,
+        "automations": automations
+    })))
+}
+
+#[put("/zones/{id}")]
+pub async fn update_zone(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    form: web::Json<UpdateZoneRequest>,
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let (existing, l) = zone_access(&data, &auth, path.into_inner(), Access::Write).await?;
+    let form = form.into_inner();
+    let polygon = form.polygon.map(geo::normalize);
+    validate_zone(&data, &l, Some(existing.id), &form.name, form.area, polygon.as_ref()).await?;
+
+    let before = existing.clone();
+    let mut active: zone::ActiveModel = existing.into();
+    active.name = Set(form.name);
+    active.area = Set(form.area);
+    active.polygon = Set(polygon.as_ref().map(geo::to_json));
+
+    let z = active.update(&data.db).await?;
+    audit::updated(&data.db, &auth, "zone", z.id, &before, &z).await;
+    Ok(response::ok_with_message("Zone updated successfully", z))
+}
+
+// Zona yang masih berisi harus dikosongkan dulu lewat zone-assignments
+#[delete("/zones/{id}")]
+pub async fn delete_zone(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let (z, _) = zone_access(&data, &auth, path.into_inner(), Access::Write).await?;
+
+    let in_use = plant::Entity::find_active().filter(plant::Column::ZoneId.eq(z.id)).one(&data.db).await?.is_some()
+        || sensor::Entity::find_active().filter(sensor::Column::ZoneId.eq(z.id)).one(&data.db).await?.is_some()
+        || valve::Entity::find_active().filter(valve::Column::ZoneId.eq(z.id)).one(&data.db).await?.is_some();
+    if in_use {
+        return Err(ApiError::conflict("Move plants, sensors and valves out of the zone first").with_code("zone_in_use"));
+    }
+
+    soft_delete::delete::<zone::Entity, _>(&data.db, z.id, soft_delete::now()).await?;
+    audit::deleted(&data.db, &auth, "zone", z.id, &z).await;
+
+    Ok(response::message("Zone deleted successfully"))
+}
+
+#[post("/zones/{id}/restore")]
+pub async fn restore_zone(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let id = path.into_inner();
+
+    let z = zone::Entity::find_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Zone"))?;
+    let l = tenancy::restorable_land(&data.db, &auth, z.land_id).await?;
+    let Some(deleted_at) = z.deleted_at else {
+        return Err(ApiError::conflict("Zone is not deleted").with_code("not_deleted"));
+    };
+    if zones::allocated_area(&data.db, z.land_id, None).await? + z.area > l.size {
+        return Err(ApiError::conflict("Restoring the zone would exceed the land size").with_code("area_exceeded"));
+    }
+
+    soft_delete::restore::<zone::Entity, _>(&data.db, id, deleted_at).await?;
+
+    let restored = zone::Entity::find_active_by_id(id)
+        .one(&data.db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Zone"))?;
+    audit::updated(&data.db, &auth, "zone", id, &z, &restored).await;
+    Ok(response::ok_with_message("Zone restored successfully", restored))
+}
+
+// Ubah zone_id baris-baris yang berubah; kembalikan pasangan sebelum/sesudah untuk audit
+async fn reassign<E, C>(
+    db: &C,
+    rows: Vec<E::Model>,
+    zone_id: Option<i32>,
+    current: impl Fn(&E::Model) -> Option<i32>,
+    set: impl Fn(&mut E::ActiveModel),
+) -> Result<Vec<(E::Model, E::Model)>, DbErr>
+where
+    E: EntityTrait,
+    C: ConnectionTrait,
+    E::Model: IntoActiveModel<E::ActiveModel> + Clone,
+    E::ActiveModel: ActiveModelTrait<Entity = E> + ActiveModelBehavior + Send,
+{
+    let mut changed = Vec::new();
+    for row in rows {
+        if current(&row) == zone_id {
+            continue;
+        }
+        let mut active = row.clone().into_active_model();
+        set(&mut active);
+        changed.push((row, active.update(db).await?));
+    }
+    Ok(changed)
+}
+
+// Automation otomatis mengikuti zona katupnya; penempatan yang membuat automation zona A
+// dipicu sensor zona B ditolak seluruhnya
+#[put("/lands/{land_id}/zone-assignments")]
+pub async fn assign_zone(
+    auth: AuthUser,
+    data: web::Data<AppState>,
+    path: web::Path<i32>,
+    form: web::Json<ZoneAssignmentRequest>,
+) -> ApiResult {
+    auth.require(permissions::LAND_WRITE)?;
+    let l = tenancy::land_access(&data.db, &auth, path.into_inner(), Access::Write).await?;
+
+    let mut v = Validator::new(&data.db);
+    if let Some(zone_id) = form.zone_id {
+        match zone::Entity::find_active_by_id(zone_id).one(&data.db).await? {
+            Some(z) if z.land_id == l.id => {}
+            _ => v.add("zone_id", format!("Zone {} does not exist on this land", zone_id)),
+        }
+    }
+    for (i, id) in form.plant_ids.iter().enumerate() {
+        v.plant_on_land(&format!("plant_ids[{}]", i), *id, l.id).await?;
+    }
+    for (i, id) in form.sensor_ids.iter().enumerate() {
+        v.sensor_on_land(&format!("sensor_ids[{}]", i), *id, l.id).await?;
+    }
+    for (i, id) in form.valve_ids.iter().enumerate() {
+        v.valve_on_land(&format!("valve_ids[{}]", i), *id, l.id).await?;
+    }
+    v.finish()?;
+
+    let zone_id = form.zone_id;
+    let txn = data.db.begin().await?;
+    let plants = plant::Entity::find_active().filter(plant::Column::Id.is_in(form.plant_ids.clone())).all(&txn).await?;
+    let plants = reassign::<plant::Entity, _>(&txn, plants, zone_id, |p| p.zone_id, |a| a.zone_id = Set(zone_id)).await?;
+    let sensors = sensor::Entity::find_active().filter(sensor::Column::Id.is_in(form.sensor_ids.clone())).all(&txn).await?;
+    let sensors = reassign::<sensor::Entity, _>(&txn, sensors, zone_id, |s| s.zone_id, |a| a.zone_id = Set(zone_id)).await?;
+    let valves = valve::Entity::find_active().filter(valve::Column::Id.is_in(form.valve_ids.clone())).all(&txn).await?;
+    let valves = reassign::<valve::Entity, _>(&txn, valves, zone_id, |x| x.zone_id, |a| a.zone_id = Set(zone_id)).await?;
+
+    let conflicts = zones::sync_automations(&txn, l.id).await?;
+    if !conflicts.is_empty() {
+        txn.rollback().await?;
+        let fields = conflicts
+            .into_iter()
+            .map(|message| FieldError { field: "zone_id".to_string(), message })
+            .collect();
+        return Err(ApiError::validation(fields));
+    }
+    txn.commit().await?;
+
+    for (before, after) in &plants {
+        audit::updated(&data.db, &auth, "plant", after.id, before, after).await;
+    }
+    for (before, after) in &sensors {
+        audit::updated(&data.db, &auth, "sensor", after.id, before, after).await;
+    }
+    for (before, after) in &valves {
+        audit::updated(&data.db, &auth, "valve", after.id, before, after).await;
+    }
+
+    Ok(response::ok_with_message(
+        "Zone assignments updated successfully",
+        serde_json::json!({
+            "plants": plants.into_iter().map(|(_, p)| p).collect::<Vec<_>>(),
+            "sensors": sensors.into_iter().map(|(_, s)| s).collect::<Vec<_>>(),
+            "valves": valves.into_iter().map(|(_, x)| x).collect::<Vec<_>>()
+        }),
+    ))
+}
@@ -0,0 +1,369 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Local, NaiveDateTime};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QuerySelect, Set, TransactionTrait,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    config::RetentionConfig,
+    models::{
+        import_job::{self, ImportStatus},
+        sensor, sensor_history, sensor_history_daily,
+    },
+    rollup::{self, Policy, Resolution},
+    soft_delete::SoftDelete,
+};
+
+// Baris per transaksi; progress disimpan setiap batch
+const BATCH_SIZE: usize = 500;
+// Error per baris yang disimpan di job (jumlah totalnya tetap dihitung)
+const MAX_STORED_ERRORS: usize = 500;
+
+const TIMESTAMP_HEADERS: &[&str] = &["timestamp", "recorded_at", "time"];
+const SENSOR_HEADERS: &[&str] = &["sensor_id", "sensor", "sensor_name"];
+const VALUE_HEADERS: &[&str] = &["value"];
+
+// Posisi kolom wajib di CSV; kolom lain diabaikan
+struct Columns {
+    timestamp: usize,
+    sensor: usize,
+    value: usize,
+}
+
+impl Columns {
+    fn from_headers(headers: &csv::StringRecord) -> Result<Self, String> {
+        let find = |names: &[&str]| {
+            headers
+                .iter()
+                .position(|h| names.contains(&h.to_lowercase().as_str()))
+        };
+        match (find(TIMESTAMP_HEADERS), find(SENSOR_HEADERS), find(VALUE_HEADERS)) {
+            (Some(timestamp), Some(sensor), Some(value)) => Ok(Self { timestamp, sensor, value }),
+            _ => Err("CSV header must contain timestamp, sensor (name or id) and value columns".to_string()),
+        }
+    }
+}
+
+fn reader(payload: &str) -> csv::Reader<&[u8]> {
+    csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(payload.as_bytes())
+}
+
+// Cek header & hitung jumlah baris sebelum job dibuat
+pub fn inspect(payload: &str) -> Result<i32, String> {
+    let mut r = reader(payload);
+    let headers = r.headers().map_err(|e| e.to_string())?.clone();
+    Columns::from_headers(&headers)?;
+
+    let mut total = 0;
+    for record in r.records() {
+        record.map_err(|e| e.to_string())?;
+        total += 1;
+    }
+    if total == 0 {
+        return Err("CSV contains no readings".to_string());
+    }
+    Ok(total)
+}
+
+// Sensor aktif di land beserta masa simpan data mentahnya
+struct LandSensors {
+    policies: HashMap<i32, Policy>,
+    // None = nama dipakai lebih dari satu sensor
+    by_name: HashMap<String, Option<i32>>,
+}
+
+impl LandSensors {
+    async fn load(db: &DatabaseConnection, retention: &RetentionConfig, land_id: i32) -> Result<Self, DbErr> {
+        let sensors = sensor::Entity::find_active()
+            .filter(sensor::Column::LandId.eq(land_id))
+            .all(db)
+            .await?;
+
+        let mut policies = HashMap::new();
+        let mut by_name = HashMap::new();
+        for s in sensors {
+            policies.insert(s.id, rollup::effective_policy(db, retention, &s).await?);
+            by_name
+                .entry(s.name.clone())
+                .and_modify(|id| *id = None)
+                .or_insert(Some(s.id));
+        }
+        Ok(Self { policies, by_name })
+    }
+
+    // Nama sensor lebih dulu, baru id (nama sensor bisa saja berupa angka)
+    fn resolve(&self, cell: &str) -> Result<i32, String> {
+        match self.by_name.get(cell) {
+            Some(Some(id)) => return Ok(*id),
+            Some(None) => return Err(format!("Sensor name '{}' is ambiguous, use the sensor id", cell)),
+            None => {}
+        }
+        cell.parse::<i32>()
+            .ok()
+            .filter(|id| self.policies.contains_key(id))
+            .ok_or_else(|| format!("Unknown sensor '{}' on this land", cell))
+    }
+}
+
+fn parse_timestamp(s: &str) -> Option<NaiveDateTime> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Some(t.with_timezone(&Local).naive_local());
+    }
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(s, f).ok())
+}
+
+struct Reading {
+    line: u64,
+    sensor_id: i32,
+    recorded_at: NaiveDateTime,
+    value: f64,
+}
+
+fn parse_row(record: &csv::StringRecord, line: u64, cols: &Columns, sensors: &LandSensors, now: NaiveDateTime) -> Result<Reading, String> {
+    let field = |i: usize, name: &str| {
+        record
+            .get(i)
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| format!("Missing {}", name))
+    };
+
+    let raw_time = field(cols.timestamp, "timestamp")?;
+    let recorded_at = parse_timestamp(raw_time).ok_or_else(|| format!("Invalid timestamp '{}'", raw_time))?;
+    if recorded_at > now {
+        return Err("Timestamp is in the future".to_string());
+    }
+
+    let sensor_id = sensors.resolve(field(cols.sensor, "sensor")?)?;
+
+    let raw_value = field(cols.value, "value")?;
+    let value = raw_value
+        .parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+        .ok_or_else(|| format!("Invalid value '{}'", raw_value))?;
+
+    Ok(Reading { line, sensor_id, recorded_at, value })
+}
+
+fn row_error(line: u64, message: impl Into<String>) -> Value {
+    json!({ "row": line, "message": message.into() })
+}
+
+// Hari yang sudah diringkas dan data mentahnya sudah lewat masa simpan: rollup menghitung
+// agregat dari data mentah, jadi pembacaan baru di sini akan menimpa agregat yang lama.
+async fn reject_purged_days<C: ConnectionTrait>(
+    db: &C,
+    sensors: &LandSensors,
+    readings: Vec<Reading>,
+    now: NaiveDateTime,
+    errors: &mut Vec<Value>,
+) -> Result<Vec<Reading>, DbErr> {
+    let is_old = |r: &Reading| {
+        sensors.policies[&r.sensor_id]
+            .cutoff(Resolution::Raw, now)
+            .is_some_and(|cutoff| r.recorded_at < cutoff)
+    };
+    let old: Vec<&Reading> = readings.iter().filter(|r| is_old(r)).collect();
+    if old.is_empty() {
+        return Ok(readings);
+    }
+
+    let days: HashSet<(i32, NaiveDateTime)> = sensor_history_daily::Entity::find()
+        .select_only()
+        .column(sensor_history_daily::Column::SensorId)
+        .column(sensor_history_daily::Column::BucketStart)
+        .filter(sensor_history_daily::Column::SensorId.is_in(old.iter().map(|r| r.sensor_id)))
+        .filter(
+            sensor_history_daily::Column::BucketStart
+                .is_in(old.iter().map(|r| rollup::bucket_floor(Resolution::Daily, r.recorded_at))),
+        )
+        .into_tuple()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect();
+
+    let mut kept = Vec::with_capacity(readings.len());
+    for r in readings {
+        if is_old(&r) && days.contains(&(r.sensor_id, rollup::bucket_floor(Resolution::Daily, r.recorded_at))) {
+            errors.push(row_error(r.line, "Readings for this day were already rolled up and purged by retention"));
+        } else {
+            kept.push(r);
+        }
+    }
+    Ok(kept)
+}
+
+// Buang pembacaan yang (sensor_id, recorded_at)-nya sudah ada, di database maupun di batch ini
+async fn dedupe<C: ConnectionTrait>(db: &C, readings: Vec<Reading>) -> Result<Vec<Reading>, DbErr> {
+    if readings.is_empty() {
+        return Ok(readings);
+    }
+
+    let existing: Vec<(i32, NaiveDateTime)> = sensor_history::Entity::find()
+        .select_only()
+        .column(sensor_history::Column::SensorId)
+        .column(sensor_history::Column::RecordedAt)
+        .filter(sensor_history::Column::SensorId.is_in(readings.iter().map(|r| r.sensor_id)))
+        .filter(sensor_history::Column::RecordedAt.is_in(readings.iter().map(|r| r.recorded_at)))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let mut seen: HashSet<(i32, NaiveDateTime)> = existing.into_iter().collect();
+    Ok(readings
+        .into_iter()
+        .filter(|r| seen.insert((r.sensor_id, r.recorded_at)))
+        .collect())
+}
+
+// Ambil alih job; false jika sedang dijalankan proses lain atau sudah selesai
+async fn claim(db: &DatabaseConnection, job_id: i32) -> Result<bool, DbErr> {
+    let res = import_job::Entity::update_many()
+        .col_expr(import_job::Column::Status, Expr::value(ImportStatus::Running))
+        .col_expr(import_job::Column::StartedAt, Expr::value(Some(Local::now().naive_local())))
+        .col_expr(import_job::Column::Failure, Expr::value(Option::<String>::None))
+        .filter(import_job::Column::Id.eq(job_id))
+        .filter(import_job::Column::Status.is_in([ImportStatus::Pending, ImportStatus::Failed]))
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected == 1)
+}
+
+async fn finish(db: &DatabaseConnection, job_id: i32, status: ImportStatus, failure: Option<String>) -> Result<(), DbErr> {
+    import_job::Entity::update_many()
+        .col_expr(import_job::Column::Status, Expr::value(status))
+        .col_expr(import_job::Column::Failure, Expr::value(failure))
+        .col_expr(import_job::Column::FinishedAt, Expr::value(Some(Local::now().naive_local())))
+        .filter(import_job::Column::Id.eq(job_id))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+// Proses job mulai dari baris `processed_rows`. Pembacaan historis tidak dievaluasi ke automation;
+// `rolled_up = false` supaya job rollup berikutnya ikut meringkasnya.
+async fn run(db: &DatabaseConnection, retention: &RetentionConfig, job_id: i32) -> Result<(), DbErr> {
+    if !claim(db, job_id).await? {
+        return Ok(());
+    }
+    let Some(job) = import_job::Entity::find_by_id(job_id).one(db).await? else {
+        return Ok(());
+    };
+
+    let sensors = LandSensors::load(db, retention, job.land_id).await?;
+    let mut r = reader(&job.payload);
+    let cols = match r.headers().map_err(|e| e.to_string()).and_then(Columns::from_headers) {
+        Ok(cols) => cols,
+        Err(e) => return finish(db, job_id, ImportStatus::Failed, Some(e)).await,
+    };
+
+    let now = Local::now().naive_local();
+    let mut stored_errors = match job.errors.clone() {
+        Value::Array(errors) => errors,
+        _ => Vec::new(),
+    };
+    let mut progress: import_job::ActiveModel = job.clone().into();
+    let (mut processed, mut imported, mut duplicates, mut error_count) =
+        (job.processed_rows, job.imported_rows, job.duplicate_rows, job.error_count);
+
+    let mut records = r.records().skip(job.processed_rows as usize);
+    loop {
+        let batch: Vec<_> = records.by_ref().take(BATCH_SIZE).collect();
+        if batch.is_empty() {
+            break;
+        }
+
+        let mut errors = Vec::new();
+        let mut readings = Vec::new();
+        for record in &batch {
+            let parsed = match record {
+                Ok(rec) => {
+                    let line = rec.position().map_or(0, |p| p.line());
+                    parse_row(rec, line, &cols, &sensors, now).map_err(|e| row_error(line, e))
+                }
+                Err(e) => Err(row_error(e.position().map_or(0, |p| p.line()), e.to_string())),
+            };
+            match parsed {
+                Ok(reading) => readings.push(reading),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        let txn = db.begin().await?;
+        let valid = readings.len();
+        let readings = reject_purged_days(&txn, &sensors, readings, now, &mut errors).await?;
+        let rejected = valid - readings.len();
+        let fresh = dedupe(&txn, readings).await?;
+        let inserted = fresh.len();
+
+        if !fresh.is_empty() {
+            sensor_history::Entity::insert_many(fresh.into_iter().map(|r| sensor_history::ActiveModel {
+                sensor_id: Set(r.sensor_id),
+                value: Set(r.value),
+                recorded_at: Set(r.recorded_at),
+                rolled_up: Set(false),
+                ..Default::default()
+            }))
+            .exec(&txn)
+            .await?;
+        }
+
+        processed += batch.len() as i32;
+        imported += inserted as i32;
+        duplicates += (valid - rejected - inserted) as i32;
+        error_count += errors.len() as i32;
+        let room = MAX_STORED_ERRORS.saturating_sub(stored_errors.len());
+        stored_errors.extend(errors.into_iter().take(room));
+
+        progress.processed_rows = Set(processed);
+        progress.imported_rows = Set(imported);
+        progress.duplicate_rows = Set(duplicates);
+        progress.error_count = Set(error_count);
+        progress.errors = Set(Value::Array(stored_errors.clone()));
+        progress = progress.update(&txn).await?.into();
+        txn.commit().await?;
+    }
+
+    finish(db, job_id, ImportStatus::Completed, None).await
+}
+
+pub fn spawn(db: DatabaseConnection, retention: RetentionConfig, job_id: i32) {
+    tokio::spawn(async move {
+        if let Err(e) = run(&db, &retention, job_id).await {
+            eprintln!("Import job {} failed: {:?}", job_id, e);
+            if let Err(e) = finish(&db, job_id, ImportStatus::Failed, Some(e.to_string())).await {
+                eprintln!("Import job {}: failed to record failure: {:?}", job_id, e);
+            }
+        }
+    });
+}
+
+// Saat startup: lanjutkan job yang belum selesai, termasuk yang terputus saat Running
+pub async fn resume_interrupted(db: &DatabaseConnection, retention: &RetentionConfig) -> Result<(), DbErr> {
+    import_job::Entity::update_many()
+        .col_expr(import_job::Column::Status, Expr::value(ImportStatus::Pending))
+        .filter(import_job::Column::Status.eq(ImportStatus::Running))
+        .exec(db)
+        .await?;
+
+    let ids: Vec<i32> = import_job::Entity::find()
+        .select_only()
+        .column(import_job::Column::Id)
+        .filter(import_job::Column::Status.eq(ImportStatus::Pending))
+        .into_tuple()
+        .all(db)
+        .await?;
+    for id in ids {
+        spawn(db.clone(), retention.clone(), id);
+    }
+    Ok(())
+}
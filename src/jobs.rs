@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use std::sync::Arc;
+
+use sea_orm::DatabaseConnection;
+
+use crate::{
+    audit,
+    config::{RetentionConfig, WeatherConfig},
+    rollup, soft_delete, tasks,
+    weather::{self, WeatherProvider},
+};
+
+// Seberapa sering task yang lewat due date dicek
+const OVERDUE_CHECK_MINUTES: u64 = 10;
+
+// Job rollup riwayat sensor & pembersihan data lama, berjalan berkala selama aplikasi hidup
+pub fn spawn_retention(db: DatabaseConnection, retention: RetentionConfig) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(retention.purge_interval_minutes.max(1) * 60));
+        loop {
+            interval.tick().await;
+
+            match rollup::run(&db, &retention).await {
+                Ok(report) => {
+                    if report.raw_deleted > 0 || report.hourly_deleted > 0 {
+                        println!("Retention: sensor history {:?}", report);
+                    }
+                }
+                Err(e) => eprintln!("Retention: sensor history rollup failed: {:?}", e),
+            }
+
+            if retention.audit_log_days > 0 {
+                match audit::purge_older_than(&db, retention.audit_log_days).await {
+                    Ok(0) => {}
+                    Ok(n) => println!("Retention: purged {} audit log entries", n),
+                    Err(e) => eprintln!("Retention: failed to purge audit log: {:?}", e),
+                }
+            }
+
+            if retention.weather_days > 0 {
+                match weather::purge_older_than(&db, retention.weather_days).await {
+                    Ok(0) => {}
+                    Ok(n) => println!("Retention: purged {} weather records", n),
+                    Err(e) => eprintln!("Retention: failed to purge weather records: {:?}", e),
+                }
+            }
+
+            if retention.soft_delete_days > 0 {
+                match soft_delete::purge_older_than(&db, retention.soft_delete_days, None).await {
+                    Ok(report) if report.total() == 0 => {}
+                    Ok(report) => println!("Retention: purged deleted records {:?}", report),
+                    Err(e) => eprintln!("Retention: failed to purge deleted records: {:?}", e),
+                }
+            }
+        }
+    });
+}
+
+// Pengingat task yang terlambat
+pub fn spawn_task_reminders(db: DatabaseConnection) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(OVERDUE_CHECK_MINUTES * 60));
+        loop {
+            interval.tick().await;
+
+            match tasks::notify_overdue(&db).await {
+                Ok(0) => {}
+                Ok(n) => println!("Tasks: notified {} overdue tasks", n),
+                Err(e) => eprintln!("Tasks: overdue check failed: {:?}", e),
+            }
+        }
+    });
+}
+
+// Ambil observasi & forecast terbaru dari provider cuaca untuk semua land
+pub fn spawn_weather(db: DatabaseConnection, provider: Arc<dyn WeatherProvider>, config: WeatherConfig) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(config.poll_minutes.max(1) * 60));
+        loop {
+            interval.tick().await;
+
+            match weather::refresh_all(&db, provider.as_ref()).await {
+                Ok(0) => {}
+                Ok(n) => println!("Weather: stored {} records from {}", n, provider.name()),
+                Err(e) => eprintln!("Weather: refresh failed: {:?}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,788 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDateTime;
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::{
+        automation::{self, AutomationType, WeatherMode},
+        derived_sensor::{self, DerivedMetric},
+        land, plant, pump, seed,
+        sensor::{self, SensorType},
+        valve, zone,
+    },
+    derived,
+    geo::{self, Ring},
+    soft_delete::SoftDelete,
+    validation::Validator,
+    weather,
+};
+
+pub const VERSION: u32 = 1;
+
+// Dokumen konfigurasi satu land (JSON / YAML). Sensor, pompa & katup diberi `key` simbolik
+// yang dirujuk automation, sehingga dokumen tidak bergantung pada id di database.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LandConfig {
+    pub version: u32,
+    pub land: LandSpec,
+    #[serde(default)]
+    pub zones: Vec<ZoneSpec>,
+    #[serde(default)]
+    pub sensors: Vec<SensorSpec>,
+    #[serde(default)]
+    pub pumps: Vec<PumpSpec>,
+    #[serde(default)]
+    pub valves: Vec<ValveSpec>,
+    #[serde(default)]
+    pub plants: Vec<PlantSpec>,
+    #[serde(default)]
+    pub automations: Vec<AutomationSpec>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LandSpec {
+    pub location_name: String,
+    pub size: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub boundary: Option<Ring>,
+}
+
+// Sensor, katup & tanaman merujuk zona lewat key-nya
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ZoneSpec {
+    pub key: String,
+    pub name: String,
+    pub area: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub polygon: Option<Ring>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SensorSpec {
+    pub key: String,
+    pub name: String,
+    pub sensor_type: SensorType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    // Hanya untuk sensor virtual; sumbernya dirujuk lewat key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derived: Option<DerivedSpec>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DerivedSpec {
+    pub metric: DerivedMetric,
+    pub temperature: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub humidity: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plant: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PumpSpec {
+    pub key: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flow_rate: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_runtime_seconds: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_daily_volume: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooldown_seconds: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ValveSpec {
+    pub key: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    #[serde(default)]
+    pub is_open: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_runtime_seconds: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_daily_volume: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooldown_seconds: Option<i32>,
+}
+
+// Seed dirujuk lewat nama karena katalog seed dipakai bersama semua land
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlantSpec {
+    // Hanya perlu jika dirujuk sensor virtual (GDD)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub name: String,
+    pub quantity: i32,
+    pub seed: String,
+    pub planted_at: NaiveDateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AutomationSpec {
+    pub name: String,
+    pub automation_type: AutomationType,
+    pub sensor: String,
+    pub sensor_value: f64,
+    pub pump: String,
+    pub valve: String,
+    pub dispense_amount: f64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Aturan hujan; tanpa `weather` automation tidak terpengaruh cuaca
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weather: Option<WeatherRuleSpec>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WeatherRuleSpec {
+    pub mode: WeatherMode,
+    pub rain_threshold_mm: f64,
+    #[serde(default = "default_lookback_hours")]
+    pub lookback_hours: i32,
+    #[serde(default = "default_forecast_hours")]
+    pub forecast_hours: i32,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_lookback_hours() -> i32 {
+    weather::DEFAULT_LOOKBACK_HOURS
+}
+
+fn default_forecast_hours() -> i32 {
+    weather::DEFAULT_FORECAST_HOURS
+}
+
+// Key dari nama; nama kembar diberi akhiran -2, -3, ...
+fn unique_keys<'a>(names: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut used = HashSet::new();
+    names
+        .map(|name| {
+            let mut key = name.to_string();
+            let mut n = 1;
+            while !used.insert(key.clone()) {
+                n += 1;
+                key = format!("{}-{}", name, n);
+            }
+            key
+        })
+        .collect()
+}
+
+// ====================================================
+// EXPORT
+// ====================================================
+
+pub async fn export<C: ConnectionTrait>(db: &C, l: &land::Model) -> Result<LandConfig, DbErr> {
+    let zones = zone::Entity::find_active()
+        .filter(zone::Column::LandId.eq(l.id))
+        .order_by_asc(zone::Column::Id)
+        .all(db)
+        .await?;
+    let sensors = sensor::Entity::find_active()
+        .filter(sensor::Column::LandId.eq(l.id))
+        .order_by_asc(sensor::Column::Id)
+        .all(db)
+        .await?;
+    let pumps = pump::Entity::find_active()
+        .filter(pump::Column::LandId.eq(l.id))
+        .order_by_asc(pump::Column::Id)
+        .all(db)
+        .await?;
+    let valves = valve::Entity::find_active()
+        .filter(valve::Column::LandId.eq(l.id))
+        .order_by_asc(valve::Column::Id)
+        .all(db)
+        .await?;
+    // Tanaman yang sudah dipanen habis bukan bagian dari konfigurasi aktif
+    let plants = plant::Entity::find_active()
+        .filter(plant::Column::LandId.eq(l.id))
+        .filter(plant::Column::ClosedAt.is_null())
+        .order_by_asc(plant::Column::Id)
+        .all(db)
+        .await?;
+    let automations = automation::Entity::find_active()
+        .filter(automation::Column::LandId.eq(l.id))
+        .order_by_asc(automation::Column::Id)
+        .all(db)
+        .await?;
+    let seeds: HashMap<i32, String> = seed::Entity::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|s| (s.id, s.name))
+        .collect();
+    let derived: HashMap<i32, derived_sensor::Model> = derived_sensor::Entity::find()
+        .filter(derived_sensor::Column::SensorId.is_in(sensors.iter().map(|s| s.id)))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|d| (d.sensor_id, d))
+        .collect();
+
+    let zone_keys: HashMap<i32, String> = zones
+        .iter()
+        .map(|z| z.id)
+        .zip(unique_keys(zones.iter().map(|z| z.name.as_str())))
+        .collect();
+    let zone_key = |id: Option<i32>| id.and_then(|id| zone_keys.get(&id).cloned());
+    let sensor_keys: HashMap<i32, String> = sensors
+        .iter()
+        .map(|s| s.id)
+        .zip(unique_keys(sensors.iter().map(|s| s.name.as_str())))
+        .collect();
+    let pump_keys: HashMap<i32, String> = pumps
+        .iter()
+        .map(|p| p.id)
+        .zip(unique_keys(pumps.iter().map(|p| p.name.as_str())))
+        .collect();
+    let valve_keys: HashMap<i32, String> = valves
+        .iter()
+        .map(|v| v.id)
+        .zip(unique_keys(valves.iter().map(|v| v.name.as_str())))
+        .collect();
+    let plant_keys: HashMap<i32, String> = plants
+        .iter()
+        .map(|p| p.id)
+        .zip(unique_keys(plants.iter().map(|p| p.name.as_str())))
+        .collect();
+    let referenced_plants: HashSet<i32> = derived.values().filter_map(|d| d.plant_id).collect();
+
+    // Sensor virtual yang sumbernya sudah terhapus tidak bisa dinyatakan ulang, jadi dilewati
+    // (automation yang memakainya ikut terlewati karena key-nya tidak ada)
+    let derived_spec = |s: &sensor::Model| -> Option<Option<DerivedSpec>> {
+        let Some(d) = derived.get(&s.id) else {
+            return Some(None);
+        };
+        Some(Some(DerivedSpec {
+            metric: d.metric,
+            temperature: sensor_keys.get(&d.temperature_sensor_id)?.clone(),
+            humidity: match d.humidity_sensor_id {
+                Some(id) => Some(sensor_keys.get(&id)?.clone()),
+                None => None,
+            },
+            plant: match d.plant_id {
+                Some(id) => Some(plant_keys.get(&id)?.clone()),
+                None => None,
+            },
+        }))
+    };
+    let sensors: Vec<SensorSpec> = sensors
+        .iter()
+        .filter_map(|s| {
+            Some(SensorSpec {
+                key: sensor_keys[&s.id].clone(),
+                name: s.name.clone(),
+                sensor_type: s.sensor_type.clone(),
+                zone: zone_key(s.zone_id),
+                latitude: s.latitude,
+                longitude: s.longitude,
+                derived: derived_spec(s)?,
+            })
+        })
+        .collect();
+    let exported: HashSet<String> = sensors.iter().map(|s| s.key.clone()).collect();
+
+    Ok(LandConfig {
+        version: VERSION,
+        land: LandSpec {
+            location_name: l.location_name.clone(),
+            size: l.size,
+            latitude: l.latitude,
+            longitude: l.longitude,
+            boundary: l.boundary.as_ref().and_then(geo::from_json),
+        },
+        zones: zones
+            .iter()
+            .map(|z| ZoneSpec {
+                key: zone_keys[&z.id].clone(),
+                name: z.name.clone(),
+                area: z.area,
+                polygon: z.polygon.as_ref().and_then(geo::from_json),
+            })
+            .collect(),
+        sensors,
+        pumps: pumps
+            .iter()
+            .map(|p| PumpSpec {
+                key: pump_keys[&p.id].clone(),
+                name: p.name.clone(),
+                latitude: p.latitude,
+                longitude: p.longitude,
+                flow_rate: p.flow_rate,
+                max_runtime_seconds: p.max_runtime_seconds,
+                max_daily_volume: p.max_daily_volume,
+                cooldown_seconds: p.cooldown_seconds,
+            })
+            .collect(),
+        valves: valves
+            .iter()
+            .map(|v| ValveSpec {
+                key: valve_keys[&v.id].clone(),
+                name: v.name.clone(),
+                zone: zone_key(v.zone_id),
+                latitude: v.latitude,
+                longitude: v.longitude,
+                is_open: v.is_open,
+                max_runtime_seconds: v.max_runtime_seconds,
+                max_daily_volume: v.max_daily_volume,
+                cooldown_seconds: v.cooldown_seconds,
+            })
+            .collect(),
+        plants: plants
+            .iter()
+            .filter_map(|p| {
+                Some(PlantSpec {
+                    key: referenced_plants.contains(&p.id).then(|| plant_keys[&p.id].clone()),
+                    name: p.name.clone(),
+                    quantity: p.quantity,
+                    seed: seeds.get(&p.seed_id)?.clone(),
+                    planted_at: p.planted_at,
+                    zone: zone_key(p.zone_id),
+                })
+            })
+            .collect(),
+        // Automation aktif selalu merujuk perangkat aktif (lihat soft_delete), jadi key-nya pasti ada
+        automations: automations
+            .iter()
+            .filter_map(|a| {
+                Some(AutomationSpec {
+                    name: a.name.clone(),
+                    automation_type: a.automation_type.clone(),
+                    sensor: sensor_keys.get(&a.sensor_id).filter(|k| exported.contains(*k))?.clone(),
+                    sensor_value: a.sensor_value,
+                    pump: pump_keys.get(&a.pump_id)?.clone(),
+                    valve: valve_keys.get(&a.valve_id)?.clone(),
+                    dispense_amount: a.dispense_amount,
+                    enabled: a.enabled,
+                    weather: match (a.weather_mode, a.rain_threshold_mm) {
+                        (WeatherMode::Ignore, _) | (_, None) => None,
+                        (mode, Some(threshold)) => Some(WeatherRuleSpec {
+                            mode,
+                            rain_threshold_mm: threshold,
+                            lookback_hours: a.rain_lookback_hours,
+                            forecast_hours: a.rain_forecast_hours,
+                        }),
+                    },
+                })
+            })
+            .collect(),
+    })
+}
+
+// ====================================================
+// VALIDASI: semua kesalahan dikumpulkan dengan path field, mis. "automations[1].pump"
+// ====================================================
+
+fn check_keys<'a>(v: &mut Validator<'_>, section: &str, keys: impl Iterator<Item = &'a str>) -> HashSet<&'a str> {
+    let mut seen = HashSet::new();
+    for (i, key) in keys.enumerate() {
+        let field = format!("{}[{}].key", section, i);
+        if key.trim().is_empty() {
+            v.add(&field, "Must not be empty");
+        } else if !seen.insert(key) {
+            v.add(&field, format!("Duplicate key '{}'", key));
+        }
+    }
+    seen
+}
+
+fn check_limit<T: PartialOrd + Default>(v: &mut Validator<'_>, field: String, value: Option<T>) {
+    if value.is_some_and(|x| x <= T::default()) {
+        v.add(&field, "Must be greater than 0");
+    }
+}
+
+fn check_zone(v: &mut Validator<'_>, zone_keys: &HashSet<&str>, field: String, zone: Option<&str>) {
+    if let Some(z) = zone
+        && !zone_keys.contains(z)
+    {
+        v.add(&field, format!("Unknown zone key '{}'", z));
+    }
+}
+
+// Sumber sensor virtual harus sensor fisik bertipe sesuai di dokumen yang sama
+fn source_error(by_key: &HashMap<&str, &SensorSpec>, key: &str, expected: SensorType) -> Option<String> {
+    match by_key.get(key) {
+        Some(s) if s.derived.is_none() && s.sensor_type == expected => None,
+        Some(_) => Some(format!("Sensor '{}' must be a {:?} sensor", key, expected)),
+        None => Some(format!("Unknown sensor key '{}'", key)),
+    }
+}
+
+// Id seed untuk setiap nama seed yang dipakai dokumen
+pub async fn validate<C: ConnectionTrait>(
+    v: &mut Validator<'_>,
+    db: &C,
+    doc: &LandConfig,
+) -> Result<HashMap<String, i32>, DbErr> {
+    if doc.version != VERSION {
+        v.add("version", format!("Unsupported version, expected {}", VERSION));
+    }
+    v.max_length("land.location_name", &doc.land.location_name, 255);
+    if doc.land.size <= 0.0 {
+        v.add("land.size", "Must be greater than 0");
+    }
+    v.coordinates("land.", doc.land.latitude, doc.land.longitude);
+    if let Some(ring) = &doc.land.boundary {
+        v.polygon("land.boundary", ring, doc.land.size);
+    }
+
+    let zone_keys = check_keys(v, "zones", doc.zones.iter().map(|z| z.key.as_str()));
+    let mut zoned_area = 0.0;
+    for (i, z) in doc.zones.iter().enumerate() {
+        v.max_length(&format!("zones[{}].name", i), &z.name, 255);
+        if !z.area.is_finite() || z.area <= 0.0 {
+            v.add(&format!("zones[{}].area", i), "Must be greater than 0");
+        } else {
+            zoned_area += z.area;
+        }
+        if let Some(ring) = &z.polygon {
+            v.polygon(&format!("zones[{}].polygon", i), ring, z.area);
+        }
+    }
+    if zoned_area > doc.land.size {
+        v.add("zones", format!("Zones cover {} but the land size is {}", zoned_area, doc.land.size));
+    }
+
+    let sensor_keys = check_keys(v, "sensors", doc.sensors.iter().map(|s| s.key.as_str()));
+    let pump_keys = check_keys(v, "pumps", doc.pumps.iter().map(|p| p.key.as_str()));
+    let valve_keys = check_keys(v, "valves", doc.valves.iter().map(|x| x.key.as_str()));
+
+    let plant_keys = check_keys(v, "plants", doc.plants.iter().filter_map(|p| p.key.as_deref()));
+
+    let by_key: HashMap<&str, &SensorSpec> = doc.sensors.iter().map(|s| (s.key.as_str(), s)).collect();
+    for (i, s) in doc.sensors.iter().enumerate() {
+        let is_virtual = matches!(
+            s.sensor_type,
+            SensorType::Vpd | SensorType::DewPoint | SensorType::GrowingDegreeDays
+        );
+        let Some(d) = &s.derived else {
+            if is_virtual {
+                v.add(&format!("sensors[{}].derived", i), "Required for derived sensor types");
+            }
+            continue;
+        };
+        if derived::sensor_type(d.metric) != s.sensor_type {
+            v.add(
+                &format!("sensors[{}].sensor_type", i),
+                format!("Must be {:?} for metric {:?}", derived::sensor_type(d.metric), d.metric),
+            );
+        }
+        if let Some(e) = source_error(&by_key, &d.temperature, SensorType::Temperature) {
+            v.add(&format!("sensors[{}].derived.temperature", i), e);
+        }
+        match d.metric {
+            DerivedMetric::Vpd | DerivedMetric::DewPoint => match &d.humidity {
+                Some(h) => {
+                    if let Some(e) = source_error(&by_key, h, SensorType::Humidity) {
+                        v.add(&format!("sensors[{}].derived.humidity", i), e);
+                    }
+                }
+                None => v.add(&format!("sensors[{}].derived.humidity", i), "Required for this metric"),
+            },
+            DerivedMetric::GrowingDegreeDays => match &d.plant {
+                Some(p) if !plant_keys.contains(p.as_str()) => {
+                    v.add(&format!("sensors[{}].derived.plant", i), format!("Unknown plant key '{}'", p))
+                }
+                Some(_) => {}
+                None => v.add(&format!("sensors[{}].derived.plant", i), "Required for this metric"),
+            },
+        }
+    }
+    for (i, s) in doc.sensors.iter().enumerate() {
+        v.max_length(&format!("sensors[{}].name", i), &s.name, 255);
+        check_zone(v, &zone_keys, format!("sensors[{}].zone", i), s.zone.as_deref());
+        v.coordinates(&format!("sensors[{}].", i), s.latitude, s.longitude);
+    }
+    for (i, p) in doc.pumps.iter().enumerate() {
+        v.max_length(&format!("pumps[{}].name", i), &p.name, 255);
+        v.coordinates(&format!("pumps[{}].", i), p.latitude, p.longitude);
+        check_limit(v, format!("pumps[{}].flow_rate", i), p.flow_rate);
+        check_limit(v, format!("pumps[{}].max_runtime_seconds", i), p.max_runtime_seconds);
+        check_limit(v, format!("pumps[{}].max_daily_volume", i), p.max_daily_volume);
+        check_limit(v, format!("pumps[{}].cooldown_seconds", i), p.cooldown_seconds);
+    }
+    for (i, x) in doc.valves.iter().enumerate() {
+        v.max_length(&format!("valves[{}].name", i), &x.name, 255);
+        check_zone(v, &zone_keys, format!("valves[{}].zone", i), x.zone.as_deref());
+        v.coordinates(&format!("valves[{}].", i), x.latitude, x.longitude);
+        check_limit(v, format!("valves[{}].max_runtime_seconds", i), x.max_runtime_seconds);
+        check_limit(v, format!("valves[{}].max_daily_volume", i), x.max_daily_volume);
+        check_limit(v, format!("valves[{}].cooldown_seconds", i), x.cooldown_seconds);
+    }
+
+    // Nama seed tidak unik di katalog; dipakai seed dengan id terkecil
+    let mut seeds = HashMap::new();
+    for s in seed::Entity::find().order_by_desc(seed::Column::Id).all(db).await? {
+        seeds.insert(s.name, s.id);
+    }
+    for (i, p) in doc.plants.iter().enumerate() {
+        v.max_length(&format!("plants[{}].name", i), &p.name, 255);
+        check_zone(v, &zone_keys, format!("plants[{}].zone", i), p.zone.as_deref());
+        if p.quantity <= 0 {
+            v.add(&format!("plants[{}].quantity", i), "Must be greater than 0");
+        }
+        if !seeds.contains_key(&p.seed) {
+            v.add(&format!("plants[{}].seed", i), format!("Seed '{}' does not exist", p.seed));
+        }
+    }
+
+    let sensor_zones: HashMap<&str, Option<&str>> = doc.sensors.iter().map(|s| (s.key.as_str(), s.zone.as_deref())).collect();
+    let valve_zones: HashMap<&str, Option<&str>> = doc.valves.iter().map(|x| (x.key.as_str(), x.zone.as_deref())).collect();
+    for (i, a) in doc.automations.iter().enumerate() {
+        v.max_length(&format!("automations[{}].name", i), &a.name, 255);
+        if let (Some(Some(s)), Some(Some(x))) = (sensor_zones.get(a.sensor.as_str()), valve_zones.get(a.valve.as_str()))
+            && s != x
+        {
+            v.add(&format!("automations[{}].sensor", i), "Sensor is in a different zone than the valve");
+        }
+        if !sensor_keys.contains(a.sensor.as_str()) {
+            v.add(&format!("automations[{}].sensor", i), format!("Unknown sensor key '{}'", a.sensor));
+        }
+        if !pump_keys.contains(a.pump.as_str()) {
+            v.add(&format!("automations[{}].pump", i), format!("Unknown pump key '{}'", a.pump));
+        }
+        if !valve_keys.contains(a.valve.as_str()) {
+            v.add(&format!("automations[{}].valve", i), format!("Unknown valve key '{}'", a.valve));
+        }
+        if a.dispense_amount <= 0.0 {
+            v.add(&format!("automations[{}].dispense_amount", i), "Must be greater than 0");
+        }
+        if let Some(w) = &a.weather {
+            v.weather_rule(
+                &format!("automations[{}].weather.", i),
+                &a.automation_type,
+                w.mode,
+                Some(w.rain_threshold_mm),
+                w.lookback_hours,
+                w.forecast_hours,
+            );
+        }
+    }
+
+    seeds.retain(|name, _| doc.plants.iter().any(|p| &p.seed == name));
+    Ok(seeds)
+}
+
+// ====================================================
+// IMPORT: dijalankan di dalam transaksi oleh pemanggil, setelah `validate` lolos
+// ====================================================
+
+#[derive(Debug, Serialize)]
+pub struct Created {
+    pub land: land::Model,
+    pub zones: Vec<zone::Model>,
+    pub sensors: Vec<sensor::Model>,
+    pub pumps: Vec<pump::Model>,
+    pub valves: Vec<valve::Model>,
+    pub plants: Vec<plant::Model>,
+    pub derived_sensors: Vec<derived_sensor::Model>,
+    pub automations: Vec<automation::Model>,
+}
+
+pub async fn apply<C: ConnectionTrait>(
+    db: &C,
+    doc: &LandConfig,
+    organization_id: i32,
+    user_id: i32,
+    seeds: &HashMap<String, i32>,
+) -> Result<Created, DbErr> {
+    let boundary = doc.land.boundary.clone().map(geo::normalize);
+    let (latitude, longitude) = geo::point_or_centroid(doc.land.latitude, doc.land.longitude, boundary.as_ref());
+    let l = land::ActiveModel {
+        location_name: Set(doc.land.location_name.clone()),
+        size: Set(doc.land.size),
+        latitude: Set(latitude),
+        longitude: Set(longitude),
+        boundary: Set(boundary.as_ref().map(geo::to_json)),
+        user_id: Set(user_id),
+        organization_id: Set(organization_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    let mut zones = Vec::new();
+    for z in &doc.zones {
+        zones.push(
+            zone::ActiveModel {
+                land_id: Set(l.id),
+                name: Set(z.name.clone()),
+                area: Set(z.area),
+                polygon: Set(z.polygon.clone().map(geo::normalize).as_ref().map(geo::to_json)),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?,
+        );
+    }
+    let zone_ids: HashMap<&str, i32> = doc.zones.iter().map(|z| z.key.as_str()).zip(zones.iter().map(|z| z.id)).collect();
+    let zone_id = |key: &Option<String>| key.as_deref().map(|k| zone_ids[k]);
+
+    let mut sensors = Vec::new();
+    for s in &doc.sensors {
+        sensors.push(
+            sensor::ActiveModel {
+                name: Set(s.name.clone()),
+                sensor_type: Set(s.sensor_type.clone()),
+                land_id: Set(l.id),
+                zone_id: Set(zone_id(&s.zone)),
+                latitude: Set(s.latitude),
+                longitude: Set(s.longitude),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?,
+        );
+    }
+
+    let mut pumps = Vec::new();
+    for p in &doc.pumps {
+        pumps.push(
+            pump::ActiveModel {
+                name: Set(p.name.clone()),
+                land_id: Set(l.id),
+                latitude: Set(p.latitude),
+                longitude: Set(p.longitude),
+                flow_rate: Set(p.flow_rate),
+                max_runtime_seconds: Set(p.max_runtime_seconds),
+                max_daily_volume: Set(p.max_daily_volume),
+                cooldown_seconds: Set(p.cooldown_seconds),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?,
+        );
+    }
+
+    let mut valves = Vec::new();
+    for x in &doc.valves {
+        valves.push(
+            valve::ActiveModel {
+                name: Set(x.name.clone()),
+                land_id: Set(l.id),
+                zone_id: Set(zone_id(&x.zone)),
+                latitude: Set(x.latitude),
+                longitude: Set(x.longitude),
+                is_open: Set(x.is_open),
+                max_runtime_seconds: Set(x.max_runtime_seconds),
+                max_daily_volume: Set(x.max_daily_volume),
+                cooldown_seconds: Set(x.cooldown_seconds),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?,
+        );
+    }
+
+    let mut plants = Vec::new();
+    for p in &doc.plants {
+        plants.push(
+            plant::ActiveModel {
+                name: Set(p.name.clone()),
+                quantity: Set(p.quantity),
+                land_id: Set(l.id),
+                zone_id: Set(zone_id(&p.zone)),
+                seed_id: Set(seeds[&p.seed]),
+                planted_at: Set(p.planted_at),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?,
+        );
+    }
+
+    let sensor_ids: HashMap<&str, i32> = doc.sensors.iter().map(|s| s.key.as_str()).zip(sensors.iter().map(|s| s.id)).collect();
+    let plant_ids: HashMap<&str, i32> = doc
+        .plants
+        .iter()
+        .zip(plants.iter())
+        .filter_map(|(spec, p)| Some((spec.key.as_deref()?, p.id)))
+        .collect();
+
+    let mut derived_sensors = Vec::new();
+    for s in &doc.sensors {
+        let Some(d) = &s.derived else { continue };
+        derived_sensors.push(
+            derived_sensor::ActiveModel {
+                sensor_id: Set(sensor_ids[s.key.as_str()]),
+                metric: Set(d.metric),
+                temperature_sensor_id: Set(sensor_ids[d.temperature.as_str()]),
+                humidity_sensor_id: Set(d.humidity.as_deref().map(|h| sensor_ids[h])),
+                plant_id: Set(d.plant.as_deref().map(|p| plant_ids[p])),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?,
+        );
+    }
+
+    let pump_ids: HashMap<&str, i32> = doc.pumps.iter().map(|p| p.key.as_str()).zip(pumps.iter().map(|p| p.id)).collect();
+    let valve_ids: HashMap<&str, i32> = doc.valves.iter().map(|x| x.key.as_str()).zip(valves.iter().map(|x| x.id)).collect();
+    let valve_zones: HashMap<i32, Option<i32>> = valves.iter().map(|x| (x.id, x.zone_id)).collect();
+
+    let mut automations = Vec::new();
+    for a in &doc.automations {
+        automations.push(
+            automation::ActiveModel {
+                name: Set(a.name.clone()),
+                automation_type: Set(a.automation_type.clone()),
+                sensor_id: Set(sensor_ids[a.sensor.as_str()]),
+                sensor_value: Set(a.sensor_value),
+                pump_id: Set(pump_ids[a.pump.as_str()]),
+                valve_id: Set(valve_ids[a.valve.as_str()]),
+                land_id: Set(l.id),
+                zone_id: Set(valve_zones[&valve_ids[a.valve.as_str()]]),
+                dispense_amount: Set(a.dispense_amount),
+                enabled: Set(a.enabled),
+                paused_until: Set(None),
+                weather_mode: Set(a.weather.as_ref().map_or(WeatherMode::Ignore, |w| w.mode)),
+                rain_threshold_mm: Set(a.weather.as_ref().map(|w| w.rain_threshold_mm)),
+                rain_lookback_hours: Set(a.weather.as_ref().map_or(weather::DEFAULT_LOOKBACK_HOURS, |w| w.lookback_hours)),
+                rain_forecast_hours: Set(a.weather.as_ref().map_or(weather::DEFAULT_FORECAST_HOURS, |w| w.forecast_hours)),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?,
+        );
+    }
+
+    Ok(Created { land: l, zones, sensors, pumps, valves, plants, derived_sensors, automations })
+}
@@ -1,20 +1,61 @@
+mod anomaly;
+mod audit;
+mod config;
 mod db;
+mod derived;
+mod engine;
+mod error;
+mod extractors;
+mod geo;
 mod handlers;
+mod importer;
+mod jobs;
+mod land_config;
+mod messaging;
 mod models;
+mod notifier;
+mod permissions;
+mod response;
+mod rollup;
+mod safety;
+mod security;
 mod seeder;
+mod session_store;
+mod soft_delete;
+mod tasks;
+mod tenancy;
+mod tokens;
+mod validation;
+mod weather;
+mod yields;
+mod zones;
 
 use actix_cors::Cors;
-use actix_session::{storage::CookieSessionStore, SessionMiddleware};
+use actix_session::SessionMiddleware;
 use actix_web::cookie::Key;
 use actix_web::web::{self, ServiceConfig};
+use config::{AnomalyConfig, RetentionConfig, SecurityConfig, WeatherConfig};
 use db::setup_tables;
+use error::ApiError;
+use messaging::MessageSender;
 use sea_orm::{Database, DatabaseConnection};
+use security::LoginThrottle;
 use seeder::seed_db;
+use session_store::DbSessionStore;
 use shuttle_actix_web::ShuttleActixWeb;
 use shuttle_runtime::SecretStore;
+use std::sync::Arc;
+use weather::WeatherProvider;
 
 struct AppState {
     db: DatabaseConnection,
+    security: SecurityConfig,
+    retention: RetentionConfig,
+    anomaly: AnomalyConfig,
+    login_throttle: LoginThrottle,
+    messenger: Box<dyn MessageSender>,
+    // None = tidak ada provider, data cuaca hanya masuk lewat API
+    weather_provider: Option<Arc<dyn WeatherProvider>>,
 }
 
 #[shuttle_runtime::main]
@@ -22,27 +63,68 @@ async fn main(
     #[shuttle_shared_db::Postgres] conn_str: String,
     #[shuttle_runtime::Secrets] secrets: SecretStore,
 ) -> ShuttleActixWeb<impl FnOnce(&mut ServiceConfig) + Send + Clone + 'static> {
+    // Dibaca paling awal: konfigurasi keamanan yang tidak lengkap menghentikan startup
+    let security = SecurityConfig::from_secrets(&secrets);
+
     let db: DatabaseConnection = Database::connect(&conn_str)
         .await
         .expect("Failed to connect to Shuttle DB");
 
-    setup_tables(&db).await.expect("Failed to create tables");
+    let reset_db = secrets.get("RESET_DB")
+        .unwrap_or_else(|| "false".to_string())
+        .to_lowercase() == "true";
+
+    setup_tables(&db, reset_db).await.expect("Failed to create tables");
+    permissions::sync_permissions(&db).await.expect("Failed to sync permissions");
 
     let should_seed = secrets.get("SEED")
         .unwrap_or_else(|| "false".to_string())
         .to_lowercase() == "true";
 
+    // Password akun contoh dari SEED_PASSWORD, harus lolos kebijakan password yang sama dengan akun biasa
     if should_seed {
-        match seed_db(&db).await {
-            Ok(_) => println!("Database seeding complete."),
-            Err(e) => println!("Database seeding failed: {:?}", e),
+        match secrets.get("SEED_PASSWORD") {
+            None => println!("Database seeding skipped: SEED_PASSWORD is not set"),
+            Some(password) => {
+                let problems = security.password.check("SEED_PASSWORD", &password, "");
+                if !problems.is_empty() {
+                    let messages: Vec<String> = problems.into_iter().map(|p| p.message).collect();
+                    println!("Database seeding skipped: SEED_PASSWORD is invalid: {}", messages.join(", "));
+                } else {
+                    match seed_db(&db, &password).await {
+                        Ok(_) => println!("Database seeding complete."),
+                        Err(e) => println!("Database seeding failed: {:?}", e),
+                    }
+                }
+            }
         }
     }
 
     let secret_key_str = secrets.get("SESSION_KEY").unwrap_or_else(|| "0".repeat(64));
     let secret_key = Key::from(secret_key_str.as_bytes());
 
-    let state = web::Data::new(AppState { db: db.clone() });
+    let retention = RetentionConfig::from_secrets(&secrets);
+    jobs::spawn_retention(db.clone(), retention.clone());
+    jobs::spawn_task_reminders(db.clone());
+    let weather_provider = weather::from_secrets(&secrets);
+    if let Some(provider) = &weather_provider {
+        jobs::spawn_weather(db.clone(), provider.clone(), WeatherConfig::from_secrets(&secrets));
+    }
+    if let Err(e) = importer::resume_interrupted(&db, &retention).await {
+        println!("Failed to resume import jobs: {:?}", e);
+    }
+
+    let login_throttle = LoginThrottle::new(security.login_max_attempts, security.login_window_seconds);
+
+    let state = web::Data::new(AppState {
+        db: db.clone(),
+        security,
+        retention,
+        anomaly: AnomalyConfig::from_secrets(&secrets),
+        login_throttle,
+        messenger: messaging::from_secrets(&secrets),
+        weather_provider,
+    });
 
     let config = move |cfg: &mut ServiceConfig| {
         cfg.service(
@@ -52,29 +134,90 @@ async fn main(
                         .allowed_origin("http://localhost:3000")
                         .allowed_origin("https://manprosi-frontend.vercel.app")
                         .allowed_methods(vec!["GET", "POST", "PUT", "DELETE"])
-                        .allowed_headers(vec![actix_web::http::header::CONTENT_TYPE])
+                        .allowed_headers(vec![
+                            actix_web::http::header::CONTENT_TYPE,
+                            actix_web::http::header::AUTHORIZATION,
+                        ])
                         .supports_credentials(),
                 )
                 .wrap(SessionMiddleware::new(
-                    CookieSessionStore::default(),
+                    DbSessionStore::new(db.clone()),
                     secret_key.clone(),
                 ))
                 .app_data(state)
+                // Body/path yang tidak valid juga dikembalikan dalam format ApiError
+                .app_data(web::JsonConfig::default().error_handler(|err, _| {
+                    ApiError::bad_request(err.to_string()).with_code("invalid_body").into()
+                }))
+                .app_data(web::PayloadConfig::new(handlers::import::MAX_UPLOAD_BYTES))
+                .app_data(web::PathConfig::default().error_handler(|err, _| {
+                    ApiError::bad_request(err.to_string()).with_code("invalid_path").into()
+                }))
                 .service(handlers::auth::login)
-                .service(handlers::auth::me)
                 .service(handlers::auth::logout)
+                .service(handlers::auth::issue_token)
+                .service(handlers::auth::refresh_token)
+                .service(handlers::auth::revoke_token)
+                .service(handlers::password_reset::request_password_reset)
+                .service(handlers::password_reset::confirm_password_reset)
+                // Me
+                .service(handlers::me::me)
+                .service(handlers::me::update_me)
+                .service(handlers::me::change_my_password)
+                .service(handlers::me::get_my_lands)
+                .service(handlers::me::get_my_notifications)
                 // User
                 .service(handlers::user::create_user)
                 .service(handlers::user::get_users)
                 .service(handlers::user::update_user)
                 .service(handlers::user::get_user_by_id)
                 .service(handlers::user::delete_user)
+                .service(handlers::user::unlock_user)
+                // Role & Permission
+                .service(handlers::role::get_permissions)
+                .service(handlers::role::get_roles)
+                .service(handlers::role::create_role)
+                .service(handlers::role::set_permissions)
+                .service(handlers::role::delete_role)
+                // Session
+                .service(handlers::session::get_user_sessions)
+                .service(handlers::session::revoke_user_session)
+                .service(handlers::session::revoke_all_user_sessions)
+                // Organization
+                .service(handlers::organization::create_organization)
+                .service(handlers::organization::get_organizations)
+                .service(handlers::organization::get_organization_by_id)
+                .service(handlers::organization::update_organization)
+                .service(handlers::organization::delete_organization)
+                .service(handlers::organization::get_organization_lands)
+                .service(handlers::organization::get_members)
+                .service(handlers::organization::add_member)
+                .service(handlers::organization::update_member)
+                .service(handlers::organization::remove_member)
                 // Land
                 .service(handlers::land::create_land)
                 .service(handlers::land::get_user_lands)
+                .service(handlers::land::search_lands)
                 .service(handlers::land::get_land_by_id)
                 .service(handlers::land::update_land)
                 .service(handlers::land::delete_land)
+                .service(handlers::land::restore_land)
+                .service(handlers::land_config::export_land_config)
+                .service(handlers::land_config::import_land_config)
+                // Zone
+                .service(handlers::zone::create_zone)
+                .service(handlers::zone::get_zones_by_land)
+                .service(handlers::zone::get_zone_by_id)
+                .service(handlers::zone::update_zone)
+                .service(handlers::zone::delete_zone)
+                .service(handlers::zone::restore_zone)
+                .service(handlers::zone::assign_zone)
+                .service(handlers::geojson::get_land_geojson)
+                // Weather
+                .service(handlers::weather::ingest_weather)
+                .service(handlers::weather::get_land_weather)
+                .service(handlers::weather::get_weather_outlook)
+                .service(handlers::weather::refresh_weather)
                 // Sensor
                 .service(handlers::sensor::create_sensor)
                 .service(handlers::sensor::get_land_sensors)
@@ -82,33 +225,68 @@ async fn main(
                 .service(handlers::sensor::get_sensor_by_id)
                 .service(handlers::sensor::update_sensor)
                 .service(handlers::sensor::delete_sensor)
+                .service(handlers::sensor::restore_sensor)
                 // Sensor History
+                .service(handlers::sensor_history::create_history)
                 .service(handlers::sensor_history::get_history_by_sensor)
                 .service(handlers::sensor_history::get_latest_history_by_sensor)
+                .service(handlers::sensor_history::get_anomalies_by_sensor)
+                // Derived Sensors
+                .service(handlers::derived_sensor::create_derived_sensor)
+                .service(handlers::derived_sensor::get_derived_sensors_by_land)
+                .service(handlers::export::export_sensor_history)
+                .service(handlers::export::export_land_history)
+                // Import
+                .service(handlers::import::import_sensor_history)
+                .service(handlers::import::get_imports_by_land)
+                .service(handlers::import::get_import)
+                .service(handlers::import::resume_import)
+                // Retention
+                .service(handlers::retention::get_retention_policies)
+                .service(handlers::retention::set_retention_policy)
+                .service(handlers::retention::delete_retention_policy)
+                .service(handlers::anomaly::get_anomaly_policies)
+                .service(handlers::anomaly::set_anomaly_policy)
+                .service(handlers::anomaly::delete_anomaly_policy)
                 // Plant
                 .service(handlers::plant::create_plant)
                 .service(handlers::plant::get_plants_by_land)
                 .service(handlers::plant::get_plant_by_id)
                 .service(handlers::plant::update_plant)
                 .service(handlers::plant::delete_plant)
+                .service(handlers::plant::restore_plant)
+                // Harvest & yield
+                .service(handlers::harvest::create_harvest)
+                .service(handlers::harvest::get_harvests_by_plant)
+                .service(handlers::harvest::delete_harvest)
+                .service(handlers::harvest::get_land_yield_report)
+                .service(handlers::harvest::get_seed_yield_report)
                 // Valve
                 .service(handlers::valve::create_valve)
                 .service(handlers::valve::get_valves_by_land)
                 .service(handlers::valve::get_valve_by_id)
                 .service(handlers::valve::update_valve)
                 .service(handlers::valve::delete_valve)
+                .service(handlers::valve::restore_valve)
                 // Pump
                 .service(handlers::pump::create_pump)
                 .service(handlers::pump::get_pumps_by_land)
                 .service(handlers::pump::get_pump_by_id)
                 .service(handlers::pump::update_pump)
                 .service(handlers::pump::delete_pump)
+                .service(handlers::pump::restore_pump)
                 // Automation
                 .service(handlers::automation::create_automation)
                 .service(handlers::automation::get_automations_by_land)
                 .service(handlers::automation::get_automation_by_id)
                 .service(handlers::automation::update_automation)
                 .service(handlers::automation::delete_automation)
+                .service(handlers::automation::restore_automation)
+                .service(handlers::automation::enable_automation)
+                .service(handlers::automation::disable_automation)
+                .service(handlers::automation::pause_automation)
+                .service(handlers::automation::resume_automation)
+                .service(handlers::automation::trigger_automation)
                 // Automation History
                 .service(handlers::automation_history::get_history_by_automation)
                 .service(handlers::automation_history::get_latest_history_by_automation)
@@ -130,9 +308,23 @@ async fn main(
                 .service(handlers::pest_control::get_pest_control_by_id)
                 .service(handlers::pest_control::update_pest_control)
                 .service(handlers::pest_control::delete_pest_control)
+                // Task
+                .service(handlers::task::create_task)
+                .service(handlers::task::create_tasks_from_recommendations)
+                .service(handlers::task::get_tasks_by_land)
+                .service(handlers::task::get_my_tasks)
+                .service(handlers::task::get_task_by_id)
+                .service(handlers::task::update_task)
+                .service(handlers::task::delete_task)
+                .service(handlers::task::create_task_comment)
                 // Notification
                 .service(handlers::notification::get_notifications_by_user)
                 .service(handlers::notification::get_all_notifications)
+                // Audit Log
+                .service(handlers::audit::get_audit_logs)
+                // Trash
+                .service(handlers::trash::get_trash)
+                .service(handlers::trash::purge_trash)
         );
     };
 
@@ -0,0 +1,80 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use chrono::Local;
+use shuttle_runtime::SecretStore;
+
+// Pesan keluar ke user (reset password, dsb.), tidak bergantung pada saluran pengirimannya.
+// Isinya bisa rahasia (kode reset), jadi jangan pernah disimpan di tempat yang bisa dibaca user lain.
+pub struct OutboundMessage {
+    pub user_id: i32,
+    pub username: String,
+    // Alamat tujuan dari profil user; sender memakai yang sesuai salurannya
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub subject: String,
+    pub body: String,
+}
+
+impl OutboundMessage {
+    fn recipient(&self) -> String {
+        let address = self.email.as_deref().or(self.phone.as_deref()).unwrap_or("no address");
+        format!("user #{} ({}, {})", self.user_id, self.username, address)
+    }
+}
+
+// Saluran pengiriman pesan. Implementasi lain (email, SMS, WhatsApp) cukup
+// mengimplementasikan trait ini lalu didaftarkan di `from_secrets`.
+#[async_trait]
+pub trait MessageSender: Send + Sync {
+    async fn send(&self, message: &OutboundMessage) -> anyhow::Result<()>;
+}
+
+// Untuk development lokal: cetak pesan ke log
+pub struct LogSender;
+
+#[async_trait]
+impl MessageSender for LogSender {
+    async fn send(&self, message: &OutboundMessage) -> anyhow::Result<()> {
+        println!("[message] to {}: {}\n{}", message.recipient(), message.subject, message.body);
+        Ok(())
+    }
+}
+
+// Tambahkan pesan ke file outbox (satu pesan per blok)
+pub struct FileSender {
+    path: PathBuf,
+}
+
+#[async_trait]
+impl MessageSender for FileSender {
+    async fn send(&self, message: &OutboundMessage) -> anyhow::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(
+            file,
+            "--- {} | to {}\nSubject: {}\n\n{}\n",
+            Local::now().naive_local(),
+            message.recipient(),
+            message.subject,
+            message.body
+        )?;
+        Ok(())
+    }
+}
+
+// MESSAGE_CHANNEL = "log" (default) | "file".
+// Tidak ada saluran in-app: tabel notification bisa dibaca admin (notification:read_all),
+// dan user yang sedang reset password belum bisa login untuk membacanya.
+pub fn from_secrets(secrets: &SecretStore) -> Box<dyn MessageSender> {
+    match secrets.get("MESSAGE_CHANNEL").as_deref().map(str::trim) {
+        Some("file") => Box::new(FileSender {
+            path: secrets
+                .get("MESSAGE_OUTBOX_PATH")
+                .unwrap_or_else(|| "outbox.log".to_string())
+                .into(),
+        }),
+        _ => Box::new(LogSender),
+    }
+}
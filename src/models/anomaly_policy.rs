@@ -0,0 +1,39 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+use super::sensor::SensorType;
+
+// Override deteksi flatline (probe macet). Yang paling spesifik menang:
+// land + tipe sensor > land > tipe sensor > default di config.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "anomaly_policy")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    // None = berlaku untuk semua land
+    pub land_id: Option<i32>,
+    // None = berlaku untuk semua tipe sensor
+    pub sensor_type: Option<SensorType>,
+    // Pembacaan identik berturut-turut yang dianggap macet (0 = tidak dicek)
+    pub flatline_readings: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::land::Entity",
+        from = "Column::LandId",
+        to = "super::land::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Land,
+}
+
+impl Related<super::land::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Land.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,56 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum AuditAction {
+    #[sea_orm(string_value = "Create")]
+    Create,
+    #[sea_orm(string_value = "Update")]
+    Update,
+    #[sea_orm(string_value = "Delete")]
+    Delete,
+    // Hapus permanen dari trash (soft delete yang lewat masa retensi)
+    #[sea_orm(string_value = "Purge")]
+    Purge,
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "audit_log")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    // User yang melakukan perubahan (kosong jika user sudah dihapus / tanpa login)
+    pub actor_id: Option<i32>,
+    pub action: AuditAction,
+    // Nama tabel entitas, mis. "land", "pump", "user"
+    pub entity_type: String,
+    pub entity_id: i32,
+    // Snapshot sebelum & sesudah perubahan
+    pub before: Option<Json>,
+    pub after: Option<Json>,
+    // Hanya field yang berubah: { "field": { "from": .., "to": .. } }
+    pub changes: Option<Json>,
+    pub ip: Option<String>,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::ActorId",
+        to = "super::user::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
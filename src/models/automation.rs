@@ -13,6 +13,21 @@ pub enum AutomationType {
     PestControl,
 }
 
+// Reaksi automation penyiraman terhadap hujan (lihat `weather::assess`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum WeatherMode {
+    #[default]
+    #[sea_orm(string_value = "Ignore")]
+    Ignore,
+    // Lewati penyiraman jika hujan mencapai threshold
+    #[sea_orm(string_value = "Skip")]
+    Skip,
+    // Seperti Skip, tapi di bawah threshold volume dikurangi sebanding dengan hujannya
+    #[sea_orm(string_value = "Scale")]
+    Scale,
+}
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
 #[sea_orm(table_name = "automation")]
 pub struct Model {
@@ -29,7 +44,22 @@ pub struct Model {
     pub pump_id: i32,
     pub valve_id: i32,
     pub land_id: i32,
+    // Zona katup yang dibuka (diisi otomatis, None = seluruh land)
+    pub zone_id: Option<i32>,
     pub dispense_amount: f64,
+
+    // Saklar on/off dan jeda sementara (misal saat maintenance)
+    pub enabled: bool,
+    pub paused_until: Option<DateTime>,
+
+    // Hujan yang diperhitungkan: observasi `rain_lookback_hours` terakhir + forecast
+    // `rain_forecast_hours` ke depan (mm), dibandingkan dengan `rain_threshold_mm`
+    pub weather_mode: WeatherMode,
+    pub rain_threshold_mm: Option<f64>,
+    pub rain_lookback_hours: i32,
+    pub rain_forecast_hours: i32,
+
+    pub deleted_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -60,6 +90,14 @@ pub enum Relation {
         to = "super::valve::Column::Id",
     )]
     Valve,
+    #[sea_orm(
+        belongs_to = "super::zone::Entity",
+        from = "Column::ZoneId",
+        to = "super::zone::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    Zone,
 
     #[sea_orm(has_many = "super::automation_history::Entity")]
     AutomationHistory,
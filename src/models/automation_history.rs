@@ -1,5 +1,26 @@
 use sea_orm::entity::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+// Jenis kejadian yang dicatat untuk sebuah automation
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum AutomationEvent {
+    #[sea_orm(string_value = "Triggered")]
+    Triggered,
+    #[sea_orm(string_value = "Enabled")]
+    Enabled,
+    #[sea_orm(string_value = "Disabled")]
+    Disabled,
+    #[sea_orm(string_value = "Paused")]
+    Paused,
+    #[sea_orm(string_value = "Resumed")]
+    Resumed,
+    #[sea_orm(string_value = "Blocked")]
+    Blocked,
+    // Dilewati engine karena hujan
+    #[sea_orm(string_value = "Skipped")]
+    Skipped,
+}
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
 #[sea_orm(table_name = "automation_history")]
@@ -7,6 +28,13 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub automation_id: i32,
+    pub event: AutomationEvent,
+    // None = dijalankan oleh engine, Some = user yang melakukan aksi
+    pub user_id: Option<i32>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub note: Option<String>,
+    // Volume yang dialirkan (hanya untuk event Triggered, sudah dikurangi jika di-scale karena hujan)
+    pub volume: Option<f64>,
     pub triggered_at: DateTime,
 }
 
@@ -20,6 +48,14 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Automation,
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    User,
 }
 
 impl Related<super::automation::Entity> for Entity {
@@ -28,4 +64,10 @@ impl Related<super::automation::Entity> for Entity {
     }
 }
 
-impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,77 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum DerivedMetric {
+    // Vapour pressure deficit (kPa) dari suhu & kelembapan
+    #[sea_orm(string_value = "Vpd")]
+    Vpd,
+    // Titik embun (°C) dari suhu & kelembapan
+    #[sea_orm(string_value = "DewPoint")]
+    DewPoint,
+    // Growing degree days kumulatif sejak tanaman ditanam
+    #[sea_orm(string_value = "GrowingDegreeDays")]
+    GrowingDegreeDays,
+}
+
+// Definisi sensor virtual. Sensornya sendiri tetap baris di tabel sensor agar riwayat,
+// endpoint history/latest dan automation bisa memakainya seperti sensor biasa.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "derived_sensor")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub sensor_id: i32,
+    pub metric: DerivedMetric,
+    pub temperature_sensor_id: i32,
+    // Wajib untuk Vpd & DewPoint
+    pub humidity_sensor_id: Option<i32>,
+    // Wajib untuk GrowingDegreeDays
+    pub plant_id: Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::sensor::Entity",
+        from = "Column::SensorId",
+        to = "super::sensor::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Sensor,
+    #[sea_orm(
+        belongs_to = "super::sensor::Entity",
+        from = "Column::TemperatureSensorId",
+        to = "super::sensor::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    TemperatureSensor,
+    #[sea_orm(
+        belongs_to = "super::sensor::Entity",
+        from = "Column::HumiditySensorId",
+        to = "super::sensor::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    HumiditySensor,
+    #[sea_orm(
+        belongs_to = "super::plant::Entity",
+        from = "Column::PlantId",
+        to = "super::plant::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Plant,
+}
+
+impl Related<super::sensor::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Sensor.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,74 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum YieldUnit {
+    #[sea_orm(string_value = "Kg")]
+    Kg,
+    #[sea_orm(string_value = "Ton")]
+    Ton,
+    #[sea_orm(string_value = "Piece")]
+    Piece,
+    #[sea_orm(string_value = "Bunch")]
+    Bunch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum QualityGrade {
+    #[sea_orm(string_value = "A")]
+    A,
+    #[sea_orm(string_value = "B")]
+    B,
+    #[sea_orm(string_value = "C")]
+    C,
+    #[sea_orm(string_value = "Reject")]
+    Reject,
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "harvest")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub plant_id: i32,
+    pub harvested_at: DateTime,
+    pub quantity: f64,
+    pub unit: YieldUnit,
+    pub quality_grade: Option<QualityGrade>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub notes: Option<String>,
+    // Panen terakhir yang menutup tanaman (panen bertahap mengisi false)
+    pub closes_plant: bool,
+    pub recorded_by: Option<i32>,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::plant::Entity",
+        from = "Column::PlantId",
+        to = "super::plant::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Plant,
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::RecordedBy",
+        to = "super::user::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    User,
+}
+
+impl Related<super::plant::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Plant.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
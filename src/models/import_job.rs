@@ -0,0 +1,79 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum ImportStatus {
+    #[sea_orm(string_value = "Pending")]
+    Pending,
+    #[sea_orm(string_value = "Running")]
+    Running,
+    #[sea_orm(string_value = "Completed")]
+    Completed,
+    #[sea_orm(string_value = "Failed")]
+    Failed,
+}
+
+// Import CSV riwayat sensor yang diproses di latar belakang per batch.
+// `processed_rows` menjadi titik lanjut jika job terhenti di tengah jalan.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "import_job")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub land_id: i32,
+    pub created_by: Option<i32>,
+    pub filename: Option<String>,
+    pub status: ImportStatus,
+    // Isi CSV disimpan agar job bisa dilanjutkan tanpa upload ulang
+    #[sea_orm(column_type = "Text")]
+    #[serde(skip)]
+    pub payload: String,
+    pub total_rows: i32,
+    pub processed_rows: i32,
+    pub imported_rows: i32,
+    // Baris yang (sensor_id, recorded_at)-nya sudah ada
+    pub duplicate_rows: i32,
+    pub error_count: i32,
+    // [{ "row": 12, "message": ".." }], dibatasi beberapa ratus baris pertama
+    pub errors: Json,
+    // Alasan job gagal (error database dsb.), bukan error per baris
+    pub failure: Option<String>,
+    pub created_at: DateTime,
+    pub started_at: Option<DateTime>,
+    pub finished_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::land::Entity",
+        from = "Column::LandId",
+        to = "super::land::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Land,
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::CreatedBy",
+        to = "super::user::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    User,
+}
+
+impl Related<super::land::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Land.def()
+    }
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
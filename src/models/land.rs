@@ -10,7 +10,17 @@ pub struct Model {
     pub id: i32,
     pub location_name: String,
     pub size: f64, 
+    // Titik lokasi land (diisi dari titik tengah boundary jika tidak dikirim)
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    // Batas land: daftar titik [lng, lat] (lihat `geo`)
+    pub boundary: Option<Json>,
+    // Petani penanggung jawab (penerima notifikasi)
     pub user_id: i32,
+    // Organisasi pemilik land; akses ke land ditentukan keanggotaan organisasi ini
+    pub organization_id: i32,
+    // Terisi saat land dihapus (soft delete), lihat `soft_delete`
+    pub deleted_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -20,10 +30,19 @@ pub enum Relation {
         from = "Column::UserId",
         to = "user::Column::Id",
         on_update = "Cascade",
-        on_delete = "Cascade"
+        on_delete = "Restrict"
     )]
     User,
 
+    #[sea_orm(
+        belongs_to = "super::organization::Entity",
+        from = "Column::OrganizationId",
+        to = "super::organization::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Restrict"
+    )]
+    Organization,
+
     #[sea_orm(has_many = "super::sensor::Entity")]
     Sensor,
 
@@ -46,6 +65,12 @@ impl Related<user::Entity> for Entity {
     }
 }
 
+impl Related<super::organization::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Organization.def()
+    }
+}
+
 impl Related<super::sensor::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Sensor.def()
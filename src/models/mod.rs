@@ -1,9 +1,24 @@
 pub mod user;
 pub mod user_role;
+pub mod permission;
+pub mod role_permission;
+pub mod user_session;
+pub mod organization;
+pub mod organization_member;
+pub mod refresh_token;
+pub mod password_reset_token;
 pub mod land;
+pub mod zone;
 pub mod sensor;
+pub mod derived_sensor;
 pub mod sensor_history;
+pub mod sensor_history_hourly;
+pub mod sensor_history_daily;
+pub mod retention_policy;
+pub mod anomaly_policy;
+pub mod import_job;
 pub mod plant;
+pub mod harvest;
 pub mod valve;
 pub mod pump;
 pub mod automation;
@@ -11,4 +26,8 @@ pub mod automation_history;
 pub mod seed;
 pub mod recommendation;
 pub mod pest_control;
-pub mod notification;
\ No newline at end of file
+pub mod task;
+pub mod task_comment;
+pub mod weather;
+pub mod notification;
+pub mod audit_log;
\ No newline at end of file
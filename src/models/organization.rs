@@ -0,0 +1,36 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+// Koperasi / kelompok tani. Land dimiliki organisasi, bukan user perorangan.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "organization")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub name: String,
+    pub created_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::organization_member::Entity")]
+    Member,
+
+    #[sea_orm(has_many = "super::land::Entity")]
+    Land,
+}
+
+impl Related<super::organization_member::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Member.def()
+    }
+}
+
+impl Related<super::land::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Land.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
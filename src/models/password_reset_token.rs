@@ -0,0 +1,36 @@
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "password_reset_token")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    // Hanya hash SHA-256 dari token yang dikirim ke user
+    #[sea_orm(unique)]
+    pub token_hash: String,
+    pub user_id: i32,
+    pub created_at: DateTime,
+    pub expires_at: DateTime,
+    // Token hanya bisa dipakai sekali
+    pub used_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
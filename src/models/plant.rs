@@ -9,8 +9,12 @@ pub struct Model {
     pub name: String,
     pub quantity: i32,
     pub land_id: i32,
+    pub zone_id: Option<i32>,
     pub seed_id: i32, // <--- Ditambahkan
     pub planted_at: DateTime,
+    // Terisi saat panen terakhir dicatat; tanaman yang sudah ditutup tidak bisa dipanen lagi
+    pub closed_at: Option<DateTime>,
+    pub deleted_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -32,6 +36,14 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Seed,
+    #[sea_orm(
+        belongs_to = "super::zone::Entity",
+        from = "Column::ZoneId",
+        to = "super::zone::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    Zone,
 }
 
 impl Related<super::land::Entity> for Entity {
@@ -1,13 +1,24 @@
 use sea_orm::entity::prelude::*;
 use serde::Serialize;
 
-#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
 #[sea_orm(table_name = "pump")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub name: String,
     pub land_id: i32,
+    // Titik pemasangan di peta
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+
+    // Batas keamanan (None = tidak dibatasi)
+    pub flow_rate: Option<f64>, // liter per menit
+    pub max_runtime_seconds: Option<i32>,
+    pub max_daily_volume: Option<f64>,
+    pub cooldown_seconds: Option<i32>,
+
+    pub deleted_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,38 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "refresh_token")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    // Sama seperti session, yang disimpan hanya hash SHA-256 dari token
+    #[sea_orm(unique)]
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub user_id: i32,
+    pub created_at: DateTime,
+    pub expires_at: DateTime,
+    // Terisi saat token dirotasi (refresh) atau dicabut
+    pub revoked_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,8 +1,8 @@
 use super::land;
 use sea_orm::entity::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
 #[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
 pub enum SensorType {
     #[sea_orm(string_value = "Temperature")]
@@ -15,6 +15,13 @@ pub enum SensorType {
     PH,
     #[sea_orm(string_value = "LightIntensity")]
     LightIntensity,
+    // Sensor virtual: nilainya dihitung dari sensor lain (lihat `derived`)
+    #[sea_orm(string_value = "Vpd")]
+    Vpd,
+    #[sea_orm(string_value = "DewPoint")]
+    DewPoint,
+    #[sea_orm(string_value = "GrowingDegreeDays")]
+    GrowingDegreeDays,
 }
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
@@ -25,6 +32,11 @@ pub struct Model {
     pub name: String,
     pub sensor_type: SensorType, 
     pub land_id: i32,
+    pub zone_id: Option<i32>,
+    // Titik pemasangan di peta
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub deleted_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -37,6 +49,14 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Land,
+    #[sea_orm(
+        belongs_to = "super::zone::Entity",
+        from = "Column::ZoneId",
+        to = "super::zone::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    Zone,
 
     #[sea_orm(has_many = "super::sensor_history::Entity")]
     SensorHistory,
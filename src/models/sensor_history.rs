@@ -1,5 +1,17 @@
 use sea_orm::entity::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+// Alasan pembacaan ditandai mencurigakan oleh deteksi anomali (lihat `anomaly`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum AnomalyKind {
+    #[sea_orm(string_value = "ZScore")]
+    ZScore,
+    #[sea_orm(string_value = "RateOfChange")]
+    RateOfChange,
+    #[sea_orm(string_value = "Flatline")]
+    Flatline,
+}
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
 #[sea_orm(table_name = "sensor_history")]
@@ -9,6 +21,12 @@ pub struct Model {
     pub sensor_id: i32,
     pub value: f64,
     pub recorded_at: DateTime,
+    // Sudah masuk agregat harian oleh job rollup; hanya baris seperti ini yang boleh dihapus retensi
+    #[serde(skip_serializing)]
+    pub rolled_up: bool,
+    // Terisi jika pembacaan dianggap anomali; tidak dipakai untuk evaluasi automation
+    pub anomaly: Option<AnomalyKind>,
+    pub anomaly_detail: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,38 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+// Agregat pembacaan sensor per hari, diisi job rollup (lihat `rollup`)
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "sensor_history_daily")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    #[serde(skip_serializing)]
+    pub id: i32,
+    pub sensor_id: i32,
+    // Awal bucket; unik bersama sensor_id
+    pub bucket_start: DateTime,
+    pub count: i32,
+    pub min_value: f64,
+    pub max_value: f64,
+    pub avg_value: f64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::sensor::Entity",
+        from = "Column::SensorId",
+        to = "super::sensor::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Sensor,
+}
+
+impl Related<super::sensor::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Sensor.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
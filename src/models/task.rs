@@ -0,0 +1,157 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum TaskPriority {
+    #[sea_orm(string_value = "Low")]
+    Low,
+    #[sea_orm(string_value = "Medium")]
+    Medium,
+    #[sea_orm(string_value = "High")]
+    High,
+    #[sea_orm(string_value = "Urgent")]
+    Urgent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum TaskStatus {
+    #[sea_orm(string_value = "Open")]
+    Open,
+    #[sea_orm(string_value = "InProgress")]
+    InProgress,
+    #[sea_orm(string_value = "Done")]
+    Done,
+    #[sea_orm(string_value = "Cancelled")]
+    Cancelled,
+}
+
+impl TaskStatus {
+    // Task yang masih perlu dikerjakan
+    pub const OPEN: [TaskStatus; 2] = [TaskStatus::Open, TaskStatus::InProgress];
+}
+
+// Pekerjaan lapangan di sebuah land (penyiraman manual, pengendalian hama, perawatan alat, ...)
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "task")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub land_id: i32,
+    pub title: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub description: Option<String>,
+    pub priority: TaskPriority,
+    pub status: TaskStatus,
+    pub due_at: Option<DateTime>,
+    pub assignee_id: Option<i32>,
+    pub created_by: Option<i32>,
+
+    // Objek yang dikerjakan (opsional, semuanya di land yang sama)
+    pub plant_id: Option<i32>,
+    pub pump_id: Option<i32>,
+    pub valve_id: Option<i32>,
+    pub sensor_id: Option<i32>,
+    pub pest_control_id: Option<i32>,
+    // Terisi jika task dibuat dari rekomendasi
+    pub recommendation_id: Option<i32>,
+
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+    pub completed_at: Option<DateTime>,
+    // Notifikasi terlambat hanya dikirim sekali per due date
+    pub overdue_notified_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::land::Entity",
+        from = "Column::LandId",
+        to = "super::land::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Land,
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::AssigneeId",
+        to = "super::user::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    Assignee,
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::CreatedBy",
+        to = "super::user::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    Creator,
+    #[sea_orm(
+        belongs_to = "super::plant::Entity",
+        from = "Column::PlantId",
+        to = "super::plant::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    Plant,
+    #[sea_orm(
+        belongs_to = "super::pump::Entity",
+        from = "Column::PumpId",
+        to = "super::pump::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    Pump,
+    #[sea_orm(
+        belongs_to = "super::valve::Entity",
+        from = "Column::ValveId",
+        to = "super::valve::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    Valve,
+    #[sea_orm(
+        belongs_to = "super::sensor::Entity",
+        from = "Column::SensorId",
+        to = "super::sensor::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    Sensor,
+    #[sea_orm(
+        belongs_to = "super::pest_control::Entity",
+        from = "Column::PestControlId",
+        to = "super::pest_control::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    PestControl,
+    #[sea_orm(
+        belongs_to = "super::recommendation::Entity",
+        from = "Column::RecommendationId",
+        to = "super::recommendation::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    Recommendation,
+    #[sea_orm(has_many = "super::task_comment::Entity")]
+    Comment,
+}
+
+impl Related<super::land::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Land.def()
+    }
+}
+
+impl Related<super::task_comment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Comment.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,5 +1,16 @@
 use super::{user_role};
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// User yang di-suspend tidak bisa login dan session/token-nya ditolak
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum UserStatus {
+    #[sea_orm(string_value = "Active")]
+    Active,
+    #[sea_orm(string_value = "Suspended")]
+    Suspended,
+}
 
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
 #[sea_orm(table_name = "user")]
@@ -10,6 +21,21 @@ pub struct Model {
     pub username: String,
     pub password: String,
     pub user_role_id: i32,
+
+    // Lockout setelah login gagal berturut-turut
+    pub failed_login_attempts: i32,
+    pub locked_until: Option<DateTime>,
+
+    // Profil & kontak untuk notifikasi
+    pub full_name: Option<String>,
+    pub phone: Option<String>,
+    #[sea_orm(unique, nullable)]
+    pub email: Option<String>,
+    pub language: String,
+    pub timezone: String,
+    pub status: UserStatus,
+    // Akun dihapus (soft delete): baris tetap ada agar land, audit & riwayat yang merujuknya utuh
+    pub deleted_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -14,6 +14,9 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "user::Entity")]
     User,
+
+    #[sea_orm(has_many = "super::role_permission::Entity")]
+    RolePermission,
 }
 
 impl Related<user::Entity> for Entity {
@@ -22,4 +25,10 @@ impl Related<user::Entity> for Entity {
     }
 }
 
+impl Related<super::role_permission::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RolePermission.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
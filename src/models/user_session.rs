@@ -0,0 +1,43 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "user_session")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    // Yang disimpan hanya hash SHA-256 dari session key di cookie
+    #[sea_orm(unique)]
+    #[serde(skip_serializing)]
+    pub session_key_hash: String,
+    pub user_id: Option<i32>,
+    #[sea_orm(column_type = "Text")]
+    #[serde(skip_serializing)]
+    pub state: String,
+    pub ip: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub user_agent: Option<String>,
+    pub created_at: DateTime,
+    pub last_seen_at: DateTime,
+    pub expires_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
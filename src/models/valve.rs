@@ -1,13 +1,25 @@
 use sea_orm::entity::prelude::*;
 use serde::Serialize;
 
-#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
 #[sea_orm(table_name = "valve")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub name: String,
     pub land_id: i32,
+    pub zone_id: Option<i32>,
+    // Titik pemasangan di peta
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub is_open: bool,
+
+    // Batas keamanan (None = tidak dibatasi)
+    pub max_runtime_seconds: Option<i32>,
+    pub max_daily_volume: Option<f64>,
+    pub cooldown_seconds: Option<i32>,
+
+    pub deleted_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -20,6 +32,14 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Land,
+    #[sea_orm(
+        belongs_to = "super::zone::Entity",
+        from = "Column::ZoneId",
+        to = "super::zone::Column::Id",
+        on_update = "Cascade",
+        on_delete = "SetNull"
+    )]
+    Zone,
 }
 
 impl Related<super::land::Entity> for Entity {
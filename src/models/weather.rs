@@ -0,0 +1,52 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum WeatherKind {
+    // Cuaca yang sudah terjadi (stasiun lokal / provider)
+    #[sea_orm(string_value = "Observation")]
+    Observation,
+    #[sea_orm(string_value = "Forecast")]
+    Forecast,
+}
+
+// Satu baris = kondisi cuaca satu land untuk satu jam (`valid_at` = awal jam tersebut)
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "weather")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub land_id: i32,
+    pub kind: WeatherKind,
+    pub valid_at: DateTime,
+    // Curah hujan (mm) selama jam tersebut
+    pub rainfall_mm: f64,
+    // Peluang hujan 0..1, hanya untuk forecast (None = dianggap pasti)
+    pub rain_probability: Option<f64>,
+    pub temperature: Option<f64>,
+    pub humidity: Option<f64>,
+    // Nama provider atau "manual" untuk data yang dikirim lewat API
+    pub source: String,
+    pub received_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::land::Entity",
+        from = "Column::LandId",
+        to = "super::land::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Land,
+}
+
+impl Related<super::land::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Land.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
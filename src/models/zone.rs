@@ -0,0 +1,38 @@
+use sea_orm::entity::prelude::*;
+use serde::Serialize;
+
+// Petak / bedengan di dalam land. Tanaman, sensor & katup bisa ditempatkan di satu zona;
+// automation mengikuti zona katupnya.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+#[sea_orm(table_name = "zone")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub land_id: i32,
+    pub name: String,
+    // Satuan sama dengan `land.size`
+    pub area: f64,
+    // Batas zona: daftar titik [lng, lat] (lihat `geo`)
+    pub polygon: Option<Json>,
+    pub deleted_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::land::Entity",
+        from = "Column::LandId",
+        to = "super::land::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Land,
+}
+
+impl Related<super::land::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Land.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,29 @@
+use sea_orm::{ActiveModelTrait, DatabaseConnection, DbErr, EntityTrait, Set};
+
+use crate::models::{land, notification};
+
+pub async fn notify_user(
+    db: &DatabaseConnection,
+    user_id: i32,
+    description: String,
+) -> Result<notification::Model, DbErr> {
+    notification::ActiveModel {
+        user_id: Set(user_id),
+        description: Set(description),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+}
+
+// Kirim notifikasi ke pemilik land
+pub async fn notify_land_owner(
+    db: &DatabaseConnection,
+    land_id: i32,
+    description: String,
+) -> Result<Option<notification::Model>, DbErr> {
+    match land::Entity::find_by_id(land_id).one(db).await? {
+        Some(l) => Ok(Some(notify_user(db, l.user_id, description).await?)),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,118 @@
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+};
+
+use crate::models::{permission, role_permission};
+
+// Permission yang dicek di handler. Role & mapping-nya diatur lewat endpoint admin,
+// tapi daftar permission-nya tetap di sini karena harus cocok dengan pengecekan di kode.
+// Super-admin platform: boleh menjangkau semua organisasi
+pub const PLATFORM_ADMIN: &str = "platform:admin";
+pub const USER_MANAGE: &str = "user:manage";
+pub const ROLE_MANAGE: &str = "role:manage";
+pub const LAND_WRITE: &str = "land:write";
+pub const AUTOMATION_WRITE: &str = "automation:write";
+pub const AUTOMATION_TRIGGER: &str = "automation:trigger";
+pub const SENSOR_INGEST: &str = "sensor:ingest";
+pub const SEED_MANAGE: &str = "seed:manage";
+pub const RECOMMENDATION_WRITE: &str = "recommendation:write";
+pub const PEST_CONTROL_WRITE: &str = "pest_control:write";
+pub const TASK_WRITE: &str = "task:write";
+pub const NOTIFICATION_READ_ALL: &str = "notification:read_all";
+pub const AUDIT_READ: &str = "audit:read";
+
+pub const ALL: &[(&str, &str)] = &[
+    (PLATFORM_ADMIN, "Access and manage every organization (platform super-admin)"),
+    (USER_MANAGE, "Create, update, suspend and unlock users; manage other users' sessions"),
+    (ROLE_MANAGE, "Create roles and assign permissions to them"),
+    (LAND_WRITE, "Create, update and delete lands, sensors, plants, pumps and valves"),
+    (AUTOMATION_WRITE, "Create, update, delete, enable, disable and pause automations"),
+    (AUTOMATION_TRIGGER, "Trigger automations manually"),
+    (SENSOR_INGEST, "Submit sensor readings"),
+    (SEED_MANAGE, "Manage the seed catalogue"),
+    (RECOMMENDATION_WRITE, "Create, update and delete recommendations"),
+    (PEST_CONTROL_WRITE, "Create, update and delete pest control records"),
+    (TASK_WRITE, "Create, assign, update and delete field tasks"),
+    (NOTIFICATION_READ_ALL, "Read notifications of all users"),
+    (AUDIT_READ, "Read the audit log of users in managed organizations"),
+];
+
+// Pastikan semua permission di `ALL` ada di database (dipanggil setiap startup)
+pub async fn sync_permissions(db: &DatabaseConnection) -> Result<(), DbErr> {
+    for (name, description) in ALL {
+        let existing = permission::Entity::find()
+            .filter(permission::Column::Name.eq(*name))
+            .one(db)
+            .await?;
+
+        match existing {
+            Some(p) if p.description == *description => {}
+            Some(p) => {
+                let mut active: permission::ActiveModel = p.into();
+                active.description = Set(description.to_string());
+                active.update(db).await?;
+            }
+            None => {
+                permission::ActiveModel {
+                    name: Set(name.to_string()),
+                    description: Set(description.to_string()),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Ganti seluruh permission sebuah role dengan daftar baru (jalankan di dalam transaksi).
+// Nama yang tidak dikenal dikembalikan sebagai Err agar bisa dilaporkan ke client.
+pub async fn set_role_permissions<C: ConnectionTrait>(
+    db: &C,
+    role_id: i32,
+    names: &[String],
+) -> Result<Result<(), Vec<String>>, DbErr> {
+    let found = permission::Entity::find()
+        .filter(permission::Column::Name.is_in(names.iter().cloned()))
+        .all(db)
+        .await?;
+
+    let unknown: Vec<String> = names
+        .iter()
+        .filter(|n| !found.iter().any(|p| &p.name == *n))
+        .cloned()
+        .collect();
+    if !unknown.is_empty() {
+        return Ok(Err(unknown));
+    }
+
+    role_permission::Entity::delete_many()
+        .filter(role_permission::Column::UserRoleId.eq(role_id))
+        .exec(db)
+        .await?;
+
+    for p in found {
+        role_permission::ActiveModel {
+            user_role_id: Set(role_id),
+            permission_id: Set(p.id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+    }
+    Ok(Ok(()))
+}
+
+// Daftar nama permission milik sebuah role
+pub async fn role_permission_names<C: ConnectionTrait>(db: &C, role_id: i32) -> Result<Vec<String>, DbErr> {
+    let rows = role_permission::Entity::find()
+        .filter(role_permission::Column::UserRoleId.eq(role_id))
+        .find_also_related(permission::Entity)
+        .all(db)
+        .await?;
+
+    let mut names: Vec<String> = rows.into_iter().filter_map(|(_, p)| p.map(|p| p.name)).collect();
+    names.sort();
+    Ok(names)
+}
@@ -0,0 +1,36 @@
+use actix_web::HttpResponse;
+use serde::Serialize;
+
+// Envelope sukses yang dipakai semua handler: { "success": true, "message"?, "data"? }
+#[derive(Serialize)]
+pub struct ApiResponse<T: Serialize> {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+}
+
+pub fn ok<T: Serialize>(data: T) -> HttpResponse {
+    HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        message: None,
+        data: Some(data),
+    })
+}
+
+pub fn ok_with_message<T: Serialize>(message: &str, data: T) -> HttpResponse {
+    HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        message: Some(message.to_string()),
+        data: Some(data),
+    })
+}
+
+pub fn message(message: &str) -> HttpResponse {
+    HttpResponse::Ok().json(ApiResponse::<()> {
+        success: true,
+        message: Some(message.to_string()),
+        data: None,
+    })
+}
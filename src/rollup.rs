@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, Local, NaiveDateTime, NaiveTime, Timelike};
+use sea_orm::{
+    sea_query::Expr,
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, Statement, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::RetentionConfig,
+    models::{
+        retention_policy,
+        sensor::{self, SensorType},
+        sensor_history::{self, AnomalyKind},
+        sensor_history_daily, sensor_history_hourly,
+    },
+};
+
+// Resolusi riwayat sensor: pembacaan mentah atau agregat per jam / per hari
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    Raw,
+    Hourly,
+    Daily,
+}
+
+// Masa simpan efektif untuk satu sensor (0 = selamanya)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Policy {
+    pub raw_days: i32,
+    pub hourly_days: i32,
+}
+
+impl Policy {
+    pub fn defaults(config: &RetentionConfig) -> Self {
+        Self {
+            raw_days: config.sensor_raw_days,
+            hourly_days: config.sensor_hourly_days,
+        }
+    }
+
+    // Data sebelum waktu ini sudah dihapus untuk resolusi tersebut (None = masih lengkap).
+    // Penghapusan selalu per hari penuh agar agregat harian tidak dihitung dari data sebagian.
+    pub fn cutoff(&self, resolution: Resolution, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let days = match resolution {
+            Resolution::Raw => self.raw_days,
+            Resolution::Hourly => self.hourly_days,
+            Resolution::Daily => 0,
+        };
+        (days > 0).then(|| start_of_day(now) - Duration::days(days as i64))
+    }
+}
+
+fn start_of_day(t: NaiveDateTime) -> NaiveDateTime {
+    t.date().and_time(NaiveTime::MIN)
+}
+
+fn start_of_hour(t: NaiveDateTime) -> NaiveDateTime {
+    start_of_day(t) + Duration::hours(t.hour() as i64)
+}
+
+// Awal bucket yang memuat waktu t
+pub fn bucket_floor(resolution: Resolution, t: NaiveDateTime) -> NaiveDateTime {
+    match resolution {
+        Resolution::Raw => t,
+        Resolution::Hourly => start_of_hour(t),
+        Resolution::Daily => start_of_day(t),
+    }
+}
+
+// Policy paling spesifik yang cocok: land + tipe > land > tipe > default
+fn policy_for(
+    policies: &[retention_policy::Model],
+    land_id: i32,
+    sensor_type: &SensorType,
+    defaults: Policy,
+) -> Policy {
+    policies
+        .iter()
+        .filter(|p| p.land_id.is_none_or(|l| l == land_id))
+        .filter(|p| p.sensor_type.as_ref().is_none_or(|t| t == sensor_type))
+        .max_by_key(|p| (p.land_id.is_some(), p.sensor_type.is_some()))
+        .map(|p| Policy {
+            raw_days: p.raw_days,
+            hourly_days: p.hourly_days,
+        })
+        .unwrap_or(defaults)
+}
+
+pub async fn effective_policy(
+    db: &DatabaseConnection,
+    config: &RetentionConfig,
+    s: &sensor::Model,
+) -> Result<Policy, DbErr> {
+    let policies = retention_policy::Entity::find()
+        .filter(
+            Condition::any()
+                .add(retention_policy::Column::LandId.eq(s.land_id))
+                .add(retention_policy::Column::LandId.is_null()),
+        )
+        .all(db)
+        .await?;
+    Ok(policy_for(&policies, s.land_id, &s.sensor_type, Policy::defaults(config)))
+}
+
+// Resolusi otomatis: rentang pendek memakai data mentah, makin panjang makin kasar.
+// Jika awal rentang sudah melewati masa simpan suatu resolusi, naik ke resolusi berikutnya.
+pub fn pick_resolution(from: NaiveDateTime, to: NaiveDateTime, policy: &Policy, now: NaiveDateTime) -> Resolution {
+    let span = to - from;
+    let mut resolution = if span <= Duration::days(2) {
+        Resolution::Raw
+    } else if span <= Duration::days(62) {
+        Resolution::Hourly
+    } else {
+        Resolution::Daily
+    };
+
+    if resolution == Resolution::Raw && policy.cutoff(Resolution::Raw, now).is_some_and(|c| from < c) {
+        resolution = Resolution::Hourly;
+    }
+    if resolution == Resolution::Hourly && policy.cutoff(Resolution::Hourly, now).is_some_and(|c| from < c) {
+        resolution = Resolution::Daily;
+    }
+    resolution
+}
+
+// ====================================================
+// ROLLUP & RETENSI (dijalankan job berkala)
+// ====================================================
+
+#[derive(Debug, Default, Serialize)]
+pub struct RollupReport {
+    pub hourly_buckets: u64,
+    pub daily_buckets: u64,
+    pub raw_deleted: u64,
+    pub hourly_deleted: u64,
+}
+
+// Hitung ulang bucket yang punya pembacaan belum di-rollup (termasuk data susulan / import).
+// Pembacaan yang ditandai anomali tidak ikut dihitung, sama seperti automation mengabaikannya.
+const UPSERT_BUCKETS: &str = r#"
+INSERT INTO "{table}" (sensor_id, bucket_start, count, min_value, max_value, avg_value)
+SELECT h.sensor_id, date_trunc('{unit}', h.recorded_at) AS bucket,
+       COUNT(*)::int, MIN(h.value), MAX(h.value), AVG(h.value)
+FROM "sensor_history" h
+WHERE (h.sensor_id, date_trunc('{unit}', h.recorded_at)) IN (
+    SELECT DISTINCT sensor_id, date_trunc('{unit}', recorded_at)
+    FROM "sensor_history"
+    WHERE NOT rolled_up AND id <= $2 AND recorded_at < $1
+)
+AND h.anomaly IS NULL
+GROUP BY h.sensor_id, bucket
+ON CONFLICT (sensor_id, bucket_start) DO UPDATE SET
+    count = EXCLUDED.count,
+    min_value = EXCLUDED.min_value,
+    max_value = EXCLUDED.max_value,
+    avg_value = EXCLUDED.avg_value
+"#;
+
+async fn upsert_buckets<C: ConnectionTrait>(
+    db: &C,
+    table: &str,
+    unit: &str,
+    before: NaiveDateTime,
+    max_id: i32,
+) -> Result<u64, DbErr> {
+    let sql = UPSERT_BUCKETS.replace("{table}", table).replace("{unit}", unit);
+    let res = db
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            [before.into(), max_id.into()],
+        ))
+        .await?;
+    Ok(res.rows_affected())
+}
+
+// Agregat per jam diperbarui untuk setiap jam yang sudah lewat, agregat harian untuk hari
+// yang sudah lewat. Pembacaan baru ditandai rolled_up setelah harinya selesai dihitung.
+async fn roll_up(db: &DatabaseConnection, now: NaiveDateTime, report: &mut RollupReport) -> Result<(), DbErr> {
+    // Batasi ke baris yang sudah ada saat job mulai, pembacaan yang masuk belakangan diproses di putaran berikutnya
+    let max_id: Option<i32> = sensor_history::Entity::find()
+        .select_only()
+        .column_as(sensor_history::Column::Id.max(), "max_id")
+        .into_tuple()
+        .one(db)
+        .await?
+        .flatten();
+    let Some(max_id) = max_id else {
+        return Ok(());
+    };
+
+    let today = start_of_day(now);
+    let txn = db.begin().await?;
+    report.hourly_buckets = upsert_buckets(&txn, "sensor_history_hourly", "hour", start_of_hour(now), max_id).await?;
+    report.daily_buckets = upsert_buckets(&txn, "sensor_history_daily", "day", today, max_id).await?;
+    sensor_history::Entity::update_many()
+        .col_expr(sensor_history::Column::RolledUp, Expr::value(true))
+        .filter(sensor_history::Column::RolledUp.eq(false))
+        .filter(sensor_history::Column::Id.lte(max_id))
+        .filter(sensor_history::Column::RecordedAt.lt(today))
+        .exec(&txn)
+        .await?;
+    txn.commit().await
+}
+
+// Hapus pembacaan mentah & agregat per jam yang melewati masa simpan sensornya.
+// Sensor dengan masa simpan sama dikelompokkan supaya cukup satu query per kelompok.
+async fn apply_retention(
+    db: &DatabaseConnection,
+    config: &RetentionConfig,
+    now: NaiveDateTime,
+    report: &mut RollupReport,
+) -> Result<(), DbErr> {
+    let policies = retention_policy::Entity::find().all(db).await?;
+    // Termasuk sensor di trash: riwayatnya tetap ikut dibersihkan
+    let sensors = sensor::Entity::find().all(db).await?;
+    let defaults = Policy::defaults(config);
+
+    let mut raw: HashMap<NaiveDateTime, Vec<i32>> = HashMap::new();
+    let mut hourly: HashMap<NaiveDateTime, Vec<i32>> = HashMap::new();
+    for s in &sensors {
+        let policy = policy_for(&policies, s.land_id, &s.sensor_type, defaults);
+        if let Some(cutoff) = policy.cutoff(Resolution::Raw, now) {
+            raw.entry(cutoff).or_default().push(s.id);
+        }
+        if let Some(cutoff) = policy.cutoff(Resolution::Hourly, now) {
+            hourly.entry(cutoff).or_default().push(s.id);
+        }
+    }
+
+    for (cutoff, ids) in raw {
+        let res = sensor_history::Entity::delete_many()
+            .filter(sensor_history::Column::SensorId.is_in(ids))
+            .filter(sensor_history::Column::RecordedAt.lt(cutoff))
+            .filter(sensor_history::Column::RolledUp.eq(true))
+            .exec(db)
+            .await?;
+        report.raw_deleted += res.rows_affected;
+    }
+    for (cutoff, ids) in hourly {
+        let res = sensor_history_hourly::Entity::delete_many()
+            .filter(sensor_history_hourly::Column::SensorId.is_in(ids))
+            .filter(sensor_history_hourly::Column::BucketStart.lt(cutoff))
+            .exec(db)
+            .await?;
+        report.hourly_deleted += res.rows_affected;
+    }
+    Ok(())
+}
+
+// Rollup dulu, baru hapus: data mentah tidak pernah hilang sebelum masuk agregat
+pub async fn run(db: &DatabaseConnection, config: &RetentionConfig) -> Result<RollupReport, DbErr> {
+    let now = Local::now().naive_local();
+    let mut report = RollupReport::default();
+    roll_up(db, now, &mut report).await?;
+    apply_retention(db, config, now, &mut report).await?;
+    Ok(report)
+}
+
+// ====================================================
+// QUERY
+// ====================================================
+
+// Satu titik riwayat; untuk agregat, value = rata-rata bucket
+#[derive(Debug, Serialize)]
+pub struct HistoryPoint {
+    pub recorded_at: NaiveDateTime,
+    pub value: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<i32>,
+    // Hanya untuk pembacaan mentah
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anomaly: Option<AnomalyKind>,
+}
+
+// Titik riwayat sensor dalam rentang [from, to), terbaru lebih dulu
+pub async fn history_points(
+    db: &DatabaseConnection,
+    sensor_id: i32,
+    resolution: Resolution,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<Vec<HistoryPoint>, DbErr> {
+    let points = match resolution {
+        Resolution::Raw => sensor_history::Entity::find()
+            .filter(sensor_history::Column::SensorId.eq(sensor_id))
+            .filter(sensor_history::Column::RecordedAt.gte(from))
+            .filter(sensor_history::Column::RecordedAt.lt(to))
+            .order_by_desc(sensor_history::Column::RecordedAt)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|h| HistoryPoint {
+                recorded_at: h.recorded_at,
+                value: h.value,
+                min: None,
+                max: None,
+                count: None,
+                anomaly: h.anomaly,
+            })
+            .collect(),
+        Resolution::Hourly => sensor_history_hourly::Entity::find()
+            .filter(sensor_history_hourly::Column::SensorId.eq(sensor_id))
+            .filter(sensor_history_hourly::Column::BucketStart.gte(bucket_floor(resolution, from)))
+            .filter(sensor_history_hourly::Column::BucketStart.lt(to))
+            .order_by_desc(sensor_history_hourly::Column::BucketStart)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|b| HistoryPoint {
+                recorded_at: b.bucket_start,
+                value: b.avg_value,
+                min: Some(b.min_value),
+                max: Some(b.max_value),
+                count: Some(b.count),
+                anomaly: None,
+            })
+            .collect(),
+        Resolution::Daily => sensor_history_daily::Entity::find()
+            .filter(sensor_history_daily::Column::SensorId.eq(sensor_id))
+            .filter(sensor_history_daily::Column::BucketStart.gte(bucket_floor(resolution, from)))
+            .filter(sensor_history_daily::Column::BucketStart.lt(to))
+            .order_by_desc(sensor_history_daily::Column::BucketStart)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|b| HistoryPoint {
+                recorded_at: b.bucket_start,
+                value: b.avg_value,
+                min: Some(b.min_value),
+                max: Some(b.max_value),
+                count: Some(b.count),
+                anomaly: None,
+            })
+            .collect(),
+    };
+    Ok(points)
+}
@@ -0,0 +1,176 @@
+use chrono::{Local, NaiveDateTime};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect,
+};
+
+use crate::{
+    models::{
+        automation,
+        automation_history::{self, AutomationEvent},
+        pump, valve,
+    },
+    soft_delete::SoftDelete,
+};
+
+// Batas yang sama berlaku untuk pompa maupun katup
+struct Limits<'a> {
+    label: &'a str,
+    name: &'a str,
+    max_runtime_seconds: Option<i32>,
+    max_daily_volume: Option<f64>,
+    cooldown_seconds: Option<i32>,
+}
+
+// Cek semua interlock sebelum automation dijalankan.
+// Mengembalikan Some(alasan) jika eksekusi harus diblokir.
+// Dipanggil di dalam transaksi: baris pompa & katup dikunci (FOR UPDATE) sampai event
+// Triggered tercatat, sehingga dua eksekusi bersamaan tidak bisa sama-sama lolos cooldown
+// atau batas volume harian.
+pub async fn check<C: ConnectionTrait>(
+    db: &C,
+    auto: &automation::Model,
+) -> Result<Option<String>, DbErr> {
+    let now = Local::now().naive_local();
+
+    let Some(p) = pump::Entity::find_active_by_id(auto.pump_id).lock_exclusive().one(db).await? else {
+        return Ok(Some(format!("Pump {} not found", auto.pump_id)));
+    };
+    let Some(v) = valve::Entity::find_active_by_id(auto.valve_id).lock_exclusive().one(db).await? else {
+        return Ok(Some(format!("Valve {} not found", auto.valve_id)));
+    };
+
+    // Pompa tidak boleh jalan jika semua katup di land tertutup
+    let open_valves = valve::Entity::find_active()
+        .filter(valve::Column::LandId.eq(p.land_id))
+        .filter(valve::Column::IsOpen.eq(true))
+        .count(db)
+        .await?;
+    if open_valves == 0 {
+        return Ok(Some(format!(
+            "Pump \"{}\" cannot run while every valve on the land is closed",
+            p.name
+        )));
+    }
+
+    // Automation per zona: katup terbuka di zona lain akan ikut mengalirkan air ke sana
+    if let Some(zone_id) = auto.zone_id
+        && let Some(other) = valve::Entity::find_active()
+            .filter(valve::Column::LandId.eq(p.land_id))
+            .filter(valve::Column::IsOpen.eq(true))
+            .filter(valve::Column::ZoneId.is_not_null())
+            .filter(valve::Column::ZoneId.ne(zone_id))
+            .one(db)
+            .await?
+    {
+        return Ok(Some(format!(
+            "Valve \"{}\" in another zone is open; running would also water that zone",
+            other.name
+        )));
+    }
+
+    // Durasi satu aktivasi dihitung dari debit pompa
+    let runtime_seconds = p.flow_rate.filter(|r| *r > 0.0).map(|r| auto.dispense_amount / r * 60.0);
+
+    let pump_limits = Limits {
+        label: "Pump",
+        name: &p.name,
+        max_runtime_seconds: p.max_runtime_seconds,
+        max_daily_volume: p.max_daily_volume,
+        cooldown_seconds: p.cooldown_seconds,
+    };
+    let pump_automations = automation::Entity::find()
+        .filter(automation::Column::PumpId.eq(p.id))
+        .select_only()
+        .column(automation::Column::Id)
+        .into_tuple::<i32>()
+        .all(db)
+        .await?;
+    if let Some(reason) =
+        check_limits(db, &pump_limits, &pump_automations, auto.dispense_amount, runtime_seconds, now).await?
+    {
+        return Ok(Some(reason));
+    }
+
+    let valve_limits = Limits {
+        label: "Valve",
+        name: &v.name,
+        max_runtime_seconds: v.max_runtime_seconds,
+        max_daily_volume: v.max_daily_volume,
+        cooldown_seconds: v.cooldown_seconds,
+    };
+    let valve_automations = automation::Entity::find()
+        .filter(automation::Column::ValveId.eq(v.id))
+        .select_only()
+        .column(automation::Column::Id)
+        .into_tuple::<i32>()
+        .all(db)
+        .await?;
+    check_limits(db, &valve_limits, &valve_automations, auto.dispense_amount, runtime_seconds, now).await
+}
+
+async fn check_limits<C: ConnectionTrait>(
+    db: &C,
+    limits: &Limits<'_>,
+    automation_ids: &[i32],
+    volume: f64,
+    runtime_seconds: Option<f64>,
+    now: NaiveDateTime,
+) -> Result<Option<String>, DbErr> {
+    if let Some(max) = limits.max_runtime_seconds {
+        match runtime_seconds {
+            Some(rt) if rt > max as f64 => {
+                return Ok(Some(format!(
+                    "{} \"{}\" would run {:.0}s, exceeding max runtime of {}s",
+                    limits.label, limits.name, rt, max
+                )));
+            }
+            Some(_) => {}
+            None => {
+                return Ok(Some(format!(
+                    "{} \"{}\" has a max runtime but the pump flow rate is unknown",
+                    limits.label, limits.name
+                )));
+            }
+        }
+    }
+
+    if let Some(cooldown) = limits.cooldown_seconds {
+        let last_run = automation_history::Entity::find()
+            .filter(automation_history::Column::AutomationId.is_in(automation_ids.to_vec()))
+            .filter(automation_history::Column::Event.eq(AutomationEvent::Triggered))
+            .order_by_desc(automation_history::Column::TriggeredAt)
+            .one(db)
+            .await?;
+        if let Some(last) = last_run {
+            let elapsed = (now - last.triggered_at).num_seconds();
+            if elapsed < cooldown as i64 {
+                return Ok(Some(format!(
+                    "{} \"{}\" is cooling down ({}s of {}s elapsed)",
+                    limits.label, limits.name, elapsed, cooldown
+                )));
+            }
+        }
+    }
+
+    if let Some(max) = limits.max_daily_volume {
+        let start_of_day = now.date().and_hms_opt(0, 0, 0).unwrap_or(now);
+        let used: f64 = automation_history::Entity::find()
+            .filter(automation_history::Column::AutomationId.is_in(automation_ids.to_vec()))
+            .filter(automation_history::Column::Event.eq(AutomationEvent::Triggered))
+            .filter(automation_history::Column::TriggeredAt.gte(start_of_day))
+            .all(db)
+            .await?
+            .iter()
+            .filter_map(|h| h.volume)
+            .sum();
+        if used + volume > max {
+            return Ok(Some(format!(
+                "{} \"{}\" would exceed its daily volume limit ({:.2} + {:.2} > {:.2})",
+                limits.label, limits.name, used, volume, max
+            )));
+        }
+    }
+
+    Ok(None)
+}
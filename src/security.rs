@@ -0,0 +1,107 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rand::{distr::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+
+use crate::{config::PasswordPolicy, validation::FieldError};
+
+// Token acak alfanumerik (session key, refresh token, dsb.)
+pub fn random_token(len: usize) -> String {
+    rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+// Token rahasia hanya disimpan dalam bentuk hash di database
+pub fn sha256_hex(value: &str) -> String {
+    format!("{:x}", Sha256::digest(value.as_bytes()))
+}
+
+// Password yang terlalu umum selalu ditolak, apa pun konfigurasinya
+const COMMON_PASSWORDS: &[&str] = &[
+    "1234", "12345", "123456", "12345678", "123456789", "password", "password1",
+    "qwerty", "qwerty123", "admin", "admin123", "letmein", "welcome", "iloveyou",
+];
+
+impl PasswordPolicy {
+    // Kembalikan daftar pelanggaran (kosong = password valid)
+    pub fn check(&self, field: &str, password: &str, username: &str) -> Vec<FieldError> {
+        let mut problems = Vec::new();
+        let mut fail = |message: String| problems.push(FieldError { field: field.to_string(), message });
+
+        if password.chars().count() < self.min_length {
+            fail(format!("Password must be at least {} characters", self.min_length));
+        }
+        if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            fail("Password must contain an uppercase letter".to_string());
+        }
+        if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            fail("Password must contain a lowercase letter".to_string());
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            fail("Password must contain a digit".to_string());
+        }
+        if self.require_symbol && password.chars().all(|c| c.is_alphanumeric()) {
+            fail("Password must contain a symbol".to_string());
+        }
+        if COMMON_PASSWORDS.contains(&password.to_lowercase().as_str()) {
+            fail("Password is too common".to_string());
+        }
+        if !username.is_empty() && password.to_lowercase().contains(&username.to_lowercase()) {
+            fail("Password must not contain the username".to_string());
+        }
+
+        problems
+    }
+}
+
+// Sliding-window limiter sederhana di memori, dipakai untuk /login
+pub struct LoginThrottle {
+    max_attempts: usize,
+    window: Duration,
+    attempts: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl LoginThrottle {
+    pub fn new(max_attempts: usize, window_seconds: u64) -> Self {
+        Self {
+            max_attempts,
+            window: Duration::from_secs(window_seconds),
+            attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Catat satu percobaan untuk setiap key.
+    // Err(detik) berisi waktu tunggu jika salah satu key sudah melewati batas.
+    pub fn hit(&self, keys: &[String]) -> Result<(), u64> {
+        let now = Instant::now();
+        let mut attempts = self.attempts.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Buang catatan yang sudah keluar dari window agar map tidak terus membesar
+        attempts.retain(|_, q| {
+            while q.front().is_some_and(|t| now.duration_since(*t) >= self.window) {
+                q.pop_front();
+            }
+            !q.is_empty()
+        });
+
+        let mut retry_after = 0;
+        for key in keys {
+            let q = attempts.entry(key.clone()).or_default();
+            if q.len() >= self.max_attempts {
+                if let Some(oldest) = q.front() {
+                    let wait = self.window.saturating_sub(now.duration_since(*oldest));
+                    retry_after = retry_after.max(wait.as_secs().max(1));
+                }
+                continue;
+            }
+            q.push_back(now);
+        }
+
+        if retry_after > 0 { Err(retry_after) } else { Ok(()) }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::models::{user, user_role, notification, land, sensor, sensor_history, plant, valve, pump, automation, automation_history, seed, recommendation, pest_control};
+use crate::models::{user, user_role, organization, organization_member, notification, land, sensor, sensor_history, plant, valve, pump, automation, automation_history, seed, recommendation, pest_control};
 use crate::models::sensor::SensorType;
 use bcrypt::{DEFAULT_COST, hash};
 use chrono::Local;
@@ -6,39 +6,69 @@ use sea_orm::{
     ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
 };
 use crate::models::recommendation::RecommendationType;
-use crate::models::automation::AutomationType;
+use crate::models::automation::{AutomationType, WeatherMode};
+use crate::models::automation_history::AutomationEvent;
 use crate::models::pest_control::PestControlStatus;
+use crate::models::user::UserStatus;
+use crate::models::organization_member::OrgRole;
+use crate::permissions::{self, *};
+use crate::weather;
 
-pub async fn seed_db(db: &DatabaseConnection) -> Result<(), DbErr> {
+// Mengembalikan role beserta penanda apakah role baru dibuat di pemanggilan ini
+async fn find_or_create_role(db: &DatabaseConnection, name: &str) -> Result<(user_role::Model, bool), DbErr> {
+    match user_role::Entity::find().filter(user_role::Column::Name.eq(name)).one(db).await? {
+        Some(r) => Ok((r, false)),
+        None => Ok((user_role::ActiveModel { name: Set(name.to_owned()), ..Default::default() }.insert(db).await?, true)),
+    }
+}
+
+// `password` dipakai semua akun contoh; sudah dicek terhadap kebijakan password oleh pemanggil
+pub async fn seed_db(db: &DatabaseConnection, password: &str) -> Result<(), DbErr> {
     // --- ROLES ---
-    let admin_role = match user_role::Entity::find().filter(user_role::Column::Name.eq("admin")).one(db).await? {
-        Some(r) => r,
-        None => user_role::ActiveModel { name: Set("admin".to_owned()), ..Default::default() }.insert(db).await?,
-    };
+    let (admin_role, admin_created) = find_or_create_role(db, "admin").await?;
+    let (farmer_role, farmer_created) = find_or_create_role(db, "farmer").await?;
+    let (consultant_role, consultant_created) = find_or_create_role(db, "consultant").await?;
 
-    let farmer_role = match user_role::Entity::find().filter(user_role::Column::Name.eq("farmer")).one(db).await? {
-        Some(r) => r,
-        None => user_role::ActiveModel { name: Set("farmer".to_owned()), ..Default::default() }.insert(db).await?,
-    };
+    // --- PERMISSIONS PER ROLE ---
+    let admin_perms: Vec<String> = permissions::ALL.iter().map(|(name, _)| name.to_string()).collect();
+    let farmer_perms = [LAND_WRITE, AUTOMATION_WRITE, AUTOMATION_TRIGGER, SENSOR_INGEST, PEST_CONTROL_WRITE, TASK_WRITE].map(String::from);
+    let consultant_perms = [RECOMMENDATION_WRITE, SEED_MANAGE].map(String::from);
 
-    let consultant_role = match user_role::Entity::find().filter(user_role::Column::Name.eq("consultant")).one(db).await? {
-        Some(r) => r,
-        None => user_role::ActiveModel { name: Set("consultant".to_owned()), ..Default::default() }.insert(db).await?,
-    };
+    // Hanya untuk role yang baru dibuat: permission role yang sudah ada mungkin sudah diubah lewat API
+    for (role, created, perms) in [
+        (&admin_role, admin_created, &admin_perms[..]),
+        (&farmer_role, farmer_created, &farmer_perms[..]),
+        (&consultant_role, consultant_created, &consultant_perms[..]),
+    ] {
+        if !created {
+            continue;
+        }
+        if let Err(unknown) = permissions::set_role_permissions(db, role.id, perms).await? {
+            println!("Unknown permissions for role {}: {:?}", role.name, unknown);
+        }
+    }
 
-    let password = "1234";
     let hashed_password = hash(password, DEFAULT_COST).expect("Failed to hash password");
 
     // --- USERS ---
     let admin_username = "miracleandsleeper";
-    if user::Entity::find().filter(user::Column::Username.eq(admin_username)).one(db).await?.is_none() {
-        user::ActiveModel {
+    let admin = match user::Entity::find().filter(user::Column::Username.eq(admin_username)).one(db).await? {
+        Some(u) => u,
+        None => user::ActiveModel {
             username: Set(admin_username.to_owned()),
             password: Set(hashed_password.clone()),
             user_role_id: Set(admin_role.id),
+            failed_login_attempts: Set(0),
+            locked_until: Set(None),
+            full_name: Set(Some("Administrator".to_owned())),
+            phone: Set(None),
+            email: Set(None),
+            language: Set("id".to_owned()),
+            timezone: Set("Asia/Jakarta".to_owned()),
+            status: Set(UserStatus::Active),
             ..Default::default()
-        }.insert(db).await?;
-    }
+        }.insert(db).await?,
+    };
 
     let farmer_username = "november rain";
     let farmer = match user::Entity::find().filter(user::Column::Username.eq(farmer_username)).one(db).await? {
@@ -48,6 +78,14 @@ pub async fn seed_db(db: &DatabaseConnection) -> Result<(), DbErr> {
                 username: Set(farmer_username.to_owned()),
                 password: Set(hashed_password.clone()),
                 user_role_id: Set(farmer_role.id),
+                failed_login_attempts: Set(0),
+                locked_until: Set(None),
+                full_name: Set(None),
+                phone: Set(None),
+                email: Set(None),
+                language: Set("id".to_owned()),
+                timezone: Set("Asia/Jakarta".to_owned()),
+                status: Set(UserStatus::Active),
                 ..Default::default()
             };
             new_farmer.insert(db).await?
@@ -55,19 +93,54 @@ pub async fn seed_db(db: &DatabaseConnection) -> Result<(), DbErr> {
     };
 
     let consultant_username = "imagine";
-    let _consultant = match user::Entity::find().filter(user::Column::Username.eq(consultant_username)).one(db).await? {
+    let consultant = match user::Entity::find().filter(user::Column::Username.eq(consultant_username)).one(db).await? {
         Some(u) => u,
         None => {
             let new_consultant = user::ActiveModel {
                 username: Set(consultant_username.to_owned()),
                 password: Set(hashed_password.clone()),
                 user_role_id: Set(consultant_role.id),
+                failed_login_attempts: Set(0),
+                locked_until: Set(None),
+                full_name: Set(None),
+                phone: Set(None),
+                email: Set(None),
+                language: Set("id".to_owned()),
+                timezone: Set("Asia/Jakarta".to_owned()),
+                status: Set(UserStatus::Active),
                 ..Default::default()
             };
             new_consultant.insert(db).await?
         }
     };
 
+    // --- ORGANIZATION (Pastikan dibuat SEBELUM Land) ---
+    let org_name = "koperasi tani";
+    let org = match organization::Entity::find().filter(organization::Column::Name.eq(org_name)).one(db).await? {
+        Some(o) => o,
+        None => organization::ActiveModel {
+            name: Set(org_name.to_owned()),
+            created_at: Set(Local::now().naive_local()),
+            ..Default::default()
+        }.insert(db).await?,
+    };
+
+    for (member, role) in [(&admin, OrgRole::Owner), (&farmer, OrgRole::Member), (&consultant, OrgRole::Viewer)] {
+        let exists = organization_member::Entity::find()
+            .filter(organization_member::Column::OrganizationId.eq(org.id))
+            .filter(organization_member::Column::UserId.eq(member.id))
+            .one(db)
+            .await?;
+        if exists.is_none() {
+            organization_member::ActiveModel {
+                organization_id: Set(org.id),
+                user_id: Set(member.id),
+                role: Set(role),
+                ..Default::default()
+            }.insert(db).await?;
+        }
+    }
+
     // --- SEED (Pastikan Seed dibuat SEBELUM Plant) ---
     let seed_name = "canon rock";
     let seed = match seed::Entity::find()
@@ -99,6 +172,7 @@ pub async fn seed_db(db: &DatabaseConnection) -> Result<(), DbErr> {
                 location_name: Set(land_name.to_owned()),
                 size: Set(2.5),
                 user_id: Set(farmer.id),
+                organization_id: Set(org.id),
                 ..Default::default()
             };
             new_land.insert(db).await?
@@ -135,6 +209,7 @@ pub async fn seed_db(db: &DatabaseConnection) -> Result<(), DbErr> {
             sensor_id: Set(sensor.id),
             value: Set(28.5),
             recorded_at: Set(Local::now().naive_local()),
+            rolled_up: Set(false),
             ..Default::default()
         }.insert(db).await?;
     }
@@ -174,6 +249,10 @@ pub async fn seed_db(db: &DatabaseConnection) -> Result<(), DbErr> {
             let new_valve = valve::ActiveModel {
                 name: Set(valve_name.to_owned()),
                 land_id: Set(land.id),
+                is_open: Set(true),
+                max_runtime_seconds: Set(None),
+                max_daily_volume: Set(None),
+                cooldown_seconds: Set(None),
                 ..Default::default()
             };
             new_valve.insert(db).await?
@@ -193,6 +272,10 @@ pub async fn seed_db(db: &DatabaseConnection) -> Result<(), DbErr> {
             let new_pump = pump::ActiveModel {
                 name: Set(pump_name.to_owned()),
                 land_id: Set(land.id),
+                flow_rate: Set(Some(10.0)),
+                max_runtime_seconds: Set(Some(600)),
+                max_daily_volume: Set(Some(100.0)),
+                cooldown_seconds: Set(Some(1800)),
                 ..Default::default()
             };
             new_pump.insert(db).await?
@@ -218,6 +301,12 @@ pub async fn seed_db(db: &DatabaseConnection) -> Result<(), DbErr> {
                 pump_id: Set(pump.id),   
                 valve_id: Set(valve.id), 
                 dispense_amount: Set(5.0), 
+                enabled: Set(true),
+                paused_until: Set(None),
+                weather_mode: Set(WeatherMode::Skip),
+                rain_threshold_mm: Set(Some(10.0)),
+                rain_lookback_hours: Set(weather::DEFAULT_LOOKBACK_HOURS),
+                rain_forecast_hours: Set(weather::DEFAULT_FORECAST_HOURS),
                 ..Default::default()
             };
             new_automation.insert(db).await?
@@ -233,6 +322,10 @@ pub async fn seed_db(db: &DatabaseConnection) -> Result<(), DbErr> {
     if automation_history_exists.is_none() {
         automation_history::ActiveModel {
             automation_id: Set(automation.id),
+            event: Set(AutomationEvent::Triggered),
+            user_id: Set(None),
+            note: Set(None),
+            volume: Set(Some(automation.dispense_amount)),
             triggered_at: Set(Local::now().naive_local()),
             ..Default::default()
         }
@@ -240,45 +333,53 @@ pub async fn seed_db(db: &DatabaseConnection) -> Result<(), DbErr> {
         .await?;
     }
 
-    let rec_watering = recommendation::ActiveModel {
-        name: Set("Always with me".to_owned()),
-        description: Set("idk".to_owned()),
-        rec_type: Set(RecommendationType::Watering),
-        seed_id: Set(seed.id), // Pakai ID Corn
-        ..Default::default()
-    };
-    recommendation::Entity::insert(rec_watering).exec(db).await?;
+    // Database tidak dikosongkan setiap start, jadi data contoh di bawah cukup dibuat sekali
+    let recommendation_exists = recommendation::Entity::find()
+        .filter(recommendation::Column::SeedId.eq(seed.id))
+        .one(db)
+        .await?;
 
-    let rec_fertilizer = recommendation::ActiveModel {
-        name: Set("One summer day".to_owned()),
-        description: Set("idk".to_owned()),
-        rec_type: Set(RecommendationType::Fertilization),
-        seed_id: Set(seed.id),
-        ..Default::default()
-    };
-    recommendation::Entity::insert(rec_fertilizer).exec(db).await?;
+    if recommendation_exists.is_none() {
+        let rec_watering = recommendation::ActiveModel {
+            name: Set("Always with me".to_owned()),
+            description: Set("idk".to_owned()),
+            rec_type: Set(RecommendationType::Watering),
+            seed_id: Set(seed.id), // Pakai ID Corn
+            ..Default::default()
+        };
+        recommendation::Entity::insert(rec_watering).exec(db).await?;
 
-    let rec_pest = recommendation::ActiveModel {
-        name: Set("Is there still anything".to_owned()),
-        description: Set("idk".to_owned()),
-        rec_type: Set(RecommendationType::PestControl),
-        seed_id: Set(seed.id),
-        ..Default::default()
-    };
-    recommendation::Entity::insert(rec_pest).exec(db).await?;
+        let rec_fertilizer = recommendation::ActiveModel {
+            name: Set("One summer day".to_owned()),
+            description: Set("idk".to_owned()),
+            rec_type: Set(RecommendationType::Fertilization),
+            seed_id: Set(seed.id),
+            ..Default::default()
+        };
+        recommendation::Entity::insert(rec_fertilizer).exec(db).await?;
+
+        let rec_pest = recommendation::ActiveModel {
+            name: Set("Is there still anything".to_owned()),
+            description: Set("idk".to_owned()),
+            rec_type: Set(RecommendationType::PestControl),
+            seed_id: Set(seed.id),
+            ..Default::default()
+        };
+        recommendation::Entity::insert(rec_pest).exec(db).await?;
 
-    pest_control::ActiveModel {
-        name: Set("come sweet death".to_owned()),
-        status: Set(PestControlStatus::NoAction),
-        land_id: Set(land.id),
-        ..Default::default()
-    }.insert(db).await?;
+        pest_control::ActiveModel {
+            name: Set("come sweet death".to_owned()),
+            status: Set(PestControlStatus::NoAction),
+            land_id: Set(land.id),
+            ..Default::default()
+        }.insert(db).await?;
 
-    notification::ActiveModel {
-        user_id: Set(farmer.id),
-        description: Set("coat i would buy".to_owned()),
-        ..Default::default()
-    }.insert(db).await?;
+        notification::ActiveModel {
+            user_id: Set(farmer.id),
+            description: Set("coat i would buy".to_owned()),
+            ..Default::default()
+        }.insert(db).await?;
+    }
 
     println!("Database seeding complete.");
     Ok(())
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use actix_session::storage::{LoadError, SaveError, SessionKey, SessionStore, UpdateError};
+use actix_web::cookie::time::Duration as CookieDuration;
+use chrono::{Duration, Local, NaiveDateTime};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+};
+
+use crate::{
+    models::user_session,
+    security::{random_token, sha256_hex as hash_key},
+};
+
+type SessionState = HashMap<String, String>;
+
+// Session disimpan di database agar bisa dicabut dari server (logout, ganti password, hapus user)
+#[derive(Clone)]
+pub struct DbSessionStore {
+    db: DatabaseConnection,
+}
+
+impl DbSessionStore {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+}
+
+// Cabut semua session milik user, mengembalikan jumlah session yang dihapus
+pub async fn revoke_user_sessions(db: &DatabaseConnection, user_id: i32) -> Result<u64, DbErr> {
+    let res = user_session::Entity::delete_many()
+        .filter(user_session::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected)
+}
+
+fn generate_key() -> Result<SessionKey, anyhow::Error> {
+    SessionKey::try_from(random_token(64)).map_err(anyhow::Error::new)
+}
+
+fn expiry(now: NaiveDateTime, ttl: &CookieDuration) -> NaiveDateTime {
+    now + Duration::seconds(ttl.whole_seconds())
+}
+
+// Nilai di session state disimpan sebagai JSON oleh actix-session
+fn state_value<T: serde::de::DeserializeOwned>(state: &SessionState, key: &str) -> Option<T> {
+    state.get(key).and_then(|v| serde_json::from_str(v).ok())
+}
+
+async fn find_by_key(db: &DatabaseConnection, key: &SessionKey) -> Result<Option<user_session::Model>, DbErr> {
+    user_session::Entity::find()
+        .filter(user_session::Column::SessionKeyHash.eq(hash_key(key.as_ref())))
+        .one(db)
+        .await
+}
+
+impl SessionStore for DbSessionStore {
+    async fn load(&self, session_key: &SessionKey) -> Result<Option<SessionState>, LoadError> {
+        let Some(row) = find_by_key(&self.db, session_key).await.map_err(|e| LoadError::Other(e.into()))? else {
+            return Ok(None);
+        };
+
+        let now = Local::now().naive_local();
+        if row.expires_at <= now {
+            user_session::Entity::delete_by_id(row.id)
+                .exec(&self.db)
+                .await
+                .map_err(|e| LoadError::Other(e.into()))?;
+            return Ok(None);
+        }
+
+        let state = serde_json::from_str(&row.state).map_err(|e| LoadError::Deserialization(e.into()))?;
+
+        // Cukup perbarui last_seen sekali per menit agar tidak menulis di setiap request
+        if now - row.last_seen_at >= Duration::minutes(1) {
+            let mut active: user_session::ActiveModel = row.into();
+            active.last_seen_at = Set(now);
+            active.update(&self.db).await.map_err(|e| LoadError::Other(e.into()))?;
+        }
+
+        Ok(Some(state))
+    }
+
+    async fn save(&self, session_state: SessionState, ttl: &CookieDuration) -> Result<SessionKey, SaveError> {
+        let key = generate_key().map_err(SaveError::Other)?;
+        let state = serde_json::to_string(&session_state).map_err(|e| SaveError::Serialization(e.into()))?;
+        let now = Local::now().naive_local();
+
+        // Sekalian bersihkan session yang sudah kedaluwarsa
+        user_session::Entity::delete_many()
+            .filter(user_session::Column::ExpiresAt.lte(now))
+            .exec(&self.db)
+            .await
+            .map_err(|e| SaveError::Other(e.into()))?;
+
+        user_session::ActiveModel {
+            session_key_hash: Set(hash_key(key.as_ref())),
+            user_id: Set(state_value(&session_state, "user_id")),
+            state: Set(state),
+            ip: Set(state_value(&session_state, "ip")),
+            user_agent: Set(state_value(&session_state, "user_agent")),
+            created_at: Set(now),
+            last_seen_at: Set(now),
+            expires_at: Set(expiry(now, ttl)),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await
+        .map_err(|e| SaveError::Other(e.into()))?;
+
+        Ok(key)
+    }
+
+    async fn update(
+        &self,
+        session_key: SessionKey,
+        session_state: SessionState,
+        ttl: &CookieDuration,
+    ) -> Result<SessionKey, UpdateError> {
+        let row = find_by_key(&self.db, &session_key).await.map_err(|e| UpdateError::Other(e.into()))?;
+        let now = Local::now().naive_local();
+
+        // Session dicabut (logout, ganti password, hapus user) atau kedaluwarsa di tengah request:
+        // jangan dibuat ulang, karena itu sama saja membatalkan pencabutannya
+        let Some(row) = row.filter(|r| r.expires_at > now) else {
+            return Err(UpdateError::Other(anyhow::anyhow!("Session has been revoked")));
+        };
+
+        let state = serde_json::to_string(&session_state).map_err(|e| UpdateError::Serialization(e.into()))?;
+
+        let mut active: user_session::ActiveModel = row.into();
+        active.user_id = Set(state_value(&session_state, "user_id"));
+        active.state = Set(state);
+        active.ip = Set(state_value(&session_state, "ip"));
+        active.user_agent = Set(state_value(&session_state, "user_agent"));
+        active.last_seen_at = Set(now);
+        active.expires_at = Set(expiry(now, ttl));
+        active.update(&self.db).await.map_err(|e| UpdateError::Other(e.into()))?;
+
+        Ok(session_key)
+    }
+
+    async fn update_ttl(&self, session_key: &SessionKey, ttl: &CookieDuration) -> Result<(), anyhow::Error> {
+        if let Some(row) = find_by_key(&self.db, session_key).await? {
+            let now = Local::now().naive_local();
+            let mut active: user_session::ActiveModel = row.into();
+            active.last_seen_at = Set(now);
+            active.expires_at = Set(expiry(now, ttl));
+            active.update(&self.db).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, session_key: &SessionKey) -> Result<(), anyhow::Error> {
+        user_session::Entity::delete_many()
+            .filter(user_session::Column::SessionKeyHash.eq(hash_key(session_key.as_ref())))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,470 @@
+use std::collections::HashSet;
+
+use chrono::{Duration, Local, NaiveDateTime, SubsecRound};
+use sea_orm::{
+    sea_query::{Expr, IntoCondition},
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, PrimaryKeyTrait,
+    QueryFilter, QuerySelect, Select,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    audit,
+    extractors::AuthUser,
+    models::{audit_log::AuditAction, automation, derived_sensor, harvest, land, plant, pump, sensor, user, valve, zone},
+};
+
+// Entitas yang dihapus dengan mengisi `deleted_at`, bukan menghapus barisnya.
+// Query biasa memakai `find_active*` sehingga baris yang terhapus tidak ikut.
+pub trait SoftDelete: EntityTrait {
+    const ID: Self::Column;
+    const DELETED_AT: Self::Column;
+
+    fn find_active() -> Select<Self> {
+        Self::find().filter(Self::DELETED_AT.is_null())
+    }
+
+    fn find_active_by_id(id: i32) -> Select<Self>
+    where
+        <Self::PrimaryKey as PrimaryKeyTrait>::ValueType: From<i32>,
+    {
+        Self::find_by_id(id).filter(Self::DELETED_AT.is_null())
+    }
+
+    fn find_deleted() -> Select<Self> {
+        Self::find().filter(Self::DELETED_AT.is_not_null())
+    }
+}
+
+impl SoftDelete for land::Entity {
+    const ID: land::Column = land::Column::Id;
+    const DELETED_AT: land::Column = land::Column::DeletedAt;
+}
+
+impl SoftDelete for sensor::Entity {
+    const ID: sensor::Column = sensor::Column::Id;
+    const DELETED_AT: sensor::Column = sensor::Column::DeletedAt;
+}
+
+impl SoftDelete for plant::Entity {
+    const ID: plant::Column = plant::Column::Id;
+    const DELETED_AT: plant::Column = plant::Column::DeletedAt;
+}
+
+impl SoftDelete for pump::Entity {
+    const ID: pump::Column = pump::Column::Id;
+    const DELETED_AT: pump::Column = pump::Column::DeletedAt;
+}
+
+impl SoftDelete for valve::Entity {
+    const ID: valve::Column = valve::Column::Id;
+    const DELETED_AT: valve::Column = valve::Column::DeletedAt;
+}
+
+impl SoftDelete for zone::Entity {
+    const ID: zone::Column = zone::Column::Id;
+    const DELETED_AT: zone::Column = zone::Column::DeletedAt;
+}
+
+// User tidak masuk trash & tidak pernah di-purge; hanya dipakai untuk menyaring akun yang dihapus
+impl SoftDelete for user::Entity {
+    const ID: user::Column = user::Column::Id;
+    const DELETED_AT: user::Column = user::Column::DeletedAt;
+}
+
+impl SoftDelete for automation::Entity {
+    const ID: automation::Column = automation::Column::Id;
+    const DELETED_AT: automation::Column = automation::Column::DeletedAt;
+}
+
+// Waktu penghapusan dibulatkan ke detik supaya nilai yang dibaca ulang dari database
+// sama persis dengan yang dipakai saat menandai baris-baris turunannya.
+pub fn now() -> NaiveDateTime {
+    Local::now().naive_local().trunc_subsecs(0)
+}
+
+// Ubah deleted_at baris yang cocok dari `from` (None = masih aktif) menjadi `to`
+async fn mark<E: SoftDelete, C: ConnectionTrait>(
+    db: &C,
+    condition: impl IntoCondition,
+    from: Option<NaiveDateTime>,
+    to: Option<NaiveDateTime>,
+) -> Result<u64, DbErr> {
+    let current = match from {
+        Some(at) => E::DELETED_AT.eq(at),
+        None => E::DELETED_AT.is_null(),
+    };
+    let res = E::update_many()
+        .col_expr(E::DELETED_AT, Expr::value(to))
+        .filter(condition)
+        .filter(current)
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected)
+}
+
+// ====================================================
+// DELETE: turunan ikut ditandai dengan timestamp yang sama, sehingga restore
+// hanya mengembalikan yang terhapus bersamanya (bukan yang sudah dihapus sebelumnya).
+// ====================================================
+
+pub async fn delete_land<C: ConnectionTrait>(db: &C, land_id: i32, at: NaiveDateTime) -> Result<(), DbErr> {
+    mark::<land::Entity, _>(db, land::Column::Id.eq(land_id), None, Some(at)).await?;
+    mark::<automation::Entity, _>(db, automation::Column::LandId.eq(land_id), None, Some(at)).await?;
+    mark::<sensor::Entity, _>(db, sensor::Column::LandId.eq(land_id), None, Some(at)).await?;
+    mark::<plant::Entity, _>(db, plant::Column::LandId.eq(land_id), None, Some(at)).await?;
+    mark::<pump::Entity, _>(db, pump::Column::LandId.eq(land_id), None, Some(at)).await?;
+    mark::<valve::Entity, _>(db, valve::Column::LandId.eq(land_id), None, Some(at)).await?;
+    mark::<zone::Entity, _>(db, zone::Column::LandId.eq(land_id), None, Some(at)).await?;
+    Ok(())
+}
+
+// Sensor, pompa & katup: automation yang memakainya ikut terhapus
+pub async fn delete_device<E: SoftDelete, C: ConnectionTrait>(
+    db: &C,
+    id: i32,
+    automation_column: automation::Column,
+    at: NaiveDateTime,
+) -> Result<(), DbErr> {
+    mark::<E, _>(db, E::ID.eq(id), None, Some(at)).await?;
+    mark::<automation::Entity, _>(db, automation_column.eq(id), None, Some(at)).await?;
+    Ok(())
+}
+
+pub async fn delete<E: SoftDelete, C: ConnectionTrait>(db: &C, id: i32, at: NaiveDateTime) -> Result<(), DbErr> {
+    mark::<E, _>(db, E::ID.eq(id), None, Some(at)).await?;
+    Ok(())
+}
+
+// Sensor virtual yang dihitung dari sensor ini ikut terhapus agar tidak tertinggal tanpa sumber
+pub async fn delete_sensor<C: ConnectionTrait>(db: &C, id: i32, at: NaiveDateTime) -> Result<(), DbErr> {
+    delete_device::<sensor::Entity, _>(db, id, automation::Column::SensorId, at).await?;
+    for virtual_id in derived_from(db, sensor_source(id)).await? {
+        delete_device::<sensor::Entity, _>(db, virtual_id, automation::Column::SensorId, at).await?;
+    }
+    Ok(())
+}
+
+// Sama untuk tanaman: sensor GDD-nya ikut terhapus
+pub async fn delete_plant<C: ConnectionTrait>(db: &C, id: i32, at: NaiveDateTime) -> Result<(), DbErr> {
+    mark::<plant::Entity, _>(db, plant::Column::Id.eq(id), None, Some(at)).await?;
+    for virtual_id in derived_from(db, derived_sensor::Column::PlantId.eq(id).into_condition()).await? {
+        delete_device::<sensor::Entity, _>(db, virtual_id, automation::Column::SensorId, at).await?;
+    }
+    Ok(())
+}
+
+fn sensor_source(id: i32) -> Condition {
+    Condition::any()
+        .add(derived_sensor::Column::TemperatureSensorId.eq(id))
+        .add(derived_sensor::Column::HumiditySensorId.eq(id))
+}
+
+// Id sensor virtual yang definisinya cocok dengan `condition`
+async fn derived_from<C: ConnectionTrait>(db: &C, condition: Condition) -> Result<Vec<i32>, DbErr> {
+    derived_sensor::Entity::find()
+        .filter(condition)
+        .select_only()
+        .column(derived_sensor::Column::SensorId)
+        .into_tuple()
+        .all(db)
+        .await
+}
+
+// ====================================================
+// RESTORE: pemanggil memastikan induknya (land) sudah aktif
+// ====================================================
+
+pub async fn restore_land<C: ConnectionTrait>(db: &C, land_id: i32, at: NaiveDateTime) -> Result<(), DbErr> {
+    mark::<land::Entity, _>(db, land::Column::Id.eq(land_id), Some(at), None).await?;
+    mark::<sensor::Entity, _>(db, sensor::Column::LandId.eq(land_id), Some(at), None).await?;
+    mark::<plant::Entity, _>(db, plant::Column::LandId.eq(land_id), Some(at), None).await?;
+    mark::<pump::Entity, _>(db, pump::Column::LandId.eq(land_id), Some(at), None).await?;
+    mark::<valve::Entity, _>(db, valve::Column::LandId.eq(land_id), Some(at), None).await?;
+    mark::<zone::Entity, _>(db, zone::Column::LandId.eq(land_id), Some(at), None).await?;
+    restore_automations(db, automation::Column::LandId.eq(land_id), at).await
+}
+
+pub async fn restore_device<E: SoftDelete, C: ConnectionTrait>(
+    db: &C,
+    id: i32,
+    automation_column: automation::Column,
+    at: NaiveDateTime,
+) -> Result<(), DbErr> {
+    mark::<E, _>(db, E::ID.eq(id), Some(at), None).await?;
+    restore_automations(db, automation_column.eq(id), at).await
+}
+
+pub async fn restore<E: SoftDelete, C: ConnectionTrait>(db: &C, id: i32, at: NaiveDateTime) -> Result<(), DbErr> {
+    mark::<E, _>(db, E::ID.eq(id), Some(at), None).await?;
+    Ok(())
+}
+
+// Sensor virtual yang terhapus bersamanya ikut kembali jika semua sumbernya sudah aktif
+pub async fn restore_sensor<C: ConnectionTrait>(db: &C, id: i32, at: NaiveDateTime) -> Result<(), DbErr> {
+    restore_device::<sensor::Entity, _>(db, id, automation::Column::SensorId, at).await?;
+    restore_derived(db, sensor_source(id), at).await
+}
+
+pub async fn restore_plant<C: ConnectionTrait>(db: &C, id: i32, at: NaiveDateTime) -> Result<(), DbErr> {
+    mark::<plant::Entity, _>(db, plant::Column::Id.eq(id), Some(at), None).await?;
+    restore_derived(db, derived_sensor::Column::PlantId.eq(id).into_condition(), at).await
+}
+
+async fn restore_derived<C: ConnectionTrait>(db: &C, condition: Condition, at: NaiveDateTime) -> Result<(), DbErr> {
+    for virtual_id in derived_from(db, condition).await? {
+        if missing_derived_sources(db, virtual_id).await?.is_empty() {
+            restore_device::<sensor::Entity, _>(db, virtual_id, automation::Column::SensorId, at).await?;
+        }
+    }
+    Ok(())
+}
+
+// Sumber sensor virtual yang masih terhapus, mis. ["temperature sensor"]. Kosong untuk sensor biasa.
+pub async fn missing_derived_sources<C: ConnectionTrait>(db: &C, sensor_id: i32) -> Result<Vec<&'static str>, DbErr> {
+    let mut missing = Vec::new();
+    let Some(def) = derived_sensor::Entity::find()
+        .filter(derived_sensor::Column::SensorId.eq(sensor_id))
+        .one(db)
+        .await?
+    else {
+        return Ok(missing);
+    };
+    if sensor::Entity::find_active_by_id(def.temperature_sensor_id).one(db).await?.is_none() {
+        missing.push("temperature sensor");
+    }
+    if let Some(humidity_id) = def.humidity_sensor_id
+        && sensor::Entity::find_active_by_id(humidity_id).one(db).await?.is_none()
+    {
+        missing.push("humidity sensor");
+    }
+    if let Some(plant_id) = def.plant_id
+        && plant::Entity::find_active_by_id(plant_id).one(db).await?.is_none()
+    {
+        missing.push("plant");
+    }
+    Ok(missing)
+}
+
+// Automation hanya dikembalikan jika sensor, pompa & katupnya juga sudah aktif
+async fn restore_automations<C: ConnectionTrait>(
+    db: &C,
+    condition: impl IntoCondition,
+    at: NaiveDateTime,
+) -> Result<(), DbErr> {
+    let candidates = automation::Entity::find()
+        .filter(condition)
+        .filter(automation::Column::DeletedAt.eq(at))
+        .all(db)
+        .await?;
+
+    let mut ids = Vec::new();
+    for auto in candidates {
+        if missing_automation_refs(db, &auto).await?.is_empty() {
+            ids.push(auto.id);
+        }
+    }
+    if !ids.is_empty() {
+        mark::<automation::Entity, _>(db, automation::Column::Id.is_in(ids), Some(at), None).await?;
+    }
+    Ok(())
+}
+
+// Referensi automation yang masih terhapus, mis. ["sensor", "pump"]
+pub async fn missing_automation_refs<C: ConnectionTrait>(
+    db: &C,
+    auto: &automation::Model,
+) -> Result<Vec<&'static str>, DbErr> {
+    let mut missing = Vec::new();
+    if land::Entity::find_active_by_id(auto.land_id).one(db).await?.is_none() {
+        missing.push("land");
+    }
+    if sensor::Entity::find_active_by_id(auto.sensor_id).one(db).await?.is_none() {
+        missing.push("sensor");
+    }
+    if pump::Entity::find_active_by_id(auto.pump_id).one(db).await?.is_none() {
+        missing.push("pump");
+    }
+    if valve::Entity::find_active_by_id(auto.valve_id).one(db).await?.is_none() {
+        missing.push("valve");
+    }
+    Ok(missing)
+}
+
+// ====================================================
+// PURGE: hapus permanen baris yang sudah lewat masa retensi
+// ====================================================
+
+#[derive(Debug, Default, Serialize)]
+pub struct PurgeReport {
+    pub automations: u64,
+    pub sensors: u64,
+    pub plants: u64,
+    pub pumps: u64,
+    pub valves: u64,
+    pub zones: u64,
+    pub lands: u64,
+    // Baris yang tetap di trash karena menghapusnya ikut menghapus data yang tidak bisa di-restore
+    pub kept: Vec<KeptRecord>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeptRecord {
+    pub entity_type: &'static str,
+    pub id: i32,
+    pub reason: String,
+}
+
+impl PurgeReport {
+    pub fn total(&self) -> u64 {
+        self.automations + self.sensors + self.plants + self.pumps + self.valves + self.zones + self.lands
+    }
+}
+
+// Hapus baris yang lewat cutoff kecuali `keep`, dan catat snapshot tiap baris ke audit log
+async fn purge<E: SoftDelete>(
+    db: &DatabaseConnection,
+    entity_type: &str,
+    cutoff: NaiveDateTime,
+    keep: &HashSet<i32>,
+    actor: Option<&AuthUser>,
+) -> Result<u64, DbErr> {
+    let rows = E::find().filter(E::DELETED_AT.lt(cutoff)).into_json().all(db).await?;
+    let rows: Vec<(i32, Value)> = rows
+        .into_iter()
+        .filter_map(|row| Some((row.get("id")?.as_i64()? as i32, row)))
+        .filter(|(id, _)| !keep.contains(id))
+        .collect();
+    if rows.is_empty() {
+        return Ok(0);
+    }
+
+    let res = E::delete_many()
+        .filter(E::ID.is_in(rows.iter().map(|(id, _)| *id)))
+        .exec(db)
+        .await?;
+    for (id, row) in rows {
+        audit::record(
+            db,
+            actor.map(|a| a.id),
+            actor.map(|a| a.ip.as_str()),
+            AuditAction::Purge,
+            entity_type,
+            id,
+            Some(row),
+            None,
+        )
+        .await;
+    }
+    Ok(res.rows_affected)
+}
+
+// Automation dihapus lebih dulu karena mereferensikan sensor, pompa & katup tanpa cascade.
+// Tanaman yang punya catatan panen (dan land yang masih punya tanaman) tidak ikut dihapus,
+// karena cascade-nya akan menghapus riwayat panen. Begitu juga sumber sensor virtual. `actor` None = job retensi.
+pub async fn purge_older_than(db: &DatabaseConnection, days: i64, actor: Option<&AuthUser>) -> Result<PurgeReport, DbErr> {
+    let cutoff = Local::now().naive_local() - Duration::days(days);
+    let none = HashSet::new();
+    let mut report = PurgeReport {
+        automations: purge::<automation::Entity>(db, "automation", cutoff, &none, actor).await?,
+        ..Default::default()
+    };
+
+    // Sumber sensor virtual yang masih aktif tetap disimpan, karena cascade-nya akan
+    // menghapus definisi sensor virtual tersebut
+    let expired_sensors: Vec<i32> = sensor::Entity::find()
+        .filter(sensor::Column::DeletedAt.lt(cutoff))
+        .select_only()
+        .column(sensor::Column::Id)
+        .into_tuple()
+        .all(db)
+        .await?;
+    let expired: HashSet<i32> = expired_sensors.iter().copied().collect();
+    let mut sources = HashSet::new();
+    for def in derived_sensor::Entity::find()
+        .filter(
+            Condition::any()
+                .add(derived_sensor::Column::TemperatureSensorId.is_in(expired_sensors.clone()))
+                .add(derived_sensor::Column::HumiditySensorId.is_in(expired_sensors)),
+        )
+        .all(db)
+        .await?
+    {
+        if expired.contains(&def.sensor_id) {
+            continue;
+        }
+        for id in [Some(def.temperature_sensor_id), def.humidity_sensor_id].into_iter().flatten() {
+            if expired.contains(&id) && sources.insert(id) {
+                report.kept.push(KeptRecord {
+                    entity_type: "sensor",
+                    id,
+                    reason: format!("Sensor is a source of virtual sensor {}", def.sensor_id),
+                });
+            }
+        }
+    }
+    report.sensors = purge::<sensor::Entity>(db, "sensor", cutoff, &sources, actor).await?;
+
+    let expired_plants = plant::Entity::find().filter(plant::Column::DeletedAt.lt(cutoff)).all(db).await?;
+    let mut keep_plants: HashSet<i32> = harvest::Entity::find()
+        .filter(harvest::Column::PlantId.is_in(expired_plants.iter().map(|p| p.id)))
+        .select_only()
+        .column(harvest::Column::PlantId)
+        .distinct()
+        .into_tuple()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect();
+    for id in &keep_plants {
+        report.kept.push(KeptRecord {
+            entity_type: "plant",
+            id: *id,
+            reason: "Plant has harvest records".to_string(),
+        });
+    }
+    // Definisi yang tersisa milik sensor virtual yang tidak ikut ter-purge di atas
+    let gdd_plants: Vec<(i32, i32)> = derived_sensor::Entity::find()
+        .filter(derived_sensor::Column::PlantId.is_in(expired_plants.iter().map(|p| p.id)))
+        .select_only()
+        .column(derived_sensor::Column::PlantId)
+        .column(derived_sensor::Column::SensorId)
+        .into_tuple()
+        .all(db)
+        .await?;
+    for (plant_id, sensor_id) in gdd_plants {
+        if keep_plants.insert(plant_id) {
+            report.kept.push(KeptRecord {
+                entity_type: "plant",
+                id: plant_id,
+                reason: format!("Plant is used by virtual sensor {sensor_id}"),
+            });
+        }
+    }
+    report.plants = purge::<plant::Entity>(db, "plant", cutoff, &keep_plants, actor).await?;
+    report.pumps = purge::<pump::Entity>(db, "pump", cutoff, &none, actor).await?;
+    report.valves = purge::<valve::Entity>(db, "valve", cutoff, &none, actor).await?;
+    report.zones = purge::<zone::Entity>(db, "zone", cutoff, &none, actor).await?;
+
+    let expired_lands = land::Entity::find().filter(land::Column::DeletedAt.lt(cutoff)).all(db).await?;
+    let lands_with_plants: HashSet<i32> = plant::Entity::find()
+        .filter(plant::Column::LandId.is_in(expired_lands.iter().map(|l| l.id)))
+        .select_only()
+        .column(plant::Column::LandId)
+        .distinct()
+        .into_tuple()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect();
+    for id in &lands_with_plants {
+        report.kept.push(KeptRecord {
+            entity_type: "land",
+            id: *id,
+            reason: "Land still has plants with harvest records".to_string(),
+        });
+    }
+    report.lands = purge::<land::Entity>(db, "land", cutoff, &lands_with_plants, actor).await?;
+    Ok(report)
+}
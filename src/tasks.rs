@@ -0,0 +1,118 @@
+use std::collections::HashSet;
+
+use chrono::{Local, NaiveDateTime};
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set};
+
+use crate::{
+    models::{
+        plant,
+        recommendation::{self, RecommendationType},
+        task::{self, TaskPriority, TaskStatus},
+    },
+    notifier,
+    soft_delete::SoftDelete,
+};
+
+// Kirim notifikasi untuk task yang lewat due date (ke assignee, atau pemilik land jika belum ada assignee).
+// Tiap task hanya diingatkan sekali sampai due date-nya diubah.
+pub async fn notify_overdue(db: &DatabaseConnection) -> Result<usize, DbErr> {
+    let now = Local::now().naive_local();
+    let overdue = task::Entity::find()
+        .filter(task::Column::Status.is_in(TaskStatus::OPEN))
+        .filter(task::Column::DueAt.lt(now))
+        .filter(task::Column::OverdueNotifiedAt.is_null())
+        .all(db)
+        .await?;
+
+    let count = overdue.len();
+    for t in overdue {
+        let description = format!(
+            "Task '{}' is overdue (due {})",
+            t.title,
+            t.due_at.map(|d| d.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default()
+        );
+        match t.assignee_id {
+            Some(user_id) => {
+                notifier::notify_user(db, user_id, description).await?;
+            }
+            None => {
+                notifier::notify_land_owner(db, t.land_id, description).await?;
+            }
+        }
+
+        let mut active: task::ActiveModel = t.into();
+        active.overdue_notified_at = Set(Some(now));
+        active.update(db).await?;
+    }
+    Ok(count)
+}
+
+fn priority_for(rec_type: &RecommendationType) -> TaskPriority {
+    match rec_type {
+        RecommendationType::PestControl => TaskPriority::High,
+        RecommendationType::Watering | RecommendationType::Fertilization => TaskPriority::Medium,
+    }
+}
+
+// Buat task dari rekomendasi benih untuk setiap tanaman aktif di land.
+// Rekomendasi yang masih punya task terbuka untuk tanaman yang sama dilewati.
+pub async fn from_recommendations(
+    db: &DatabaseConnection,
+    land_id: i32,
+    created_by: i32,
+    assignee_id: Option<i32>,
+    due_at: Option<NaiveDateTime>,
+) -> Result<Vec<task::Model>, DbErr> {
+    let plants = plant::Entity::find_active()
+        .filter(plant::Column::LandId.eq(land_id))
+        .filter(plant::Column::ClosedAt.is_null())
+        .all(db)
+        .await?;
+    if plants.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let recommendations = recommendation::Entity::find()
+        .filter(recommendation::Column::SeedId.is_in(plants.iter().map(|p| p.seed_id)))
+        .all(db)
+        .await?;
+
+    let existing: HashSet<(i32, Option<i32>)> = task::Entity::find()
+        .filter(task::Column::LandId.eq(land_id))
+        .filter(task::Column::RecommendationId.is_not_null())
+        .filter(task::Column::Status.is_in(TaskStatus::OPEN))
+        .all(db)
+        .await?
+        .into_iter()
+        .filter_map(|t| Some((t.recommendation_id?, t.plant_id)))
+        .collect();
+
+    let now = Local::now().naive_local();
+    let mut created = Vec::new();
+    for p in &plants {
+        for rec in recommendations.iter().filter(|r| r.seed_id == p.seed_id) {
+            if existing.contains(&(rec.id, Some(p.id))) {
+                continue;
+            }
+            let t = task::ActiveModel {
+                land_id: Set(land_id),
+                title: Set(format!("{} - {}", rec.name, p.name)),
+                description: Set(Some(rec.description.clone())),
+                priority: Set(priority_for(&rec.rec_type)),
+                status: Set(TaskStatus::Open),
+                due_at: Set(due_at),
+                assignee_id: Set(assignee_id),
+                created_by: Set(Some(created_by)),
+                plant_id: Set(Some(p.id)),
+                recommendation_id: Set(Some(rec.id)),
+                created_at: Set(now),
+                updated_at: Set(now),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+            created.push(t);
+        }
+    }
+    Ok(created)
+}
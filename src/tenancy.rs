@@ -0,0 +1,151 @@
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect, Select};
+
+use crate::{
+    error::ApiError,
+    extractors::AuthUser,
+    models::{land, organization_member::{self, OrgRole}},
+    permissions,
+    soft_delete::SoftDelete,
+};
+
+// Tingkat akses yang dibutuhkan terhadap data milik sebuah organisasi
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    Manage,
+}
+
+impl OrgRole {
+    pub fn grants(self, access: Access) -> bool {
+        match access {
+            Access::Read => true,
+            Access::Write => self != OrgRole::Viewer,
+            Access::Manage => matches!(self, OrgRole::Owner | OrgRole::Manager),
+        }
+    }
+}
+
+// Bukan anggota = 404 (tidak membocorkan keberadaan data organisasi lain),
+// anggota dengan role kurang = 403.
+pub fn require_org(auth: &AuthUser, organization_id: i32, access: Access, what: &str) -> Result<(), ApiError> {
+    if auth.is_super_admin() {
+        return Ok(());
+    }
+    match auth.org_role(organization_id) {
+        None => Err(ApiError::not_found(what)),
+        Some(role) if role.grants(access) => Ok(()),
+        Some(_) => Err(ApiError::forbidden("Your organization role does not allow this action")),
+    }
+}
+
+// Ambil land dan pastikan caller punya akses ke organisasi pemiliknya
+pub async fn land_access(
+    db: &DatabaseConnection,
+    auth: &AuthUser,
+    land_id: i32,
+    access: Access,
+) -> Result<land::Model, ApiError> {
+    let l = land::Entity::find_active_by_id(land_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Land"))?;
+    require_org(auth, l.organization_id, access, "Land")?;
+    Ok(l)
+}
+
+// Seperti `land_access`, tapi land yang sudah dihapus ikut dicari (untuk trash & restore)
+pub async fn land_access_with_deleted(
+    db: &DatabaseConnection,
+    auth: &AuthUser,
+    land_id: i32,
+    access: Access,
+) -> Result<land::Model, ApiError> {
+    let l = land::Entity::find_by_id(land_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Land"))?;
+    require_org(auth, l.organization_id, access, "Land")?;
+    Ok(l)
+}
+
+// Restore data di dalam land: land-nya harus bisa ditulis caller dan tidak sedang dihapus
+pub async fn restorable_land(db: &DatabaseConnection, auth: &AuthUser, land_id: i32) -> Result<land::Model, ApiError> {
+    let l = land_access_with_deleted(db, auth, land_id, Access::Write).await?;
+    if l.deleted_at.is_some() {
+        return Err(ApiError::conflict("Restore the land first").with_code("parent_deleted"));
+    }
+    Ok(l)
+}
+
+// Batasi query land ke organisasi caller (super-admin tidak dibatasi)
+pub fn scope_lands(select: Select<land::Entity>, auth: &AuthUser) -> Select<land::Entity> {
+    if auth.is_super_admin() {
+        select
+    } else {
+        let orgs: Vec<i32> = auth.memberships.iter().map(|(org, _)| *org).collect();
+        select.filter(land::Column::OrganizationId.is_in(orgs))
+    }
+}
+
+// Id land yang boleh dilihat caller, None = semua (super-admin)
+pub async fn visible_land_ids(db: &DatabaseConnection, auth: &AuthUser) -> Result<Option<Vec<i32>>, ApiError> {
+    if auth.is_super_admin() {
+        return Ok(None);
+    }
+    let ids = scope_lands(land::Entity::find_active(), auth)
+        .select_only()
+        .column(land::Column::Id)
+        .into_tuple()
+        .all(db)
+        .await?;
+    Ok(Some(ids))
+}
+
+// Id user anggota organisasi yang dikelola caller, None = semua (super-admin)
+pub async fn managed_user_ids(db: &DatabaseConnection, auth: &AuthUser) -> Result<Option<Vec<i32>>, ApiError> {
+    if auth.is_super_admin() {
+        return Ok(None);
+    }
+    let managed: Vec<i32> = auth
+        .memberships
+        .iter()
+        .filter(|(_, role)| role.grants(Access::Manage))
+        .map(|(org, _)| *org)
+        .collect();
+    let ids = organization_member::Entity::find()
+        .filter(organization_member::Column::OrganizationId.is_in(managed))
+        .select_only()
+        .column(organization_member::Column::UserId)
+        .distinct()
+        .into_tuple()
+        .all(db)
+        .await?;
+    Ok(Some(ids))
+}
+
+// Mengelola user lain (lihat detail, ubah, suspend, cabut session) butuh user:manage
+// dan user tersebut harus anggota organisasi yang dikelola caller.
+pub async fn require_user_admin(db: &DatabaseConnection, auth: &AuthUser, user_id: i32) -> Result<(), ApiError> {
+    if auth.id == user_id {
+        return Ok(());
+    }
+    auth.require(permissions::USER_MANAGE)?;
+    if auth.is_super_admin() {
+        return Ok(());
+    }
+
+    let managed: Vec<i32> = auth
+        .memberships
+        .iter()
+        .filter(|(_, role)| role.grants(Access::Manage))
+        .map(|(org, _)| *org)
+        .collect();
+    let shared = organization_member::Entity::find()
+        .filter(organization_member::Column::UserId.eq(user_id))
+        .filter(organization_member::Column::OrganizationId.is_in(managed))
+        .one(db)
+        .await?;
+
+    shared.map(|_| ()).ok_or_else(|| ApiError::not_found("User"))
+}
@@ -0,0 +1,141 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration, Local, Utc};
+use hmac::{Hmac, Mac};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{
+    config::SecurityConfig,
+    models::refresh_token,
+    security::{random_token, sha256_hex},
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Serialize, Deserialize)]
+struct Claims {
+    sub: i32,
+    exp: i64,
+}
+
+#[derive(Serialize)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub token_type: &'static str,
+    pub expires_in: i64,
+    pub refresh_token: String,
+}
+
+fn signature(secret: &[u8], payload: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+// Access token berformat "<payload base64>.<signature base64>", payload berisi user id & waktu kedaluwarsa
+pub fn sign_access_token(secret: &[u8], user_id: i32, ttl_seconds: i64) -> String {
+    let claims = Claims { sub: user_id, exp: Utc::now().timestamp() + ttl_seconds };
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap_or_default());
+    let sig = URL_SAFE_NO_PAD.encode(signature(secret, &payload).finalize().into_bytes());
+    format!("{}.{}", payload, sig)
+}
+
+// Kembalikan user id jika tanda tangan valid dan token belum kedaluwarsa
+pub fn verify_access_token(secret: &[u8], token: &str) -> Option<i32> {
+    let (payload, sig) = token.split_once('.')?;
+    let sig = URL_SAFE_NO_PAD.decode(sig).ok()?;
+    signature(secret, payload).verify_slice(&sig).ok()?;
+
+    let claims: Claims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    (claims.exp > Utc::now().timestamp()).then_some(claims.sub)
+}
+
+async fn issue_refresh_token(db: &DatabaseConnection, user_id: i32, ttl_days: i64) -> Result<String, DbErr> {
+    let token = random_token(64);
+    let now = Local::now().naive_local();
+
+    refresh_token::ActiveModel {
+        token_hash: Set(sha256_hex(&token)),
+        user_id: Set(user_id),
+        created_at: Set(now),
+        expires_at: Set(now + Duration::days(ttl_days)),
+        revoked_at: Set(None),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(token)
+}
+
+pub async fn issue_token_pair(
+    db: &DatabaseConnection,
+    security: &SecurityConfig,
+    user_id: i32,
+) -> Result<TokenPair, DbErr> {
+    Ok(TokenPair {
+        access_token: sign_access_token(&security.token_secret, user_id, security.access_token_ttl_seconds),
+        token_type: "Bearer",
+        expires_in: security.access_token_ttl_seconds,
+        refresh_token: issue_refresh_token(db, user_id, security.refresh_token_ttl_days).await?,
+    })
+}
+
+// Tukar refresh token dengan pasangan token baru; token lama langsung dicabut (rotasi).
+// None jika token tidak dikenal, kedaluwarsa, atau sudah pernah dipakai.
+pub async fn rotate_refresh_token(
+    db: &DatabaseConnection,
+    security: &SecurityConfig,
+    token: &str,
+) -> Result<Option<TokenPair>, DbErr> {
+    let Some(row) = refresh_token::Entity::find()
+        .filter(refresh_token::Column::TokenHash.eq(sha256_hex(token)))
+        .one(db)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    // Token yang sudah dirotasi dipakai lagi: kemungkinan bocor, cabut semua token user tersebut
+    if row.revoked_at.is_some() {
+        revoke_user_refresh_tokens(db, row.user_id).await?;
+        return Ok(None);
+    }
+
+    let now = Local::now().naive_local();
+    if row.expires_at <= now {
+        return Ok(None);
+    }
+
+    let user_id = row.user_id;
+    let mut active: refresh_token::ActiveModel = row.into();
+    active.revoked_at = Set(Some(now));
+    active.update(db).await?;
+
+    issue_token_pair(db, security, user_id).await.map(Some)
+}
+
+// Cabut satu refresh token, false jika token tidak ditemukan
+pub async fn revoke_refresh_token(db: &DatabaseConnection, token: &str) -> Result<bool, DbErr> {
+    let res = refresh_token::Entity::update_many()
+        .col_expr(refresh_token::Column::RevokedAt, Local::now().naive_local().into())
+        .filter(refresh_token::Column::TokenHash.eq(sha256_hex(token)))
+        .filter(refresh_token::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected > 0)
+}
+
+// Cabut semua refresh token milik user (ganti password/role, "logout di semua perangkat")
+pub async fn revoke_user_refresh_tokens(db: &DatabaseConnection, user_id: i32) -> Result<u64, DbErr> {
+    let res = refresh_token::Entity::update_many()
+        .col_expr(refresh_token::Column::RevokedAt, Local::now().naive_local().into())
+        .filter(refresh_token::Column::UserId.eq(user_id))
+        .filter(refresh_token::Column::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected)
+}
@@ -0,0 +1,215 @@
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use serde::Serialize;
+
+use crate::{
+    error::ApiError,
+    geo,
+    models::{
+        automation::{AutomationType, WeatherMode},
+        organization_member, pest_control, plant, pump, seed, sensor, valve,
+    },
+    soft_delete::SoftDelete,
+    weather,
+};
+
+// Bahasa yang didukung untuk notifikasi & pesan ke user
+pub const SUPPORTED_LANGUAGES: &[&str] = &["id", "en"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+// Kumpulkan semua field yang tidak valid sebelum menyentuh database
+pub struct Validator<'a> {
+    db: &'a DatabaseConnection,
+    errors: Vec<FieldError>,
+}
+
+impl<'a> Validator<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db, errors: Vec::new() }
+    }
+
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    pub async fn seed_exists(&mut self, field: &str, seed_id: i32) -> Result<(), DbErr> {
+        if seed::Entity::find_by_id(seed_id).one(self.db).await?.is_none() {
+            self.add(field, format!("Seed {} does not exist", seed_id));
+        }
+        Ok(())
+    }
+
+    pub async fn member_of_org(&mut self, field: &str, user_id: i32, organization_id: i32) -> Result<(), DbErr> {
+        let member = organization_member::Entity::find()
+            .filter(organization_member::Column::UserId.eq(user_id))
+            .filter(organization_member::Column::OrganizationId.eq(organization_id))
+            .one(self.db)
+            .await?;
+        if member.is_none() {
+            self.add(field, format!("User {} is not a member of organization {}", user_id, organization_id));
+        }
+        Ok(())
+    }
+
+    pub async fn sensor_on_land(&mut self, field: &str, sensor_id: i32, land_id: i32) -> Result<(), DbErr> {
+        let found = sensor::Entity::find_active_by_id(sensor_id).one(self.db).await?.map(|s| s.land_id);
+        self.check_same_land(field, "Sensor", sensor_id, found, land_id);
+        Ok(())
+    }
+
+    pub async fn pump_on_land(&mut self, field: &str, pump_id: i32, land_id: i32) -> Result<(), DbErr> {
+        let found = pump::Entity::find_active_by_id(pump_id).one(self.db).await?.map(|p| p.land_id);
+        self.check_same_land(field, "Pump", pump_id, found, land_id);
+        Ok(())
+    }
+
+    pub async fn valve_on_land(&mut self, field: &str, valve_id: i32, land_id: i32) -> Result<(), DbErr> {
+        let found = valve::Entity::find_active_by_id(valve_id).one(self.db).await?.map(|v| v.land_id);
+        self.check_same_land(field, "Valve", valve_id, found, land_id);
+        Ok(())
+    }
+
+    pub async fn plant_on_land(&mut self, field: &str, plant_id: i32, land_id: i32) -> Result<(), DbErr> {
+        let found = plant::Entity::find_active_by_id(plant_id).one(self.db).await?.map(|p| p.land_id);
+        self.check_same_land(field, "Plant", plant_id, found, land_id);
+        Ok(())
+    }
+
+    pub async fn pest_control_on_land(&mut self, field: &str, pest_control_id: i32, land_id: i32) -> Result<(), DbErr> {
+        let found = pest_control::Entity::find_by_id(pest_control_id).one(self.db).await?.map(|p| p.land_id);
+        self.check_same_land(field, "Pest control", pest_control_id, found, land_id);
+        Ok(())
+    }
+
+    // Field `{prefix}latitude` & `{prefix}longitude`
+    pub fn coordinates(&mut self, prefix: &str, latitude: Option<f64>, longitude: Option<f64>) {
+        if let Some(e) = geo::point_error(latitude, longitude) {
+            let field = if latitude.is_none() { "latitude" } else { "longitude" };
+            self.add(&format!("{}{}", prefix, field), e);
+        }
+    }
+
+    // Aturan hujan hanya untuk automation penyiraman dan butuh threshold
+    pub fn weather_rule(
+        &mut self,
+        prefix: &str,
+        automation_type: &AutomationType,
+        mode: WeatherMode,
+        threshold: Option<f64>,
+        lookback_hours: i32,
+        forecast_hours: i32,
+    ) {
+        if mode != WeatherMode::Ignore {
+            if *automation_type != AutomationType::Watering {
+                self.add(&format!("{}weather_mode", prefix), "Only Watering automations can react to rain");
+            }
+            if threshold.is_none() {
+                self.add(&format!("{}rain_threshold_mm", prefix), "Required when weather_mode is not Ignore");
+            }
+        }
+        if threshold.is_some_and(|t| !t.is_finite() || t <= 0.0) {
+            self.add(&format!("{}rain_threshold_mm", prefix), "Must be greater than 0");
+        }
+        for (field, hours) in [("rain_lookback_hours", lookback_hours), ("rain_forecast_hours", forecast_hours)] {
+            if !(0..=weather::MAX_WINDOW_HOURS).contains(&hours) {
+                self.add(&format!("{}{}", prefix, field), format!("Must be between 0 and {}", weather::MAX_WINDOW_HOURS));
+            }
+        }
+    }
+
+    // Batas & kapasitas perangkat: kosong berarti tanpa batas, jika diisi harus lebih dari 0
+    pub fn positive(&mut self, field: &str, value: Option<f64>) {
+        if value.is_some_and(|x| !x.is_finite() || x <= 0.0) {
+            self.add(field, "Must be greater than 0");
+        }
+    }
+
+    // Poligon valid dan luasnya cocok dengan luas yang dicatat
+    pub fn polygon(&mut self, field: &str, ring: &[[f64; 2]], recorded: f64) {
+        if let Some(e) = geo::ring_error(ring).or_else(|| geo::area_error(ring, recorded)) {
+            self.add(field, e);
+        }
+    }
+
+    pub fn max_length(&mut self, field: &str, value: &str, max: usize) {
+        if value.trim().is_empty() {
+            self.add(field, "Must not be empty");
+        } else if value.chars().count() > max {
+            self.add(field, format!("Must be at most {} characters", max));
+        }
+    }
+
+    // Cek sederhana: satu '@', bagian lokal tidak kosong, domain punya titik
+    pub fn email(&mut self, field: &str, value: &str) {
+        let valid = match value.split_once('@') {
+            Some((local, domain)) => {
+                !local.is_empty()
+                    && !domain.contains('@')
+                    && domain.contains('.')
+                    && !domain.starts_with('.')
+                    && !domain.ends_with('.')
+                    && !value.chars().any(char::is_whitespace)
+            }
+            None => false,
+        };
+        if !valid || value.len() > 254 {
+            self.add(field, "Must be a valid email address");
+        }
+    }
+
+    // Nomor telepon: boleh diawali '+', 8-15 digit, spasi dan '-' diabaikan
+    pub fn phone(&mut self, field: &str, value: &str) {
+        let rest = value.strip_prefix('+').unwrap_or(value);
+        let digits = rest.chars().filter(|c| c.is_ascii_digit()).count();
+        let valid = rest.chars().all(|c| c.is_ascii_digit() || c == ' ' || c == '-');
+        if !valid || !(8..=15).contains(&digits) {
+            self.add(field, "Must be a phone number with 8 to 15 digits");
+        }
+    }
+
+    pub fn language(&mut self, field: &str, value: &str) {
+        if !SUPPORTED_LANGUAGES.contains(&value) {
+            self.add(field, format!("Must be one of: {}", SUPPORTED_LANGUAGES.join(", ")));
+        }
+    }
+
+    // "UTC" atau nama zona IANA, misalnya "Asia/Jakarta"
+    pub fn timezone(&mut self, field: &str, value: &str) {
+        let valid = value == "UTC"
+            || value.split_once('/').is_some_and(|(area, city)| {
+                !area.is_empty()
+                    && !city.is_empty()
+                    && value.chars().all(|c| c.is_ascii_alphanumeric() || "/_-+".contains(c))
+            });
+        if !valid {
+            self.add(field, "Must be UTC or an IANA time zone such as Asia/Jakarta");
+        }
+    }
+
+    // Tidak ada dan milik land lain dilaporkan sama, agar id milik tenant lain tidak bisa dipetakan
+    fn check_same_land(&mut self, field: &str, label: &str, id: i32, found_land: Option<i32>, land_id: i32) {
+        if found_land != Some(land_id) {
+            self.add(field, format!("{} {} does not exist on this land", label, id));
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    // Err(422) dengan daftar field yang tidak valid jika ada
+    pub fn finish(self) -> Result<(), ApiError> {
+        if self.is_valid() {
+            Ok(())
+        } else {
+            Err(ApiError::validation(self.errors))
+        }
+    }
+}
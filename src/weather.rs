@@ -0,0 +1,305 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, Local, NaiveDateTime, Timelike};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+    sea_query::OnConflict,
+};
+use serde::{Deserialize, Serialize};
+use shuttle_runtime::SecretStore;
+
+use crate::{
+    models::{
+        automation::{self, AutomationType, WeatherMode},
+        land,
+        weather::{self, WeatherKind},
+    },
+    soft_delete::SoftDelete,
+};
+
+// Jendela hujan default untuk automation baru
+pub const DEFAULT_LOOKBACK_HOURS: i32 = 24;
+pub const DEFAULT_FORECAST_HOURS: i32 = 12;
+pub const MAX_WINDOW_HOURS: i32 = 168;
+
+// Satu data cuaca per jam, dari provider maupun dari API
+#[derive(Debug, Clone, Deserialize)]
+pub struct WeatherReading {
+    pub kind: WeatherKind,
+    pub valid_at: NaiveDateTime,
+    pub rainfall_mm: f64,
+    #[serde(default)]
+    pub rain_probability: Option<f64>,
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub humidity: Option<f64>,
+}
+
+// Pesan kesalahan per field untuk data yang tidak masuk akal
+pub fn reading_errors(r: &WeatherReading) -> Vec<(&'static str, &'static str)> {
+    let mut errors = Vec::new();
+    if !r.rainfall_mm.is_finite() || r.rainfall_mm < 0.0 {
+        errors.push(("rainfall_mm", "Must be 0 or greater"));
+    }
+    if let Some(p) = r.rain_probability {
+        if r.kind == WeatherKind::Observation {
+            errors.push(("rain_probability", "Only allowed for forecasts"));
+        } else if !(0.0..=1.0).contains(&p) {
+            errors.push(("rain_probability", "Must be between 0 and 1"));
+        }
+    }
+    if r.humidity.is_some_and(|h| !(0.0..=100.0).contains(&h)) {
+        errors.push(("humidity", "Must be between 0 and 100"));
+    }
+    errors
+}
+
+// Sumber data cuaca. Provider lain (API layanan cuaca, stasiun lokal) cukup
+// mengimplementasikan trait ini lalu didaftarkan di `from_secrets`.
+#[async_trait]
+pub trait WeatherProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn fetch(&self, land: &land::Model) -> anyhow::Result<Vec<WeatherReading>>;
+}
+
+// Entri file fixture: waktu absolut (`valid_at`) atau relatif terhadap jam sekarang (`offset_hours`)
+#[derive(Deserialize)]
+struct FixtureEntry {
+    kind: WeatherKind,
+    valid_at: Option<NaiveDateTime>,
+    offset_hours: Option<i64>,
+    rainfall_mm: f64,
+    #[serde(default)]
+    rain_probability: Option<f64>,
+    #[serde(default)]
+    temperature: Option<f64>,
+    #[serde(default)]
+    humidity: Option<f64>,
+}
+
+// Untuk development & lokasi offline: baca `{dir}/land-{id}.json`, atau `{dir}/default.json`
+// jika land tidak punya file sendiri. Isinya array FixtureEntry.
+pub struct FileProvider {
+    dir: PathBuf,
+}
+
+#[async_trait]
+impl WeatherProvider for FileProvider {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn fetch(&self, land: &land::Model) -> anyhow::Result<Vec<WeatherReading>> {
+        let specific = self.dir.join(format!("land-{}.json", land.id));
+        let path = if specific.exists() { specific } else { self.dir.join("default.json") };
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let entries: Vec<FixtureEntry> = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        let now = slot(Local::now().naive_local());
+        entries
+            .into_iter()
+            .map(|e| {
+                let valid_at = match (e.valid_at, e.offset_hours) {
+                    (Some(at), _) => at,
+                    (None, Some(offset)) => now + Duration::hours(offset),
+                    (None, None) => anyhow::bail!("{}: entry needs valid_at or offset_hours", path.display()),
+                };
+                Ok(WeatherReading {
+                    kind: e.kind,
+                    valid_at,
+                    rainfall_mm: e.rainfall_mm,
+                    rain_probability: e.rain_probability,
+                    temperature: e.temperature,
+                    humidity: e.humidity,
+                })
+            })
+            .collect()
+    }
+}
+
+// WEATHER_PROVIDER = "file" | kosong (tanpa provider, data hanya masuk lewat API)
+pub fn from_secrets(secrets: &SecretStore) -> Option<Arc<dyn WeatherProvider>> {
+    match secrets.get("WEATHER_PROVIDER").as_deref().map(str::trim) {
+        Some("file") => Some(Arc::new(FileProvider {
+            dir: secrets
+                .get("WEATHER_FIXTURE_DIR")
+                .unwrap_or_else(|| "weather".to_string())
+                .into(),
+        })),
+        _ => None,
+    }
+}
+
+// Data disimpan per jam
+pub fn slot(at: NaiveDateTime) -> NaiveDateTime {
+    at.with_minute(0).and_then(|t| t.with_second(0)).and_then(|t| t.with_nanosecond(0)).unwrap_or(at)
+}
+
+// Simpan data cuaca; data untuk land, jenis & jam yang sama menimpa yang lama
+pub async fn store<C: ConnectionTrait>(
+    db: &C,
+    land_id: i32,
+    source: &str,
+    readings: &[WeatherReading],
+) -> Result<usize, DbErr> {
+    let now = Local::now().naive_local();
+    for r in readings {
+        let row = weather::ActiveModel {
+            land_id: Set(land_id),
+            kind: Set(r.kind),
+            valid_at: Set(slot(r.valid_at)),
+            rainfall_mm: Set(r.rainfall_mm),
+            rain_probability: Set(r.rain_probability),
+            temperature: Set(r.temperature),
+            humidity: Set(r.humidity),
+            source: Set(source.to_string()),
+            received_at: Set(now),
+            ..Default::default()
+        };
+        weather::Entity::insert(row)
+            .on_conflict(
+                OnConflict::columns([weather::Column::LandId, weather::Column::Kind, weather::Column::ValidAt])
+                    .update_columns([
+                        weather::Column::RainfallMm,
+                        weather::Column::RainProbability,
+                        weather::Column::Temperature,
+                        weather::Column::Humidity,
+                        weather::Column::Source,
+                        weather::Column::ReceivedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(db)
+            .await?;
+    }
+    Ok(readings.len())
+}
+
+// Ambil data terbaru dari provider untuk satu land. Data yang tidak valid dibuang.
+pub async fn refresh(db: &DatabaseConnection, provider: &dyn WeatherProvider, l: &land::Model) -> anyhow::Result<usize> {
+    let readings: Vec<WeatherReading> = provider
+        .fetch(l)
+        .await?
+        .into_iter()
+        .filter(|r| reading_errors(r).is_empty())
+        .collect();
+    Ok(store(db, l.id, provider.name(), &readings).await?)
+}
+
+// Dipanggil job berkala untuk semua land aktif; kegagalan satu land tidak menghentikan yang lain
+pub async fn refresh_all(db: &DatabaseConnection, provider: &dyn WeatherProvider) -> Result<usize, DbErr> {
+    let lands = land::Entity::find_active().all(db).await?;
+    let mut total = 0;
+    for l in &lands {
+        match refresh(db, provider, l).await {
+            Ok(n) => total += n,
+            Err(e) => eprintln!("Weather: failed to refresh land #{}: {:?}", l.id, e),
+        }
+    }
+    Ok(total)
+}
+
+// Hapus data cuaca yang lebih tua dari masa retensi (forecast yang sudah lewat ikut terhapus)
+pub async fn purge_older_than(db: &DatabaseConnection, days: i64) -> Result<u64, DbErr> {
+    let cutoff = Local::now().naive_local() - Duration::days(days);
+    let res = weather::Entity::delete_many()
+        .filter(weather::Column::ValidAt.lt(cutoff))
+        .exec(db)
+        .await?;
+    Ok(res.rows_affected)
+}
+
+// ====================================================
+// HUJAN & KEPUTUSAN PENYIRAMAN
+// ====================================================
+
+// Hujan yang sudah turun (observasi `lookback_hours` terakhir, termasuk jam berjalan) ditambah
+// hujan yang diperkirakan (forecast jam-jam berikutnya, dikali peluangnya)
+#[derive(Debug, Serialize)]
+pub struct RainOutlook {
+    pub lookback_hours: i32,
+    pub forecast_hours: i32,
+    pub observed_mm: f64,
+    pub forecast_mm: f64,
+    pub total_mm: f64,
+}
+
+pub async fn outlook<C: ConnectionTrait>(
+    db: &C,
+    land_id: i32,
+    lookback_hours: i32,
+    forecast_hours: i32,
+    now: NaiveDateTime,
+) -> Result<RainOutlook, DbErr> {
+    let current = slot(now);
+    let observed_mm: f64 = weather::Entity::find()
+        .filter(weather::Column::LandId.eq(land_id))
+        .filter(weather::Column::Kind.eq(WeatherKind::Observation))
+        .filter(weather::Column::ValidAt.gt(current - Duration::hours(lookback_hours as i64)))
+        .filter(weather::Column::ValidAt.lte(current))
+        .all(db)
+        .await?
+        .iter()
+        .map(|w| w.rainfall_mm)
+        .sum();
+    let forecast_mm: f64 = weather::Entity::find()
+        .filter(weather::Column::LandId.eq(land_id))
+        .filter(weather::Column::Kind.eq(WeatherKind::Forecast))
+        .filter(weather::Column::ValidAt.gt(current))
+        .filter(weather::Column::ValidAt.lte(current + Duration::hours(forecast_hours as i64)))
+        .all(db)
+        .await?
+        .iter()
+        .map(|w| w.rainfall_mm * w.rain_probability.unwrap_or(1.0))
+        .sum();
+
+    Ok(RainOutlook {
+        lookback_hours,
+        forecast_hours,
+        observed_mm,
+        forecast_mm,
+        total_mm: observed_mm + forecast_mm,
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum Decision {
+    // Jalankan dengan volume ini; `note` terisi jika volumenya dikurangi
+    Run { volume: f64, note: Option<String> },
+    Skip { reason: String },
+}
+
+// Keputusan penyiraman berdasarkan hujan. Automation tanpa aturan cuaca selalu jalan penuh.
+pub async fn assess<C: ConnectionTrait>(db: &C, auto: &automation::Model, now: NaiveDateTime) -> Result<Decision, DbErr> {
+    let full = Decision::Run { volume: auto.dispense_amount, note: None };
+    let Some(threshold) = auto.rain_threshold_mm else {
+        return Ok(full);
+    };
+    if auto.weather_mode == WeatherMode::Ignore || auto.automation_type != AutomationType::Watering {
+        return Ok(full);
+    }
+
+    let rain = outlook(db, auto.land_id, auto.rain_lookback_hours, auto.rain_forecast_hours, now).await?;
+    if rain.total_mm >= threshold {
+        return Ok(Decision::Skip {
+            reason: format!(
+                "Rain {:.1} mm reaches the {} mm threshold ({:.1} mm in the last {}h, {:.1} mm expected in the next {}h)",
+                rain.total_mm, threshold, rain.observed_mm, rain.lookback_hours, rain.forecast_mm, rain.forecast_hours
+            ),
+        });
+    }
+    if auto.weather_mode == WeatherMode::Scale && rain.total_mm > 0.0 {
+        let factor = 1.0 - rain.total_mm / threshold;
+        return Ok(Decision::Run {
+            volume: auto.dispense_amount * factor,
+            note: Some(format!("Volume scaled to {:.0}% for {:.1} mm of rain", factor * 100.0, rain.total_mm)),
+        });
+    }
+    Ok(full)
+}
@@ -0,0 +1,153 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QuerySelect, SelectTwo};
+use serde::Serialize;
+
+use crate::models::{
+    automation,
+    automation_history::{self, AutomationEvent},
+    harvest::{self, YieldUnit},
+    land, plant,
+};
+
+// Musim tanam mengikuti iklim Indonesia: kemarau April–September, hujan Oktober–Maret.
+// Panen dikelompokkan menurut musim saat tanaman ditanam.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Season {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub label: String,
+}
+
+fn first_of(year: i32, month: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .unwrap_or_default()
+}
+
+pub fn season_of(t: NaiveDateTime) -> Season {
+    let year = t.year();
+    match t.month() {
+        4..=9 => Season {
+            start: first_of(year, 4),
+            end: first_of(year, 10),
+            label: format!("{}-dry", year),
+        },
+        10..=12 => Season {
+            start: first_of(year, 10),
+            end: first_of(year + 1, 4),
+            label: format!("{}/{}-wet", year, year + 1),
+        },
+        _ => Season {
+            start: first_of(year - 1, 10),
+            end: first_of(year, 4),
+            label: format!("{}/{}-wet", year - 1, year),
+        },
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnitYield {
+    pub unit: YieldUnit,
+    pub quantity: f64,
+    // Per satuan luas land (`land.size`)
+    pub per_area: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeasonYield {
+    pub land_id: i32,
+    pub location_name: String,
+    pub land_size: f64,
+    pub season: String,
+    pub season_start: NaiveDateTime,
+    pub season_end: NaiveDateTime,
+    pub plants: usize,
+    pub harvests: usize,
+    pub yields: Vec<UnitYield>,
+    // Total volume automation yang berjalan di land selama musim tersebut
+    pub water_used: f64,
+    pub water_per_area: Option<f64>,
+}
+
+// Panen beserta tanamannya (tanaman yang di-soft delete tidak ikut)
+pub fn harvests_with_plants() -> SelectTwo<harvest::Entity, plant::Entity> {
+    harvest::Entity::find()
+        .find_also_related(plant::Entity)
+        .filter(plant::Column::DeletedAt.is_null())
+}
+
+async fn water_used(db: &DatabaseConnection, land_id: i32, season: &Season) -> Result<f64, DbErr> {
+    let total: Option<Option<f64>> = automation_history::Entity::find()
+        .select_only()
+        .column_as(automation_history::Column::Volume.sum(), "total")
+        .inner_join(automation::Entity)
+        .filter(automation::Column::LandId.eq(land_id))
+        .filter(automation_history::Column::Event.eq(AutomationEvent::Triggered))
+        .filter(automation_history::Column::TriggeredAt.gte(season.start))
+        .filter(automation_history::Column::TriggeredAt.lt(season.end))
+        .into_tuple()
+        .one(db)
+        .await?;
+    Ok(total.flatten().unwrap_or(0.0))
+}
+
+fn per_area(value: f64, size: f64) -> Option<f64> {
+    (size > 0.0).then(|| value / size)
+}
+
+// Ringkas panen per land & musim tanam
+pub async fn report(
+    db: &DatabaseConnection,
+    rows: Vec<(harvest::Model, Option<plant::Model>)>,
+) -> Result<Vec<SeasonYield>, DbErr> {
+    let rows: Vec<(harvest::Model, plant::Model)> = rows.into_iter().filter_map(|(h, p)| Some((h, p?))).collect();
+    let land_ids: HashSet<i32> = rows.iter().map(|(_, p)| p.land_id).collect();
+    let lands: HashMap<i32, land::Model> = land::Entity::find()
+        .filter(land::Column::Id.is_in(land_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|l| (l.id, l))
+        .collect();
+
+    let mut groups: BTreeMap<(i32, Season), Vec<(harvest::Model, plant::Model)>> = BTreeMap::new();
+    for (h, p) in rows {
+        groups.entry((p.land_id, season_of(p.planted_at))).or_default().push((h, p));
+    }
+
+    let mut report = Vec::new();
+    for ((land_id, season), rows) in groups {
+        let Some(l) = lands.get(&land_id) else { continue };
+
+        let mut totals: BTreeMap<YieldUnit, f64> = BTreeMap::new();
+        for (h, _) in &rows {
+            *totals.entry(h.unit).or_default() += h.quantity;
+        }
+        let plants: HashSet<i32> = rows.iter().map(|(_, p)| p.id).collect();
+        let water = water_used(db, land_id, &season).await?;
+
+        report.push(SeasonYield {
+            land_id,
+            location_name: l.location_name.clone(),
+            land_size: l.size,
+            season: season.label,
+            season_start: season.start,
+            season_end: season.end,
+            plants: plants.len(),
+            harvests: rows.len(),
+            yields: totals
+                .into_iter()
+                .map(|(unit, quantity)| UnitYield {
+                    unit,
+                    quantity,
+                    per_area: per_area(quantity, l.size),
+                })
+                .collect(),
+            water_used: water,
+            water_per_area: per_area(water, l.size),
+        });
+    }
+    Ok(report)
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, Set};
+
+use crate::{
+    models::{automation, sensor, valve, zone},
+    soft_delete::SoftDelete,
+};
+
+// Total luas zona aktif di land, tanpa zona `except` (untuk update)
+pub async fn allocated_area<C: ConnectionTrait>(db: &C, land_id: i32, except: Option<i32>) -> Result<f64, DbErr> {
+    let zones = zone::Entity::find_active()
+        .filter(zone::Column::LandId.eq(land_id))
+        .all(db)
+        .await?;
+    Ok(zones.iter().filter(|z| Some(z.id) != except).map(|z| z.area).sum())
+}
+
+pub async fn valve_zone<C: ConnectionTrait>(db: &C, valve_id: i32) -> Result<Option<i32>, DbErr> {
+    Ok(valve::Entity::find_by_id(valve_id).one(db).await?.and_then(|v| v.zone_id))
+}
+
+// Automation di zona A tidak boleh dipicu sensor zona B (sensor tanpa zona berlaku untuk semua zona)
+pub fn sensor_fits(sensor_zone: Option<i32>, automation_zone: Option<i32>) -> bool {
+    match (sensor_zone, automation_zone) {
+        (Some(s), Some(a)) => s == a,
+        _ => true,
+    }
+}
+
+// Samakan zona automation dengan zona katupnya, lalu kembalikan automation yang jadi memakai
+// sensor dari zona lain. Dipanggil di dalam transaksi setelah penempatan perangkat berubah.
+pub async fn sync_automations<C: ConnectionTrait>(db: &C, land_id: i32) -> Result<Vec<String>, DbErr> {
+    let automations = automation::Entity::find_active()
+        .filter(automation::Column::LandId.eq(land_id))
+        .all(db)
+        .await?;
+    let valves: HashMap<i32, Option<i32>> = valve::Entity::find()
+        .filter(valve::Column::LandId.eq(land_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|v| (v.id, v.zone_id))
+        .collect();
+    let sensors: HashMap<i32, sensor::Model> = sensor::Entity::find()
+        .filter(sensor::Column::LandId.eq(land_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|s| (s.id, s))
+        .collect();
+
+    let mut conflicts = Vec::new();
+    for auto in automations {
+        let zone_id = valves.get(&auto.valve_id).copied().flatten();
+        if let Some(s) = sensors.get(&auto.sensor_id)
+            && !sensor_fits(s.zone_id, zone_id)
+        {
+            conflicts.push(format!(
+                "Automation '{}' would be triggered by sensor '{}' from another zone",
+                auto.name, s.name
+            ));
+        }
+        if auto.zone_id != zone_id {
+            let mut active: automation::ActiveModel = auto.into();
+            active.zone_id = Set(zone_id);
+            active.update(db).await?;
+        }
+    }
+    Ok(conflicts)
+}